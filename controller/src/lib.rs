@@ -1,4 +1,4 @@
-#![feature(once_cell)]
+#![allow(stable_features)]
 
 pub mod model;
 pub mod motion;
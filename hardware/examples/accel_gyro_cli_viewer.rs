@@ -1,6 +1,6 @@
-use hardware::i2c::connect;
 use hardware::i2c::mpu6050::{ADDRESS_LOW, MPU6050};
 use hardware::i2c::register_io::I2cWithAddr;
+use hardware::i2c::BusRegistry;
 use linux_embedded_hal::Delay;
 use std::io::{stdout, Result};
 
@@ -12,8 +12,10 @@ fn main() {
 }
 
 fn run_loop() -> Result<()> {
-    let dev = connect(1)?;
-    let mut mpu = MPU6050::new(I2cWithAddr::new(dev, ADDRESS_LOW)).unwrap();
+    let mut buses = BusRegistry::new();
+    buses.register_linux("main", 1);
+    let dev = buses.get("main")?;
+    let mut mpu = MPU6050::new_verified(I2cWithAddr::new(dev, ADDRESS_LOW)).unwrap();
     mpu.normal_setup(&mut Delay).unwrap();
 
     ctrlc::set_handler(|| {
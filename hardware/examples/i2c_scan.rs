@@ -0,0 +1,15 @@
+use hardware::i2c::scanner::scan;
+use hardware::i2c::BusRegistry;
+
+fn main() {
+    let mut buses = BusRegistry::new();
+    buses.register_linux("main", 1);
+    let dev = buses.get("main").unwrap();
+    let found = scan(&mut *dev.lock());
+    if found.is_empty() {
+        println!("No device responded.");
+    }
+    for f in found {
+        println!("0x{:02x}: {}", f.address.0, f.device);
+    }
+}
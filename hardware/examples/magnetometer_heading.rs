@@ -0,0 +1,38 @@
+use hardware::i2c::mpu6050::aux_i2c::{MasterClock, Slave, SlaveRead};
+use hardware::i2c::mpu6050::{ADDRESS_LOW, MPU6050};
+use hardware::i2c::register_io::I2cWithAddr;
+use hardware::i2c::{BusRegistry, I2cAddr};
+use linux_embedded_hal::Delay;
+use std::time::Duration;
+
+/// HMC5883L connected to the auxiliary I2C bus of the MPU6050.
+const HMC5883L: I2cAddr = I2cAddr(0x1e);
+
+fn main() {
+    let mut buses = BusRegistry::new();
+    buses.register_linux("main", 1);
+    let dev = buses.get("main").unwrap();
+    let mut mpu = MPU6050::new_verified(I2cWithAddr::new(dev, ADDRESS_LOW)).unwrap();
+    mpu.normal_setup(&mut Delay).unwrap();
+    mpu.enable_i2c_master(MasterClock::Khz400).unwrap();
+
+    let timeout = Duration::from_millis(100);
+    // 8 samples averaged at 15Hz, gain 1090 LSB/Gauss, continuous measurement.
+    for (reg, v) in [(0x00, 0x70), (0x01, 0x20), (0x02, 0x00)] {
+        mpu.write_slave_register(HMC5883L, reg, v, timeout).unwrap();
+    }
+    // DATA X, Z and Y in big endian.
+    mpu.set_slave_read(Slave::Slv0, SlaveRead::new(HMC5883L, 0x03, 6))
+        .unwrap();
+
+    loop {
+        let data = mpu.read_slave_data(Slave::Slv0).unwrap();
+        let x = i16::from_be_bytes([data[0], data[1]]) as f64;
+        let z = i16::from_be_bytes([data[2], data[3]]) as f64;
+        let y = i16::from_be_bytes([data[4], data[5]]) as f64;
+        let heading = y.atan2(x).to_degrees().rem_euclid(360.0);
+        println!("x: {:6} y: {:6} z: {:6} heading: {:5.1}°", x, y, z, heading);
+
+        std::thread::sleep(Duration::from_millis(500));
+    }
+}
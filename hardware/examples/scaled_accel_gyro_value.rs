@@ -1,10 +1,12 @@
-use hardware::i2c::connect;
+use hardware::i2c::mpu6050::calibration::{Calibration, Gravity};
 use hardware::i2c::mpu6050::raw_data::{AccelFullScale, GyroFullScale};
 use hardware::i2c::mpu6050::{ADDRESS_LOW, MPU6050};
 use hardware::i2c::register_io::I2cWithAddr;
+use hardware::i2c::BusRegistry;
 use linux_embedded_hal::Delay;
 use num_traits::FromPrimitive;
 use std::io::{stdout, Result as IOResult};
+use std::path::{Path, PathBuf};
 use std::result::Result;
 
 use crossterm::*;
@@ -18,6 +20,10 @@ struct Args {
 
     #[structopt(long, default_value = "0", parse(try_from_str = parse_gyro))]
     gyro: GyroFullScale,
+
+    /// Loads the calibration from the file, or calibrates with Z axis up and saves it.
+    #[structopt(long, parse(from_os_str))]
+    calibration: Option<PathBuf>,
 }
 
 fn parse_accel(src: &str) -> Result<AccelFullScale, String> {
@@ -36,16 +42,33 @@ fn parse_gyro(src: &str) -> Result<GyroFullScale, String> {
 
 fn main() {
     let args = Args::from_args();
-    run_loop(args.accel, args.gyro).unwrap();
+    run_loop(args.accel, args.gyro, args.calibration.as_deref()).unwrap();
 }
 
-fn run_loop(accel_fs: AccelFullScale, gyro_fs: GyroFullScale) -> IOResult<()> {
-    let dev = connect(1)?;
-    let mut mpu = MPU6050::new(I2cWithAddr::new(dev, ADDRESS_LOW)).unwrap();
+fn run_loop(
+    accel_fs: AccelFullScale,
+    gyro_fs: GyroFullScale,
+    calibration: Option<&Path>,
+) -> IOResult<()> {
+    let mut buses = BusRegistry::new();
+    buses.register_linux("main", 1);
+    let dev = buses.get("main")?;
+    let mut mpu = MPU6050::new_verified(I2cWithAddr::new(dev, ADDRESS_LOW)).unwrap();
     mpu.normal_setup(&mut Delay).unwrap();
     mpu.set_gyro_full_scale(gyro_fs).unwrap();
     mpu.set_accel_full_scale(accel_fs).unwrap();
 
+    let cal = match calibration {
+        Some(path) if path.exists() => Calibration::load(path)?,
+        Some(path) => {
+            println!("Calibrating. Keep the sensor still with Z axis up...");
+            let cal = mpu.calibrate(Gravity::ZUp, 200, 5, &mut Delay).unwrap();
+            cal.save(path)?;
+            cal
+        }
+        None => Calibration::default(),
+    };
+
     ctrlc::set_handler(|| {
         execute!(
             stdout(),
@@ -65,18 +88,19 @@ fn run_loop(accel_fs: AccelFullScale, gyro_fs: GyroFullScale) -> IOResult<()> {
     )?;
 
     loop {
-        let info = mpu.get_infos().unwrap();
-        let accel_info = info.accel.scale::<f64>(accel_fs);
-        let gyro_info = info.gyro.scale::<f64>(gyro_fs);
+        let info = cal.apply(&mpu.get_infos().unwrap(), accel_fs, gyro_fs);
+        let m = info.to_measurement::<f64>(accel_fs, gyro_fs);
+        let (accel, gyro) = (&m.accel, &m.gyro);
 
         execute!(
             stdout(),
             style::Print(format!("{:?}\n", info.accel)),
             style::Print(format!("{:?}\n", info.gyro)),
             cursor::MoveDown(1),
-            style::Print(format!("{:?}\n", accel_info)),
-            style::Print(format!("{:?}\n", gyro_info)),
-            cursor::MoveUp(5),
+            style::Print(format!("{} {} {}\n", accel.x(), accel.y(), accel.z())),
+            style::Print(format!("{} {} {}\n", gyro.x(), gyro.y(), gyro.z())),
+            style::Print(format!("{}\n", m.temp)),
+            cursor::MoveUp(6),
         )?;
 
         std::thread::sleep(std::time::Duration::from_millis(500));
@@ -1,11 +1,11 @@
-use hardware::i2c::connect;
 use hardware::i2c::mpu6050::raw_data::{AccelFullScale, FullScale, GyroFullScale};
 use hardware::i2c::mpu6050::{ADDRESS_LOW, MPU6050};
 use hardware::i2c::pca9685::PCA9685;
 use hardware::i2c::register_io::I2cWithAddr;
 use hardware::i2c::servo::SG90_180;
+use hardware::i2c::{BusRegistry, ThreadSafeI2c};
 use hardware::model::sensor::{AccelInfo, GyroInfo};
-use linux_embedded_hal::Delay;
+use linux_embedded_hal::{Delay, I2cdev};
 use num_traits::FromPrimitive;
 use pwm_pca9685::Channel;
 use std::io::Result as IOResult;
@@ -114,7 +114,11 @@ fn main() -> IOResult<()> {
         z: args.servo_gyro_z,
     };
 
-    let for_pca9685 = start_pca9685()?;
+    let mut buses = BusRegistry::new();
+    buses.register_linux("main", 1);
+    let dev = buses.get("main")?;
+
+    let for_pca9685 = start_pca9685(dev.clone())?;
 
     let for_accel = start_accel(
         for_pca9685.clone(),
@@ -131,17 +135,17 @@ fn main() -> IOResult<()> {
         args.gyro_filter,
     )?;
 
-    start_mpu6050(args.accel_fs, args.gyro_fs, for_accel, for_gyro)
+    start_mpu6050(dev, args.accel_fs, args.gyro_fs, for_accel, for_gyro)
 }
 
 fn start_mpu6050(
+    dev: ThreadSafeI2c<I2cdev>,
     accel_fs: AccelFullScale,
     gyro_fs: GyroFullScale,
     for_accel: mpsc::Sender<AccelInfo<f64>>,
     for_gyro: mpsc::Sender<GyroInfo<f64>>,
 ) -> IOResult<()> {
-    let dev = connect(1)?;
-    let mut mpu = MPU6050::new(I2cWithAddr::new(dev, ADDRESS_LOW)).unwrap();
+    let mut mpu = MPU6050::new_verified(I2cWithAddr::new(dev, ADDRESS_LOW)).unwrap();
     mpu.normal_setup(&mut Delay).unwrap();
     mpu.set_gyro_full_scale(gyro_fs).unwrap();
     mpu.set_accel_full_scale(accel_fs).unwrap();
@@ -156,11 +160,10 @@ fn start_mpu6050(
     }
 }
 
-fn start_pca9685() -> IOResult<mpsc::Sender<(Channel, f64)>> {
+fn start_pca9685(dev: ThreadSafeI2c<I2cdev>) -> IOResult<mpsc::Sender<(Channel, f64)>> {
     let (tx, rx) = mpsc::channel();
 
     std::thread::spawn(move || {
-        let dev = connect(1).unwrap();
         let mut pca9685 = PCA9685::new(dev, 0x40).unwrap();
 
         for (channel, rate) in rx {
@@ -0,0 +1,174 @@
+use crate::i2c::{I2cAddr, RegAddr};
+
+use core::fmt::{Display, Formatter};
+use getset::CopyGetters;
+
+/// Errors of the underlying bus that can be carried by `Error`.
+pub trait BusError: std::error::Error + Send + Sync + 'static {}
+
+impl<E> BusError for E where E: std::error::Error + Send + Sync + 'static {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Device {
+    Unknown,
+    Mpu6050,
+    Pca9685,
+}
+
+impl Display for Device {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Device::Unknown => write!(f, "Unknown device"),
+            Device::Mpu6050 => write!(f, "MPU6050"),
+            Device::Pca9685 => write!(f, "PCA9685"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// Writing bytes to the device failed.
+    Write,
+    /// Writing bytes and then reading the response failed.
+    WriteRead,
+    /// The driver refused the given value before accessing the bus.
+    InvalidInput,
+    /// The FIFO buffer of the device overflowed and its contents were discarded.
+    FifoOverflow,
+    /// The GPIO line connected to the device failed.
+    Gpio,
+    /// The device did not respond in time.
+    Timeout,
+    /// A slave on the auxiliary I2C bus of the device did not acknowledge.
+    AuxNack,
+    /// The device at the address is not the expected one.
+    UnexpectedDevice,
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ErrorKind::Write => write!(f, "failed to write"),
+            ErrorKind::WriteRead => write!(f, "failed to read"),
+            ErrorKind::InvalidInput => write!(f, "invalid input"),
+            ErrorKind::FifoOverflow => write!(f, "FIFO overflowed"),
+            ErrorKind::Gpio => write!(f, "GPIO line failed"),
+            ErrorKind::Timeout => write!(f, "timed out"),
+            ErrorKind::AuxNack => write!(f, "auxiliary I2C slave did not acknowledge"),
+            ErrorKind::UnexpectedDevice => write!(f, "unexpected device at the address"),
+        }
+    }
+}
+
+/// The error of every driver in this crate.
+/// It tells which device, address and register was being accessed,
+/// and keeps the error of the bus as its source.
+#[derive(Debug, CopyGetters)]
+#[get_copy = "pub"]
+pub struct Error {
+    kind: ErrorKind,
+    device: Device,
+    address: I2cAddr,
+    register: Option<RegAddr>,
+    #[getset(skip)]
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, device: Device, address: I2cAddr) -> Self {
+        Self {
+            kind,
+            device,
+            address,
+            register: None,
+            source: None,
+        }
+    }
+
+    pub fn write<E: BusError>(device: Device, address: I2cAddr, reg: RegAddr, err: E) -> Self {
+        Self::new(ErrorKind::Write, device, address)
+            .with_register(reg)
+            .with_source(err)
+    }
+
+    pub fn write_read<E: BusError>(device: Device, address: I2cAddr, reg: RegAddr, err: E) -> Self {
+        Self::new(ErrorKind::WriteRead, device, address)
+            .with_register(reg)
+            .with_source(err)
+    }
+
+    pub fn with_register(mut self, reg: RegAddr) -> Self {
+        self.register = Some(reg);
+        self
+    }
+
+    pub fn with_source<E: BusError>(mut self, err: E) -> Self {
+        self.source = Some(Box::new(err));
+        self
+    }
+
+    /// The error of the underlying bus, if any.
+    pub fn bus_error(&self) -> Option<&(dyn std::error::Error + Send + Sync + 'static)> {
+        self.source.as_deref()
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} at 0x{:02x}", self.device, self.address.0)?;
+        if let Some(reg) = self.register {
+            write!(f, " register 0x{:02x}", reg.0)?;
+        }
+        write!(f, ": {}", self.kind)?;
+        if let Some(src) = &self.source {
+            write!(f, ": {}", src)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|e| e as &(dyn std::error::Error + 'static))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+    use std::io;
+
+    #[test]
+    fn display_with_register() {
+        let src = io::Error::new(io::ErrorKind::BrokenPipe, "nack");
+        let err = Error::write_read(Device::Mpu6050, I2cAddr(0x68), RegAddr(0x3b), src);
+        assert_eq!(
+            err.to_string(),
+            "MPU6050 at 0x68 register 0x3b: failed to read: nack"
+        );
+    }
+
+    #[test]
+    fn display_without_register() {
+        let err = Error::new(ErrorKind::InvalidInput, Device::Pca9685, I2cAddr(0x40));
+        assert_eq!(err.to_string(), "PCA9685 at 0x40: invalid input");
+        assert!(err.source().is_none());
+    }
+
+    #[test]
+    fn source_is_bus_error() {
+        let src = io::Error::new(io::ErrorKind::TimedOut, "timeout");
+        let err = Error::write(Device::Unknown, I2cAddr(0x10), RegAddr(0x01), src);
+        assert_eq!(err.kind(), ErrorKind::Write);
+        assert_eq!(err.register(), Some(RegAddr(0x01)));
+
+        let io_err = err
+            .source()
+            .and_then(|e| e.downcast_ref::<io::Error>())
+            .expect("The source must be io::Error");
+        assert_eq!(io_err.kind(), io::ErrorKind::TimedOut);
+    }
+}
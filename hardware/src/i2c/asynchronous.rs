@@ -0,0 +1,235 @@
+//! Drivers for an async runtime (tokio), enabled by the `async` feature.
+//!
+//! The transfers still run on the blocking bus, but in the blocking thread pool of tokio
+//! (`spawn_blocking`), so a slow transfer, e.g. one retried after a NACK,
+//! does not block the other tasks on the thread of the runtime.
+//! `AsyncBus` serializes them with an async mutex, so a task waiting for the bus
+//! yields to the others, and no OS mutex is held across an await.
+
+pub mod mpu6050;
+pub mod pca9685;
+
+use super::{I2cAddr, RegAddr, Register};
+use crate::error::*;
+
+use async_trait::async_trait;
+use embedded_hal::blocking::i2c::{SevenBitAddress, Write, WriteRead};
+use std::sync::Arc;
+use tokio::sync::{Mutex, MutexGuard, OwnedMutexGuard};
+
+/// A blocking bus shared between tasks.
+/// The clones access the same bus, one transfer at a time in the order they are awaited.
+pub struct AsyncBus<T> {
+    bus: Arc<Mutex<T>>,
+}
+
+impl<T> Clone for AsyncBus<T> {
+    fn clone(&self) -> Self {
+        Self {
+            bus: self.bus.clone(),
+        }
+    }
+}
+
+impl<T> AsyncBus<T> {
+    pub fn new(bus: T) -> Self {
+        Self {
+            bus: Arc::new(Mutex::new(bus)),
+        }
+    }
+
+    /// Holds the bus until the guard is dropped, for several transfers without others in between.
+    pub async fn lock(&self) -> MutexGuard<'_, T> {
+        self.bus.lock().await
+    }
+}
+
+impl<T: Send + 'static> AsyncBus<T> {
+    /// Runs the transfers of `f` in the blocking thread pool, holding the bus.
+    pub async fn transfer<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let guard = self.bus.clone().lock_owned().await;
+        run_blocking(guard, f).await.1
+    }
+}
+
+impl<T> AsyncBus<T>
+where
+    T: Write + Send + 'static,
+    <T as Write>::Error: Send,
+{
+    pub async fn write(
+        &self,
+        address: SevenBitAddress,
+        bytes: &[u8],
+    ) -> Result<(), <T as Write>::Error> {
+        let bytes = bytes.to_vec();
+        self.transfer(move |bus| bus.write(address, &bytes)).await
+    }
+}
+
+impl<T> AsyncBus<T>
+where
+    T: WriteRead + Send + 'static,
+    <T as WriteRead>::Error: Send,
+{
+    pub async fn write_read(
+        &self,
+        address: SevenBitAddress,
+        bytes: &[u8],
+        buf: &mut [u8],
+    ) -> Result<(), <T as WriteRead>::Error> {
+        let bytes = bytes.to_vec();
+        let mut res = vec![0; buf.len()];
+        let (result, res) = self
+            .transfer(move |bus| (bus.write_read(address, &bytes, &mut res), res))
+            .await;
+        buf.copy_from_slice(&res);
+        result
+    }
+}
+
+/// Runs `f` with the bus in the blocking thread pool and gives the guard back.
+/// A panic in `f` is resumed in the task.
+async fn run_blocking<T, R, F>(mut guard: OwnedMutexGuard<T>, f: F) -> (OwnedMutexGuard<T>, R)
+where
+    T: Send + 'static,
+    F: FnOnce(&mut T) -> R + Send + 'static,
+    R: Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        let result = f(&mut guard);
+        (guard, result)
+    })
+    .await
+    .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+}
+
+/// The async version of `I2cRegister`. The registers are not cached.
+#[async_trait]
+pub trait AsyncI2cRegister<T>: Send
+where
+    T: Write + WriteRead + Send + 'static,
+    <T as Write>::Error: BusError,
+    <T as WriteRead>::Error: BusError,
+{
+    fn bus(&self) -> &AsyncBus<T>;
+    fn address(&self) -> I2cAddr;
+    fn device(&self) -> Device;
+
+    async fn read_bytes(&mut self, reg: RegAddr, res: &mut [u8]) -> Result<(), Error> {
+        let addr = self.address();
+        let device = self.device();
+        self.bus()
+            .write_read(addr.into(), &[reg.into()], res)
+            .await
+            .map_err(|e| Error::write_read(device, addr, reg, e))
+    }
+
+    async fn read_byte(&mut self, reg: RegAddr) -> Result<u8, Error> {
+        let mut buf = [0; 1];
+        self.read_bytes(reg, &mut buf).await?;
+        Ok(buf[0])
+    }
+
+    async fn write_byte(&mut self, reg: RegAddr, v: u8) -> Result<(), Error> {
+        self.write_bytes(reg, &[v]).await
+    }
+
+    /// Writes the bytes to the consecutive registers from `reg` in one transfer.
+    /// The device must increment the register address automatically.
+    async fn write_bytes(&mut self, reg: RegAddr, bytes: &[u8]) -> Result<(), Error> {
+        let addr = self.address();
+        let device = self.device();
+        let mut buf = Vec::with_capacity(bytes.len() + 1);
+        buf.push(reg.into());
+        buf.extend_from_slice(bytes);
+        self.bus()
+            .write(addr.into(), &buf)
+            .await
+            .map_err(|e| Error::write(device, addr, reg, e))
+    }
+
+    async fn read_register<R: Register + Send>(&mut self) -> Result<R, Error> {
+        let byte = self.read_byte(R::ADDR).await?;
+        Ok(R::from(byte))
+    }
+
+    async fn write_register<R: Register + Send>(&mut self, reg_value: R) -> Result<(), Error> {
+        self.write_byte(R::ADDR, reg_value.into()).await
+    }
+
+    /// Reads the register, lets `f` modify it and writes it back,
+    /// holding the bus so that no other task accesses it in between.
+    /// Returns the written value.
+    async fn modify_register<R, F>(&mut self, f: F) -> Result<R, Error>
+    where
+        R: Register + Send + 'static,
+        F: FnOnce(&mut R) + Send,
+    {
+        let addr = self.address();
+        let device = self.device();
+        let guard = self.bus().bus.clone().lock_owned().await;
+        let (guard, byte) = run_blocking(guard, move |bus| {
+            let mut buf = [0; 1];
+            bus.write_read(addr.into(), &[R::ADDR.into()], &mut buf)
+                .map(|_| buf[0])
+        })
+        .await;
+        let mut value = R::from(byte.map_err(|e| Error::write_read(device, addr, R::ADDR, e))?);
+        f(&mut value);
+        let byte: u8 = value.into();
+        run_blocking(guard, move |bus| {
+            bus.write(addr.into(), &[R::ADDR.into(), byte])
+        })
+        .await
+        .1
+        .map_err(|e| Error::write(device, addr, R::ADDR, e))?;
+        Ok(value)
+    }
+}
+
+/// The async version of `I2cWithAddr`.
+#[derive(Clone)]
+pub struct AsyncI2cWithAddr<T> {
+    bus: AsyncBus<T>,
+    address: I2cAddr,
+    device: Device,
+}
+
+impl<T> AsyncI2cWithAddr<T> {
+    pub fn new(bus: AsyncBus<T>, address: I2cAddr) -> Self {
+        Self {
+            bus,
+            address,
+            device: Device::Unknown,
+        }
+    }
+
+    /// Sets which device is at the address. It is reported in errors.
+    pub fn set_device(&mut self, device: Device) {
+        self.device = device;
+    }
+}
+
+impl<T> AsyncI2cRegister<T> for AsyncI2cWithAddr<T>
+where
+    T: Write + WriteRead + Send + 'static,
+    <T as Write>::Error: BusError,
+    <T as WriteRead>::Error: BusError,
+{
+    fn bus(&self) -> &AsyncBus<T> {
+        &self.bus
+    }
+
+    fn address(&self) -> I2cAddr {
+        self.address
+    }
+
+    fn device(&self) -> Device {
+        self.device
+    }
+}
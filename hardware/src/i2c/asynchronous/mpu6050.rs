@@ -0,0 +1,253 @@
+use super::*;
+use crate::i2c::mpu6050::fifo::*;
+use crate::i2c::mpu6050::interrupt::*;
+use crate::i2c::mpu6050::raw_data::*;
+use crate::i2c::mpu6050::register::*;
+use crate::i2c::mpu6050::WHO_AM_I;
+use crate::model::sensor::Measurement;
+
+use num_traits::{Float, FloatConst};
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// The async version of `MPU6050`.
+/// The FIFO buffer takes only the sensors of the device; the auxiliary I2C master is not supported.
+pub struct AsyncMPU6050<T> {
+    dev: AsyncI2cWithAddr<T>,
+    fifo: Option<FifoSensors>,
+    accel_fs: Option<AccelFullScale>,
+    gyro_fs: Option<GyroFullScale>,
+}
+
+impl<T> AsyncMPU6050<T>
+where
+    T: Write + WriteRead + Send + 'static,
+    <T as Write>::Error: BusError,
+    <T as WriteRead>::Error: BusError,
+{
+    pub fn new(mut dev: AsyncI2cWithAddr<T>) -> AsyncMPU6050<T> {
+        dev.set_device(Device::Mpu6050);
+        AsyncMPU6050 {
+            dev,
+            fifo: None,
+            accel_fs: None,
+            gyro_fs: None,
+        }
+    }
+
+    /// `new` and `verify_identity`.
+    pub async fn new_verified(dev: AsyncI2cWithAddr<T>) -> Result<AsyncMPU6050<T>, Error> {
+        let mut o = Self::new(dev);
+        o.verify_identity().await?;
+        Ok(o)
+    }
+
+    /// Fails with `ErrorKind::UnexpectedDevice` unless the device answers WHO_AM_I as an MPU6050.
+    pub async fn verify_identity(&mut self) -> Result<(), Error> {
+        let value: WhoAmI = self.dev.read_register().await?;
+        if u8::from(value) == WHO_AM_I {
+            Ok(())
+        } else {
+            Err(self
+                .error(ErrorKind::UnexpectedDevice)
+                .with_register(WhoAmI::ADDR))
+        }
+    }
+
+    pub async fn normal_setup(&mut self) -> Result<(), Error> {
+        self.reset().await?;
+        self.set_sleep_enabled(false).await?;
+        self.disable_all_interrupts().await?;
+        self.set_clock_source(ClockSel::Xgyro).await?;
+        self.set_accel_full_scale(AccelFullScale::G2).await?;
+        self.set_gyro_full_scale(GyroFullScale::Deg2000).await?;
+        self.set_sample_rate_divider(4.into()).await?;
+        Ok(())
+    }
+
+    pub async fn reset(&mut self) -> Result<(), Error> {
+        self.dev
+            .modify_register(|r: &mut PwrMgmt1| r.set_device_reset(true))
+            .await?;
+        self.fifo = None;
+        self.accel_fs = Some(AccelFullScale::G2);
+        self.gyro_fs = Some(GyroFullScale::Deg250);
+        sleep(Duration::from_millis(200)).await;
+        Ok(())
+    }
+
+    pub async fn set_sleep_enabled(&mut self, v: bool) -> Result<(), Error> {
+        self.dev
+            .modify_register(|r: &mut PwrMgmt1| r.set_sleep(v))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn disable_all_interrupts(&mut self) -> Result<(), Error> {
+        self.dev.write_register(IntEnable::from(0)).await
+    }
+
+    /// Enables the given interrupts and disables the others.
+    pub async fn enable_interrupts(&mut self, interrupts: Interrupts) -> Result<(), Error> {
+        self.dev.write_register(IntEnable::from(interrupts)).await
+    }
+
+    /// Reads which interrupts have been generated.
+    /// Reading clears the status and releases a latched INT pin.
+    pub async fn get_interrupt_status(&mut self) -> Result<Interrupts, Error> {
+        let value: IntStatus = self.dev.read_register().await?;
+        Ok(value.into())
+    }
+
+    pub async fn set_clock_source(&mut self, v: ClockSel) -> Result<(), Error> {
+        self.dev
+            .modify_register(|r: &mut PwrMgmt1| r.set_clksel(v))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_sample_rate_divider(&mut self, v: SampleRateDivider) -> Result<(), Error> {
+        self.dev.write_register(v).await
+    }
+
+    pub async fn set_digital_lowpass_filter(
+        &mut self,
+        filter: DigitalLowPassFilterCfg,
+    ) -> Result<(), Error> {
+        self.dev
+            .modify_register(|r: &mut Configure| r.set_dlpf(filter))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_accel_full_scale(&mut self, scale: AccelFullScale) -> Result<(), Error> {
+        self.dev
+            .modify_register(|r: &mut AccelConfig| r.set_scale(scale))
+            .await?;
+        self.accel_fs = Some(scale);
+        Ok(())
+    }
+
+    pub async fn set_gyro_full_scale(&mut self, scale: GyroFullScale) -> Result<(), Error> {
+        self.dev
+            .modify_register(|r: &mut GyroConfig| r.set_scale(scale))
+            .await?;
+        self.gyro_fs = Some(scale);
+        Ok(())
+    }
+
+    /// The full scale is read from the device only if it has not been set by this driver.
+    pub async fn get_accel_full_scale(&mut self) -> Result<AccelFullScale, Error> {
+        match self.accel_fs {
+            Some(scale) => Ok(scale),
+            None => {
+                let value: AccelConfig = self.dev.read_register().await?;
+                self.accel_fs = Some(value.get_scale());
+                Ok(value.get_scale())
+            }
+        }
+    }
+
+    /// The full scale is read from the device only if it has not been set by this driver.
+    pub async fn get_gyro_full_scale(&mut self) -> Result<GyroFullScale, Error> {
+        match self.gyro_fs {
+            Some(scale) => Ok(scale),
+            None => {
+                let value: GyroConfig = self.dev.read_register().await?;
+                self.gyro_fs = Some(value.get_scale());
+                Ok(value.get_scale())
+            }
+        }
+    }
+
+    pub async fn get_infos(&mut self) -> Result<RawData, Error> {
+        let mut buf = [0; 14];
+        self.dev.read_bytes(AccelData::ADDR, &mut buf).await?;
+        Ok(RawData::from(&buf))
+    }
+
+    /// Reads the measurements and converts them with the current full scales:
+    /// acceleration in m/s², angular velocity in deg/s and temperature in degrees C.
+    pub async fn get_measurement<V>(&mut self) -> Result<Measurement<V>, Error>
+    where
+        V: Float + FloatConst + From<i32>,
+    {
+        let accel_fs = self.get_accel_full_scale().await?;
+        let gyro_fs = self.get_gyro_full_scale().await?;
+        let raw = self.get_infos().await?;
+        Ok(raw.to_measurement(accel_fs, gyro_fs))
+    }
+
+    /// Starts loading the sensor measurements into the FIFO buffer.
+    /// The FIFO buffer is emptied.
+    /// Fails with `ErrorKind::InvalidInput` if a slave of the auxiliary I2C master is included.
+    pub async fn enable_fifo(&mut self, sensors: FifoSensors) -> Result<(), Error> {
+        if sensors.is_empty() || sensors.slaves.iter().any(|len| *len > 0) {
+            return Err(self
+                .error(ErrorKind::InvalidInput)
+                .with_register(FifoEnable::ADDR));
+        }
+        self.dev.write_register(FifoEnable::from(sensors)).await?;
+        self.fifo = Some(sensors);
+        self.reset_fifo().await
+    }
+
+    pub async fn disable_fifo(&mut self) -> Result<(), Error> {
+        self.dev
+            .modify_register(|r: &mut UserCtrl| r.set_fifo_en(false))
+            .await?;
+        self.dev.write_register(FifoEnable::from(0)).await?;
+        self.fifo = None;
+        Ok(())
+    }
+
+    /// Discards the contents of the FIFO buffer.
+    pub async fn reset_fifo(&mut self) -> Result<(), Error> {
+        let mut value = self
+            .dev
+            .modify_register(|r: &mut UserCtrl| {
+                r.set_fifo_en(false);
+                r.set_fifo_reset(true);
+            })
+            .await?;
+        if self.fifo.is_some() {
+            value.set_fifo_reset(false);
+            value.set_fifo_en(true);
+            self.dev.write_register(value).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn get_fifo_count(&mut self) -> Result<FifoCount, Error> {
+        let mut buf = [0; 2];
+        self.dev.read_bytes(FifoCount::ADDR, &mut buf).await?;
+        Ok(FifoCount::from(&buf))
+    }
+
+    /// Reads all complete frames in the FIFO buffer, like `MPU6050::read_fifo`.
+    pub async fn read_fifo(&mut self) -> Result<Vec<FifoFrame>, Error> {
+        let sensors = self.fifo.ok_or_else(|| {
+            self.error(ErrorKind::InvalidInput)
+                .with_register(FifoData::ADDR)
+        })?;
+
+        let count: u16 = self.get_fifo_count().await?.into();
+        if count >= FIFO_SIZE {
+            self.reset_fifo().await?;
+            return Err(self
+                .error(ErrorKind::FifoOverflow)
+                .with_register(FifoCount::ADDR));
+        }
+
+        let size = sensors.frame_size();
+        let mut buf = vec![0; (count as usize) / size * size];
+        if !buf.is_empty() {
+            self.dev.read_bytes(FifoData::ADDR, &mut buf).await?;
+        }
+        Ok(sensors.parse(&buf))
+    }
+
+    fn error(&self, kind: ErrorKind) -> Error {
+        Error::new(kind, Device::Mpu6050, self.dev.address())
+    }
+}
@@ -0,0 +1,182 @@
+use super::*;
+use crate::i2c::pca9685::frame::{Counts, CHANNELS};
+use crate::i2c::pca9685::*;
+
+use pwm_pca9685::Channel;
+
+const MODE1_ALLCALL: u8 = 0b_0000_0001;
+const MODE1_SLEEP: u8 = 0b_0001_0000;
+const MODE1_AI: u8 = 0b_0010_0000;
+
+/// The async version of `PCA9685`.
+/// It writes the registers by itself, in the same order as `pwm_pca9685`.
+pub struct AsyncPCA9685<T> {
+    dev: AsyncI2cWithAddr<T>,
+    /// The value written to MODE1 last. The power-on value until then.
+    mode1: u8,
+    prescale: Option<u8>,
+    /// The counts last written to the channels.
+    counts: [Counts; CHANNELS],
+}
+
+impl<T> AsyncPCA9685<T>
+where
+    T: Write + WriteRead + Send + 'static,
+    <T as Write>::Error: BusError,
+    <T as WriteRead>::Error: BusError,
+{
+    pub fn new(bus: AsyncBus<T>, addr: u8) -> AsyncPCA9685<T> {
+        let mut dev = AsyncI2cWithAddr::new(bus, I2cAddr(addr));
+        dev.set_device(Device::Pca9685);
+        AsyncPCA9685 {
+            dev,
+            mode1: MODE1_SLEEP | MODE1_ALLCALL,
+            prescale: None,
+            counts: [Counts::default(); CHANNELS],
+        }
+    }
+
+    /// `new` after checking the device like `identify`.
+    /// Fails with `ErrorKind::UnexpectedDevice` if the device does not look like a PCA9685.
+    pub async fn new_verified(bus: AsyncBus<T>, addr: u8) -> Result<AsyncPCA9685<T>, Error> {
+        let mut o = Self::new(bus, addr);
+        o.dev.read_byte(MODE1).await?;
+        let mode2 = o.dev.read_byte(MODE2).await?;
+        if mode2 & 0b_1110_0000 != 0 {
+            return Err(
+                Error::new(ErrorKind::UnexpectedDevice, Device::Pca9685, o.address())
+                    .with_register(MODE2),
+            );
+        }
+        Ok(o)
+    }
+
+    pub fn address(&self) -> I2cAddr {
+        self.dev.address()
+    }
+
+    pub async fn enable(&mut self) -> Result<(), Error> {
+        self.write_mode1(self.mode1 & !MODE1_SLEEP).await
+    }
+
+    pub async fn disable(&mut self) -> Result<(), Error> {
+        self.write_mode1(self.mode1 | MODE1_SLEEP).await
+    }
+
+    /// The oscillator is stopped while PRE_SCALE is written, and restarted if it was running.
+    /// The bus is held for the whole sequence.
+    /// The counts of the channels already running are rewritten like `PCA9685::set_prescale`,
+    /// so that their pulse widths in microseconds are kept against the new period.
+    pub async fn set_prescale(&mut self, v: u8) -> Result<(), Error> {
+        let prev = self.prescale;
+        if prev == Some(v) {
+            return Ok(());
+        }
+        if v < 3 {
+            return Err(self.error(ErrorKind::InvalidInput).with_register(PRE_SCALE));
+        }
+        let addr = self.address();
+        let mode1 = self.mode1;
+        let running = mode1 & MODE1_SLEEP == 0;
+        self.dev
+            .bus()
+            .transfer(move |bus| {
+                let mut write = |reg: RegAddr, value: u8| {
+                    bus.write(addr.into(), &[reg.into(), value])
+                        .map_err(|e| Error::write(Device::Pca9685, addr, reg, e))
+                };
+                if running {
+                    write(MODE1, mode1 | MODE1_SLEEP)?;
+                }
+                write(PRE_SCALE, v)?;
+                if running {
+                    write(MODE1, mode1)?;
+                }
+                Ok::<_, Error>(())
+            })
+            .await?;
+        self.prescale = Some(v);
+        match prev {
+            Some(prev) if self.counts.iter().any(|c| c.width() > 0) => {
+                let ratio = (prev as f64 + 1.0) / (v as f64 + 1.0);
+                self.write_counts(self.counts.map(|c| c.scale(ratio))).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub async fn set_one_duty_cycle(&mut self, channel: Channel, rate: f64) -> Result<(), Error> {
+        let reg = channel_register(channel);
+        let off = (PULSE_BASE * rate) as u16;
+        if off > 4095 {
+            return Err(self.error(ErrorKind::InvalidInput).with_register(reg));
+        }
+        self.enable_auto_increment().await?;
+        let counts = Counts { on: 0, off };
+        self.dev.write_bytes(reg, &count_bytes(&counts)).await?;
+        match channel {
+            Channel::All => self.counts = [counts; CHANNELS],
+            _ => self.counts[channel as usize] = counts,
+        }
+        Ok(())
+    }
+
+    /// Writes the counts of all of the channels in one transfer, like `PCA9685`.
+    async fn write_counts(&mut self, counts: [Counts; CHANNELS]) -> Result<(), Error> {
+        self.enable_auto_increment().await?;
+        if counts.iter().all(|c| *c == counts[0]) {
+            self.dev
+                .write_bytes(ALL_LED_ON_L, &count_bytes(&counts[0]))
+                .await?;
+        } else {
+            let bytes: Vec<u8> = counts.iter().flat_map(count_bytes).collect();
+            self.dev.write_bytes(LED0_ON_L, &bytes).await?;
+        }
+        self.counts = counts;
+        Ok(())
+    }
+
+    async fn enable_auto_increment(&mut self) -> Result<(), Error> {
+        if self.mode1 & MODE1_AI == 0 {
+            self.write_mode1(self.mode1 | MODE1_AI).await?;
+        }
+        Ok(())
+    }
+
+    /// The prescale is common to the board, so all of them must have the same prescale;
+    /// fails with `ErrorKind::InvalidInput` without writing anything otherwise.
+    pub async fn set_duty_cycle<S>(&mut self, rates: &[(&S, f64)]) -> Result<(), Error>
+    where
+        S: HasChannel + HasPrescale + Sync,
+    {
+        let prescale = match rates.first() {
+            Some((s, _)) => s.prescale(),
+            None => return Ok(()),
+        };
+        if rates.iter().any(|(s, _)| s.prescale() != prescale) {
+            return Err(self.error(ErrorKind::InvalidInput).with_register(PRE_SCALE));
+        }
+        self.set_prescale(prescale).await?;
+        for (s, rate) in rates.iter() {
+            self.set_one_duty_cycle(s.channel(), *rate).await?;
+        }
+        Ok(())
+    }
+
+    async fn write_mode1(&mut self, v: u8) -> Result<(), Error> {
+        self.dev.write_byte(MODE1, v).await?;
+        self.mode1 = v;
+        Ok(())
+    }
+
+    fn error(&self, kind: ErrorKind) -> Error {
+        Error::new(kind, Device::Pca9685, self.address())
+    }
+}
+
+/// The bytes of LEDn_ON_L to LEDn_OFF_H.
+fn count_bytes(counts: &Counts) -> [u8; 4] {
+    let [on_l, on_h] = counts.on.to_le_bytes();
+    let [off_l, off_h] = counts.off.to_le_bytes();
+    [on_l, on_h, off_l, off_h]
+}
@@ -0,0 +1,224 @@
+//! Declarative definition of the registers of a byte.
+//!
+//! ```ignore
+//! register! {
+//!     /// This register allows the user to configure the power mode and clock source.
+//!     pub struct PwrMgmt1 @ 0x6b {
+//!         clksel: ClockSel [2:0],
+//!         /// When set to 1, this bit disables the temperature sensor.
+//!         tempdis: bool [3],
+//!     }
+//! }
+//! ```
+//!
+//! generates the newtype of `u8` with the `Register` impl, `get_clksel`, `set_clksel`,
+//! `get_tempdis` and `set_tempdis`, and `Debug` which shows the decoded fields.
+//! The address can be omitted for a register of which the address is not fixed.
+//! `volatile` after the address marks a register which the device changes by itself,
+//! such as status and self-clearing bits (`@ 0x3a volatile`).
+//! The field types implement `Bitfield`; `bitfield_enum!` implements it for `FromPrimitive` enums.
+
+#[doc(hidden)]
+pub use derive_more::{From, Into};
+#[doc(hidden)]
+pub use num_traits::FromPrimitive;
+#[doc(hidden)]
+pub use paste::paste;
+
+/// A value stored in some bits of a register.
+pub trait Bitfield: Sized {
+    /// The bits are shifted to the least significant bit and masked.
+    fn from_bits(bits: u8) -> Self;
+    fn into_bits(self) -> u8;
+}
+
+impl Bitfield for bool {
+    fn from_bits(bits: u8) -> Self {
+        bits != 0
+    }
+
+    fn into_bits(self) -> u8 {
+        self as u8
+    }
+}
+
+impl Bitfield for u8 {
+    fn from_bits(bits: u8) -> Self {
+        bits
+    }
+
+    fn into_bits(self) -> u8 {
+        self
+    }
+}
+
+/// Implements `Bitfield` for enums of `#[repr(u8)]` which derive `FromPrimitive`.
+/// Every value of the bits must have its variant.
+#[macro_export]
+macro_rules! bitfield_enum {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl $crate::i2c::bitfield::Bitfield for $ty {
+                fn from_bits(bits: u8) -> Self {
+                    <$ty as $crate::i2c::bitfield::FromPrimitive>::from_u8(bits).expect(concat!(
+                        "The bits must be converted to ",
+                        stringify!($ty),
+                        "."
+                    ))
+                }
+
+                fn into_bits(self) -> u8 {
+                    self as u8
+                }
+            }
+        )*
+    };
+}
+
+/// Defines a register of a byte. See the module document.
+#[macro_export]
+macro_rules! register {
+    (@lo $hi:literal) => { $hi };
+    (@lo $hi:literal $lo:literal) => { $lo };
+
+    (@addr $name:ident) => {};
+    (@addr $name:ident $addr:literal) => {
+        impl $crate::i2c::register_io::Register for $name {
+            const ADDR: $crate::i2c::register_io::RegAddr =
+                $crate::i2c::register_io::RegAddr($addr);
+        }
+    };
+    (@addr $name:ident $addr:literal volatile) => {
+        impl $crate::i2c::register_io::Register for $name {
+            const ADDR: $crate::i2c::register_io::RegAddr =
+                $crate::i2c::register_io::RegAddr($addr);
+            const VOLATILE: bool = true;
+        }
+    };
+
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident $(@ $addr:literal $($volatile:ident)?)? {
+            $(
+                $(#[$fmeta:meta])*
+                $field:ident: $fty:ty [$hi:literal $(: $lo:literal)?]
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(
+            $crate::i2c::bitfield::From,
+            $crate::i2c::bitfield::Into,
+            Clone,
+            Copy,
+            PartialEq,
+            Eq,
+            Hash,
+        )]
+        $vis struct $name(u8);
+
+        $crate::register!(@addr $name $($addr $($volatile)?)?);
+
+        $crate::i2c::bitfield::paste! {
+            impl $name {
+                $(
+                    $(#[$fmeta])*
+                    pub fn [<get_ $field>](&self) -> $fty {
+                        const LO: u8 = $crate::register!(@lo $hi $($lo)?);
+                        const MASK: u8 = 0xff >> (7 - $hi + LO);
+                        <$fty as $crate::i2c::bitfield::Bitfield>::from_bits((self.0 >> LO) & MASK)
+                    }
+
+                    pub fn [<set_ $field>](&mut self, v: $fty) {
+                        const LO: u8 = $crate::register!(@lo $hi $($lo)?);
+                        const MASK: u8 = 0xff >> (7 - $hi + LO);
+                        let bits = $crate::i2c::bitfield::Bitfield::into_bits(v) & MASK;
+                        self.0 = (self.0 & !(MASK << LO)) | (bits << LO);
+                    }
+                )*
+            }
+
+            impl core::fmt::Debug for $name {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    f.debug_struct(stringify!($name))
+                        $(.field(stringify!($field), &self.[<get_ $field>]()))*
+                        .finish()
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i2c::register_io::*;
+    use num_derive::FromPrimitive;
+
+    #[derive(Debug, Clone, Copy, FromPrimitive, PartialEq, Eq)]
+    #[repr(u8)]
+    enum Mode {
+        A = 0,
+        B = 1,
+        C = 2,
+        D = 3,
+    }
+
+    crate::bitfield_enum!(Mode);
+
+    crate::register! {
+        struct Sample @ 0x42 {
+            low: u8 [2:0],
+            flag: bool [3],
+            mode: Mode [5:4],
+            top: bool [7],
+        }
+    }
+
+    crate::register! {
+        struct Status @ 0x43 volatile {
+            ready: bool [0],
+        }
+    }
+
+    crate::register! {
+        struct Floating {
+            all: u8 [7:0],
+        }
+    }
+
+    #[test]
+    fn fields() {
+        let mut r = Sample::from(0b_1010_0110);
+        assert_eq!(Sample::ADDR, RegAddr(0x42));
+        assert_eq!((Sample::VOLATILE, Status::VOLATILE), (false, true));
+        let mut status = Status::from(0);
+        status.set_ready(true);
+        assert!(status.get_ready());
+        assert_eq!(r.get_low(), 0b110);
+        assert!(!r.get_flag());
+        assert_eq!(r.get_mode(), Mode::C);
+        assert!(r.get_top());
+
+        r.set_low(0b1001);
+        r.set_flag(true);
+        r.set_mode(Mode::B);
+        r.set_top(false);
+        assert_eq!(u8::from(r), 0b_0001_1001);
+        assert_eq!(Mode::from_bits(3), Mode::D);
+        assert_eq!(Mode::A.into_bits(), 0);
+
+        let mut f = Floating::from(0);
+        f.set_all(0xa5);
+        assert_eq!(f.get_all(), 0xa5);
+    }
+
+    #[test]
+    fn debug() {
+        let r = Sample::from(0b_1010_0110);
+        assert_eq!(
+            format!("{:?}", r),
+            "Sample { low: 6, flag: false, mode: C, top: true }"
+        );
+    }
+}
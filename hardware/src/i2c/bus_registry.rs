@@ -0,0 +1,224 @@
+use super::ThreadSafeI2c;
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use linux_embedded_hal::I2cdev;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+
+type Opener<T> = Box<dyn FnMut() -> Result<T> + Send>;
+
+struct Bus<T> {
+    opener: Opener<T>,
+    device: Option<ThreadSafeI2c<T>>,
+}
+
+/// Named I2C buses that are opened on demand.
+///
+/// A bus is registered with a function that opens it,
+/// so any `Write + WriteRead` implementation can be used:
+/// Linux i2cdev, an in-memory simulator, or a wrapper around another bus.
+/// A failure to open is returned to the caller and is not remembered,
+/// so the next `get` tries to open the bus again.
+pub struct BusRegistry<T> {
+    buses: HashMap<String, Bus<T>>,
+}
+
+impl<T> Default for BusRegistry<T> {
+    fn default() -> Self {
+        Self {
+            buses: HashMap::new(),
+        }
+    }
+}
+
+impl<T> BusRegistry<T>
+where
+    T: Write + WriteRead,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a bus under the name.
+    /// The opener is called on the first `get` and on every `reconnect`.
+    /// A bus already registered under the same name is closed and replaced.
+    pub fn register<F>(&mut self, name: &str, opener: F)
+    where
+        F: FnMut() -> Result<T> + Send + 'static,
+    {
+        let bus = Bus {
+            opener: Box::new(opener),
+            device: None,
+        };
+        self.buses.insert(name.to_string(), bus);
+    }
+
+    /// Removes the bus from this registry.
+    /// Returns false if no bus is registered under the name.
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.buses.remove(name).is_some()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.buses.keys().map(String::as_str).collect()
+    }
+
+    pub fn is_connected(&self, name: &str) -> bool {
+        self.buses
+            .get(name)
+            .map(|bus| bus.device.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Returns the connection of the bus, opening it if it is not connected yet.
+    pub fn get(&mut self, name: &str) -> Result<ThreadSafeI2c<T>> {
+        let bus = self.bus_mut(name)?;
+        match &bus.device {
+            Some(dev) => Ok(dev.clone()),
+            None => {
+                let dev = ThreadSafeI2c::new((bus.opener)()?);
+                bus.device = Some(dev.clone());
+                Ok(dev)
+            }
+        }
+    }
+
+    /// Closes the bus and opens it again.
+    pub fn reconnect(&mut self, name: &str) -> Result<ThreadSafeI2c<T>> {
+        self.close(name)?;
+        self.get(name)
+    }
+
+    /// Drops the connection held by this registry.
+    /// The device is released when all clones handed out by `get` are dropped too.
+    /// Returns false if the bus was not connected.
+    pub fn close(&mut self, name: &str) -> Result<bool> {
+        let bus = self.bus_mut(name)?;
+        Ok(bus.device.take().is_some())
+    }
+
+    fn bus_mut(&mut self, name: &str) -> Result<&mut Bus<T>> {
+        self.buses.get_mut(name).ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("I2C bus '{}' is not registered", name),
+            )
+        })
+    }
+}
+
+impl BusRegistry<I2cdev> {
+    /// Registers `/dev/i2c-{bus}` under the name.
+    pub fn register_linux(&mut self, name: &str, bus: u8) {
+        let path = format!("/dev/i2c-{}", bus);
+        self.register(name, move || {
+            I2cdev::new(&path).map_err(|err| {
+                Error::new(
+                    ErrorKind::NotConnected,
+                    format!("Failed to connect I2C device '{}': {}", path, err),
+                )
+            })
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal::blocking::i2c::SevenBitAddress;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Debug, PartialEq)]
+    struct NopI2c(usize);
+
+    impl Write for NopI2c {
+        type Error = Error;
+
+        fn write(&mut self, _: SevenBitAddress, _: &[u8]) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    impl WriteRead for NopI2c {
+        type Error = Error;
+
+        fn write_read(&mut self, _: SevenBitAddress, _: &[u8], _: &mut [u8]) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn counting(count: Arc<AtomicUsize>) -> impl FnMut() -> Result<NopI2c> {
+        move || Ok(NopI2c(count.fetch_add(1, Ordering::SeqCst)))
+    }
+
+    #[test]
+    fn get_opens_once() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let mut registry = BusRegistry::new();
+        registry.register("main", counting(count.clone()));
+        assert!(!registry.is_connected("main"));
+
+        let a = registry.get("main").unwrap();
+        let b = registry.get("main").unwrap();
+        assert!(registry.is_connected("main"));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert_eq!(*a.lock(), NopI2c(0));
+        assert_eq!(*b.lock(), NopI2c(0));
+    }
+
+    #[test]
+    fn reconnect_opens_again() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let mut registry = BusRegistry::new();
+        registry.register("main", counting(count.clone()));
+
+        registry.get("main").unwrap();
+        let dev = registry.reconnect("main").unwrap();
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+        assert_eq!(*dev.lock(), NopI2c(1));
+    }
+
+    #[test]
+    fn close_then_get() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let mut registry = BusRegistry::new();
+        registry.register("main", counting(count.clone()));
+
+        assert!(!registry.close("main").unwrap());
+        registry.get("main").unwrap();
+        assert!(registry.close("main").unwrap());
+        assert!(!registry.is_connected("main"));
+
+        let dev = registry.get("main").unwrap();
+        assert_eq!(*dev.lock(), NopI2c(1));
+    }
+
+    #[test]
+    fn failure_is_not_cached() {
+        let mut tries = 0;
+        let mut registry = BusRegistry::new();
+        registry.register("flaky", move || {
+            tries += 1;
+            if tries < 3 {
+                Err(Error::new(ErrorKind::NotConnected, "not yet"))
+            } else {
+                Ok(NopI2c(tries))
+            }
+        });
+
+        assert!(registry.get("flaky").is_err());
+        assert!(registry.get("flaky").is_err());
+        let dev = registry.get("flaky").unwrap();
+        assert_eq!(*dev.lock(), NopI2c(3));
+    }
+
+    #[test]
+    fn unknown_name() {
+        let mut registry = BusRegistry::<NopI2c>::new();
+        let err = registry.get("none").err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert!(registry.close("none").is_err());
+        assert!(!registry.unregister("none"));
+    }
+}
@@ -1,35 +1,447 @@
-pub mod error;
+pub mod aux_i2c;
+pub mod calibration;
+pub mod fifo;
+pub mod interrupt;
+pub mod power;
 pub mod raw_data;
-mod register;
+pub(crate) mod register;
+pub mod self_test;
 
+use crate::error::*;
 use crate::i2c::*;
+use crate::model::sensor::Measurement;
+use aux_i2c::*;
+use calibration::*;
+use fifo::*;
+use interrupt::*;
+use power::*;
 use raw_data::*;
 use register::*;
+use self_test::*;
 
 use embedded_hal::blocking::delay::DelayMs;
 use embedded_hal::blocking::i2c::{Write, WriteRead};
+use num_traits::{Float, FloatConst};
 use std::result::Result;
+use std::time::{Duration, Instant};
 
 pub const ADDRESS_LOW: I2cAddr = I2cAddr(0x68);
 pub const ADDRESS_HIGH: I2cAddr = I2cAddr(0x69);
 
+/// The value of WHO_AM_I, regardless of the AD0 pin.
+pub const WHO_AM_I: u8 = 0x68;
+
+/// The wait between the reads of I2C_MST_STATUS for a transfer through slave 4.
+/// A byte takes about 25µs on the auxiliary bus at 400kHz.
+const SLV4_POLL_INTERVAL: Duration = Duration::from_micros(100);
+
+/// The registers whose reads change the device, so must not be retried:
+/// I2C_MST_STATUS and INT_STATUS are cleared, and FIFO_R_W pops the bytes.
+/// See `RetryingI2c::set_no_retry`.
+pub const NO_RETRY_REGISTERS: [RegAddr; 3] = [I2cMstStatus::ADDR, IntStatus::ADDR, FifoData::ADDR];
+
+/// Whether the device at the address answers WHO_AM_I as an MPU6050.
+pub fn identify<T>(bus: &mut T, address: I2cAddr) -> Result<bool, Error>
+where
+    T: WriteRead,
+    <T as WriteRead>::Error: BusError,
+{
+    let mut buf = [0; 1];
+    bus.write_read(address.into(), &[WhoAmI::ADDR.into()], &mut buf)
+        .map_err(|e| Error::write_read(Device::Mpu6050, address, WhoAmI::ADDR, e))?;
+    Ok(buf[0] == WHO_AM_I)
+}
+
 pub struct MPU6050<T> {
     dev: I2cWithAddr<T>,
+    fifo: Option<FifoSensors>,
+    int_pin: IntPinConfig,
+    accel_fs: Option<AccelFullScale>,
+    gyro_fs: Option<GyroFullScale>,
+    /// Number of bytes read from each of slave 0 to 3 of the auxiliary I2C master.
+    aux_slaves: [u8; 4],
 }
 
 impl<T> MPU6050<T>
 where
     T: Write + WriteRead,
-    <T as Write>::Error: core::fmt::Debug,
-    <T as WriteRead>::Error: core::fmt::Debug,
+    <T as Write>::Error: BusError,
+    <T as WriteRead>::Error: BusError,
 {
-    pub fn new(dev: I2cWithAddr<T>) -> Result<MPU6050<T>, Error<T>> {
-        let o = MPU6050 { dev };
-        // ここで何かすることになるかもしれないので Result 型にしている。
+    pub fn new(mut dev: I2cWithAddr<T>) -> Result<MPU6050<T>, Error> {
+        dev.set_device(Device::Mpu6050);
+        let o = MPU6050 {
+            dev,
+            fifo: None,
+            int_pin: IntPinConfig::default(),
+            accel_fs: None,
+            gyro_fs: None,
+            aux_slaves: [0; 4],
+        };
+        Ok(o)
+    }
+
+    /// `new` and `verify_identity`.
+    pub fn new_verified(dev: I2cWithAddr<T>) -> Result<MPU6050<T>, Error> {
+        let mut o = Self::new(dev)?;
+        o.verify_identity()?;
         Ok(o)
     }
 
-    pub fn normal_setup(&mut self, d: &mut impl DelayMs<u8>) -> Result<(), Error<T>> {
+    /// Fails with `ErrorKind::UnexpectedDevice` unless the device answers WHO_AM_I as an MPU6050.
+    pub fn verify_identity(&mut self) -> Result<(), Error> {
+        let address = self.dev.address();
+        if identify(self.dev.i2c_device(), address)? {
+            Ok(())
+        } else {
+            Err(self
+                .error(ErrorKind::UnexpectedDevice)
+                .with_register(WhoAmI::ADDR))
+        }
+    }
+
+    pub fn get_standby(&mut self) -> Result<Standby, Error> {
+        let value: PwrMgmt2 = self.dev.read_register()?;
+        Ok(value.into())
+    }
+
+    pub fn set_motion_detection(&mut self, motion: MotionDetection) -> Result<(), Error> {
+        self.dev.write_register(MotThr::from(motion.threshold()))?;
+        self.dev.write_register(MotDur::from(motion.duration_ms))
+    }
+
+    /// Configures the zero motion interrupt, which is enabled with `Interrupts::zero_motion`.
+    pub fn set_zero_motion_detection(&mut self, zero: ZeroMotionDetection) -> Result<(), Error> {
+        self.dev.write_register(ZrmotThr::from(zero.threshold()))?;
+        self.dev.write_register(ZrmotDur::from(zero.duration()))
+    }
+
+    pub fn disable_all_interrupts(&mut self) -> Result<(), Error> {
+        self.dev.write_register(IntEnable::from(0))
+    }
+
+    pub fn get_interrupt_pin(&self) -> IntPinConfig {
+        self.int_pin
+    }
+
+    /// Enables the given interrupts and disables the others.
+    pub fn enable_interrupts(&mut self, interrupts: Interrupts) -> Result<(), Error> {
+        self.dev.write_register(IntEnable::from(interrupts))
+    }
+
+    pub fn get_enabled_interrupts(&mut self) -> Result<Interrupts, Error> {
+        let value: IntEnable = self.dev.read_register()?;
+        Ok(value.into())
+    }
+
+    /// Reads which interrupts have been generated.
+    /// Reading clears the status and releases a latched INT pin.
+    pub fn get_interrupt_status(&mut self) -> Result<Interrupts, Error> {
+        let value: IntStatus = self.dev.read_register()?;
+        Ok(value.into())
+    }
+
+    /// Blocks until the INT pin becomes active, then reads and clears the interrupt status.
+    /// Returns `ErrorKind::Timeout` if no interrupt is generated in time.
+    pub fn wait_for_interrupt<L: InterruptLine>(
+        &mut self,
+        line: &mut L,
+        timeout: Duration,
+    ) -> Result<Interrupts, Error> {
+        let active = self.int_pin.is_active_high();
+        let ready = line
+            .wait_for_level(active, timeout)
+            .map_err(|err| self.error(ErrorKind::Gpio).with_source(err))?;
+        if !ready {
+            return Err(self
+                .error(ErrorKind::Timeout)
+                .with_register(IntStatus::ADDR));
+        }
+        self.get_interrupt_status()
+    }
+
+    /// Blocks until new measurements are ready to be read.
+    /// Other interrupts generated meanwhile are cleared and ignored.
+    ///
+    /// The data ready interrupt must be enabled with `enable_interrupts`.
+    pub fn wait_for_data_ready<L: InterruptLine>(
+        &mut self,
+        line: &mut L,
+        timeout: Duration,
+    ) -> Result<Interrupts, Error> {
+        let start = Instant::now();
+        loop {
+            let rest = timeout.saturating_sub(start.elapsed());
+            let status = self.wait_for_interrupt(line, rest)?;
+            if status.data_ready {
+                return Ok(status);
+            }
+        }
+    }
+
+    pub fn set_sample_rate_divider(&mut self, v: SampleRateDivider) -> Result<(), Error> {
+        self.dev.write_register(v)
+    }
+
+    /// The full scale is read from the device only if it has not been set by this driver.
+    pub fn get_accel_full_scale(&mut self) -> Result<AccelFullScale, Error> {
+        match self.accel_fs {
+            Some(scale) => Ok(scale),
+            None => {
+                let value: AccelConfig = self.dev.read_register()?;
+                self.accel_fs = Some(value.get_scale());
+                Ok(value.get_scale())
+            }
+        }
+    }
+
+    /// The full scale is read from the device only if it has not been set by this driver.
+    pub fn get_gyro_full_scale(&mut self) -> Result<GyroFullScale, Error> {
+        match self.gyro_fs {
+            Some(scale) => Ok(scale),
+            None => {
+                let value: GyroConfig = self.dev.read_register()?;
+                self.gyro_fs = Some(value.get_scale());
+                Ok(value.get_scale())
+            }
+        }
+    }
+
+    pub fn get_infos(&mut self) -> Result<RawData, Error> {
+        let mut buf = [0; 14];
+        self.dev.read_bytes(AccelData::ADDR, &mut buf)?;
+        Ok(RawData::from(&buf))
+    }
+
+    /// Reads the measurements and converts them with the current full scales:
+    /// acceleration in m/s², angular velocity in deg/s and temperature in degrees C.
+    pub fn get_measurement<V>(&mut self) -> Result<Measurement<V>, Error>
+    where
+        V: Float + FloatConst + From<i32>,
+    {
+        let accel_fs = self.get_accel_full_scale()?;
+        let gyro_fs = self.get_gyro_full_scale()?;
+        let raw = self.get_infos()?;
+        Ok(raw.to_measurement(accel_fs, gyro_fs))
+    }
+
+    /// Averages the measurements of the sensor at rest.
+    /// The sensor must not move with the `gravity` axis up until this returns.
+    pub fn calibrate(
+        &mut self,
+        gravity: Gravity,
+        samples: usize,
+        interval_ms: u8,
+        d: &mut impl DelayMs<u8>,
+    ) -> Result<Calibration, Error> {
+        if samples == 0 {
+            return Err(self.error(ErrorKind::InvalidInput));
+        }
+        let accel_fs = self.get_accel_full_scale()?;
+        let gyro_fs = self.get_gyro_full_scale()?;
+
+        let mut buf = Vec::with_capacity(samples);
+        for _ in 0..samples {
+            buf.push(self.get_infos()?);
+            d.delay_ms(interval_ms);
+        }
+        Calibration::from_samples(&buf, accel_fs, gyro_fs, gravity)
+            .ok_or_else(|| self.error(ErrorKind::InvalidInput))
+    }
+
+    /// Writes the calibration to the offset registers,
+    /// so the sensor outputs the corrected measurements by itself.
+    /// The offset registers are reset to the factory values by `reset`.
+    pub fn write_offsets(&mut self, cal: &Calibration) -> Result<(), Error> {
+        let mut accel = [0; 6];
+        self.dev.read_bytes(AccelOffset::ADDR, &mut accel)?;
+        let mut gyro = [0; 6];
+        self.dev.read_bytes(GyroOffset::ADDR, &mut gyro)?;
+
+        let (accel, gyro) = cal.to_registers(AccelOffset::from(&accel), GyroOffset::from(&gyro));
+        self.dev
+            .write_bytes(AccelOffset::ADDR, &<[u8; 6]>::from(accel))?;
+        self.dev
+            .write_bytes(GyroOffset::ADDR, &<[u8; 6]>::from(gyro))
+    }
+
+    /// Runs the factory self-test and compares the responses with the factory trims.
+    /// The sensor should be kept still while testing.
+    /// The full scales are changed during the test and restored afterwards.
+    pub fn self_test(&mut self, d: &mut impl DelayMs<u8>) -> Result<SelfTestReport, Error> {
+        let accel: AccelConfig = self.dev.read_register()?;
+        let gyro: GyroConfig = self.dev.read_register()?;
+        let result = self.run_self_test(accel, gyro, d);
+        self.dev.write_register(accel)?;
+        self.dev.write_register(gyro)?;
+        result
+    }
+
+    fn run_self_test(
+        &mut self,
+        mut accel: AccelConfig,
+        mut gyro: GyroConfig,
+        d: &mut impl DelayMs<u8>,
+    ) -> Result<SelfTestReport, Error> {
+        accel.set_scale(self_test::ACCEL_FULL_SCALE);
+        gyro.set_scale(self_test::GYRO_FULL_SCALE);
+
+        let mut measure = |mpu: &mut Self, enabled: bool| {
+            let xyz = FlagsXYZ::new(enabled, enabled, enabled);
+            accel.set_xyz(xyz);
+            gyro.set_xyz(xyz);
+            mpu.dev.write_register(accel)?;
+            mpu.dev.write_register(gyro)?;
+            // 出力が安定するまで待つ
+            d.delay_ms(250);
+            mpu.get_infos()
+        };
+        let disabled = measure(self, false)?;
+        let enabled = measure(self, true)?;
+
+        let mut buf = [0; 4];
+        self.dev.read_bytes(SelfTestCodes::ADDR, &mut buf)?;
+        Ok(SelfTestReport::new(
+            SelfTestCodes::from(&buf),
+            disabled,
+            enabled,
+        ))
+    }
+
+    pub fn get_fifo_count(&mut self) -> Result<FifoCount, Error> {
+        let mut buf = [0; 2];
+        self.dev.read_bytes(FifoCount::ADDR, &mut buf)?;
+        Ok(FifoCount::from(&buf))
+    }
+
+    /// Reads the bytes from the slave into EXT_SENS_DATA at every sample.
+    pub fn set_slave_read(&mut self, slave: Slave, read: SlaveRead) -> Result<(), Error> {
+        if !read.is_valid() {
+            return Err(self
+                .error(ErrorKind::InvalidInput)
+                .with_register(slave.base()));
+        }
+        let lens = self.lens_with(slave, read.len);
+        if ext_sens_range(&lens, Slave::Slv3).end > EXT_SENS_DATA_SIZE {
+            return Err(self
+                .error(ErrorKind::InvalidInput)
+                .with_register(slave.base()));
+        }
+        self.dev.write_bytes(slave.base(), &read.to_registers())?;
+        self.aux_slaves = lens;
+        Ok(())
+    }
+
+    pub fn disable_slave(&mut self, slave: Slave) -> Result<(), Error> {
+        self.dev
+            .write_byte(RegAddr(slave.base().0 + 2), I2cSlvCtrl::from(0).into())?;
+        self.aux_slaves = self.lens_with(slave, 0);
+        Ok(())
+    }
+
+    fn lens_with(&self, slave: Slave, len: u8) -> [u8; 4] {
+        let mut lens = self.aux_slaves;
+        lens[slave.index()] = len;
+        lens
+    }
+
+    /// The latest bytes read from the slave.
+    pub fn read_slave_data(&mut self, slave: Slave) -> Result<Vec<u8>, Error> {
+        let range = ext_sens_range(&self.aux_slaves, slave);
+        let mut buf = vec![0; range.len()];
+        if !buf.is_empty() {
+            self.dev
+                .read_bytes(RegAddr(EXT_SENS_DATA_00.0 + range.start as u8), &mut buf)?;
+        }
+        Ok(buf)
+    }
+
+    /// Reading clears the status.
+    pub fn get_i2c_master_status(&mut self) -> Result<MasterStatus, Error> {
+        let value: I2cMstStatus = self.dev.read_register()?;
+        Ok(value.into())
+    }
+
+    /// Writes a byte to the register of a slave on the auxiliary I2C bus through slave 4.
+    /// Used to configure the slave while the auxiliary I2C master is enabled.
+    pub fn write_slave_register(
+        &mut self,
+        address: I2cAddr,
+        reg: u8,
+        v: u8,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        self.slv4_transfer(address, false, [reg, v], timeout)
+    }
+
+    /// Reads a byte from the register of a slave on the auxiliary I2C bus through slave 4.
+    pub fn read_slave_register(
+        &mut self,
+        address: I2cAddr,
+        reg: u8,
+        timeout: Duration,
+    ) -> Result<u8, Error> {
+        self.slv4_transfer(address, true, [reg, 0], timeout)?;
+        let value: I2cSlv4Di = self.dev.read_register()?;
+        Ok(value.into())
+    }
+
+    fn slv4_transfer(
+        &mut self,
+        address: I2cAddr,
+        read: bool,
+        [reg, v]: [u8; 2],
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        if address.0 >= 0x80 {
+            return Err(self
+                .error(ErrorKind::InvalidInput)
+                .with_register(I2cSlv4Addr::ADDR));
+        }
+        let mut slave = I2cSlv4Addr::from(0);
+        slave.set_addr(address.0);
+        slave.set_rnw(read);
+        self.dev
+            .write_bytes(I2cSlv4Addr::ADDR, &[slave.into(), reg, v])?;
+        let mut ctrl = I2cSlv4Ctrl::from(0);
+        ctrl.set_en(true);
+        self.dev.write_register(ctrl)?;
+
+        let start = Instant::now();
+        loop {
+            let status = self.get_i2c_master_status()?;
+            if status.nack[4] {
+                return Err(self
+                    .error(ErrorKind::AuxNack)
+                    .with_register(I2cMstStatus::ADDR));
+            }
+            if status.slv4_done {
+                return Ok(());
+            }
+            if start.elapsed() >= timeout {
+                return Err(self
+                    .error(ErrorKind::Timeout)
+                    .with_register(I2cMstStatus::ADDR));
+            }
+            // 他のデバイスと共有しているバスを占有しないように待つ
+            std::thread::sleep(SLV4_POLL_INTERVAL);
+        }
+    }
+
+    fn error(&self, kind: ErrorKind) -> Error {
+        Error::new(kind, Device::Mpu6050, self.dev.address())
+    }
+}
+
+/// The methods which change a part of a register.
+/// The bus is held from the read to the write, so the bus must implement `LockBus`.
+impl<T> MPU6050<T>
+where
+    T: LockBus,
+    <T as Write>::Error: BusError,
+    <T as WriteRead>::Error: BusError,
+{
+    pub fn normal_setup(&mut self, d: &mut impl DelayMs<u8>) -> Result<(), Error> {
         self.reset(d)?;
         self.set_sleep_enabled(false)?;
         self.disable_all_interrupts()?;
@@ -40,66 +452,238 @@ where
         Ok(())
     }
 
-    pub fn reset(&mut self, d: &mut impl DelayMs<u8>) -> Result<(), Error<T>> {
-        let mut value: PwrMgmt1 = self.dev.read_register()?;
-        value.set_device_reset(true);
-        self.dev.write_register(value)?;
+    pub fn reset(&mut self, d: &mut impl DelayMs<u8>) -> Result<(), Error> {
+        self.dev
+            .modify_register(|r: &mut PwrMgmt1| r.set_device_reset(true))?;
+        self.dev.invalidate_cache();
+        self.fifo = None;
+        self.int_pin = IntPinConfig::default();
+        self.accel_fs = Some(AccelFullScale::G2);
+        self.gyro_fs = Some(GyroFullScale::Deg250);
+        self.aux_slaves = [0; 4];
         d.delay_ms(200);
         Ok(())
     }
 
-    pub fn set_sleep_enabled(&mut self, v: bool) -> Result<(), Error<T>> {
-        let mut value: PwrMgmt1 = self.dev.read_register()?;
-        value.set_sleep(v);
-        self.dev.write_register(value)
+    pub fn set_sleep_enabled(&mut self, v: bool) -> Result<(), Error> {
+        self.dev
+            .modify_register(|r: &mut PwrMgmt1| r.set_sleep(v))?;
+        Ok(())
     }
 
-    pub fn reset_signal_path(&mut self, d: &mut impl DelayMs<u8>) -> Result<(), Error<T>> {
-        let mut value: UserCtrl = self.dev.read_register()?;
-        value.set_sigcond_reset(true);
+    /// Puts the axes into standby mode and wakes up the others.
+    /// The wake-up frequency of the low power mode is kept.
+    pub fn set_standby(&mut self, standby: Standby) -> Result<(), Error> {
+        self.dev.modify_register(|r: &mut PwrMgmt2| {
+            let wake = r.get_lp_wake_ctrl();
+            *r = standby.into();
+            r.set_lp_wake_ctrl(wake);
+        })?;
+        Ok(())
+    }
+
+    /// Enters Accelerometer Only Low Power Mode.
+    /// The device sleeps and wakes up at the frequency only to take a sample of the accelerometer.
+    /// The gyroscope is put into standby mode and the temperature sensor is disabled.
+    pub fn enter_cycle_mode(&mut self, freq: WakeFrequency) -> Result<(), Error> {
+        let mut value: PwrMgmt2 = Standby::gyro().into();
+        value.set_lp_wake_ctrl(freq);
         self.dev.write_register(value)?;
-        d.delay_ms(200);
+
+        self.dev.modify_register(|r: &mut PwrMgmt1| {
+            r.set_sleep(false);
+            r.set_cycle(true);
+            r.set_tempdis(true);
+        })?;
         Ok(())
     }
 
-    pub fn disable_all_interrupts(&mut self) -> Result<(), Error<T>> {
-        self.dev.write_register(IntEnable::from(0))
+    /// Leaves the low power mode and wakes up all of the sensors.
+    pub fn exit_cycle_mode(&mut self) -> Result<(), Error> {
+        self.dev.modify_register(|r: &mut PwrMgmt1| {
+            r.set_cycle(false);
+            r.set_tempdis(false);
+        })?;
+        self.set_standby(Standby::default())
     }
 
-    pub fn set_clock_source(&mut self, v: ClockSel) -> Result<(), Error<T>> {
-        let mut value: PwrMgmt1 = self.dev.read_register()?;
-        value.set_clksel(v);
-        self.dev.write_register(value)
+    /// Sleeps in the low power mode until motion is detected.
+    /// Only the motion interrupt is enabled, so the INT pin tells when the device is moved.
+    ///
+    /// The high pass filter of the accelerometer is set to 5Hz, so the motion detector
+    /// responds to changes of the acceleration and not to the gravity.
+    pub fn enable_wake_on_motion(
+        &mut self,
+        freq: WakeFrequency,
+        motion: MotionDetection,
+    ) -> Result<(), Error> {
+        self.dev
+            .modify_register(|r: &mut AccelConfig| r.set_hpf(AccelHighPassFilter::Hz5))?;
+        self.set_motion_detection(motion)?;
+        self.enable_interrupts(Interrupts {
+            motion: true,
+            ..Interrupts::default()
+        })?;
+        self.enter_cycle_mode(freq)
     }
 
-    pub fn set_sample_rate_divider(&mut self, v: SampleRateDivider) -> Result<(), Error<T>> {
-        self.dev.write_register(v)
+    pub fn reset_signal_path(&mut self, d: &mut impl DelayMs<u8>) -> Result<(), Error> {
+        self.dev
+            .modify_register(|r: &mut UserCtrl| r.set_sigcond_reset(true))?;
+        d.delay_ms(200);
+        Ok(())
+    }
+
+    /// Configures the INT pin.
+    /// The FSYNC and bypass settings in the same register are kept.
+    pub fn set_interrupt_pin(&mut self, config: IntPinConfig) -> Result<(), Error> {
+        self.dev
+            .modify_register(|r: &mut IntPinCfg| config.apply(r))?;
+        self.int_pin = config;
+        Ok(())
+    }
+
+    pub fn set_clock_source(&mut self, v: ClockSel) -> Result<(), Error> {
+        self.dev
+            .modify_register(|r: &mut PwrMgmt1| r.set_clksel(v))?;
+        Ok(())
     }
 
     pub fn set_digital_lowpass_filter(
         &mut self,
         filter: DigitalLowPassFilterCfg,
-    ) -> Result<(), Error<T>> {
-        let mut value: Configure = self.dev.read_register()?;
-        value.set_dlpf(filter);
-        self.dev.write_register(value)
+    ) -> Result<(), Error> {
+        self.dev
+            .modify_register(|r: &mut Configure| r.set_dlpf(filter))?;
+        Ok(())
+    }
+
+    pub fn set_accel_full_scale(&mut self, scale: AccelFullScale) -> Result<(), Error> {
+        self.dev
+            .modify_register(|r: &mut AccelConfig| r.set_scale(scale))?;
+        self.accel_fs = Some(scale);
+        Ok(())
     }
 
-    pub fn set_accel_full_scale(&mut self, scale: AccelFullScale) -> Result<(), Error<T>> {
-        let mut value: AccelConfig = self.dev.read_register()?;
-        value.set_scale(scale);
-        self.dev.write_register(value)
+    pub fn set_gyro_full_scale(&mut self, scale: GyroFullScale) -> Result<(), Error> {
+        self.dev
+            .modify_register(|r: &mut GyroConfig| r.set_scale(scale))?;
+        self.gyro_fs = Some(scale);
+        Ok(())
     }
 
-    pub fn set_gyro_full_scale(&mut self, scale: GyroFullScale) -> Result<(), Error<T>> {
-        let mut value: GyroConfig = self.dev.read_register()?;
-        value.set_scale(scale);
-        self.dev.write_register(value)
+    /// Starts loading the sensor measurements into the FIFO buffer.
+    /// The FIFO buffer is emptied.
+    /// The slaves must be read by the auxiliary I2C master with the same lengths.
+    pub fn enable_fifo(&mut self, sensors: FifoSensors) -> Result<(), Error> {
+        let slaves_match = sensors
+            .slaves
+            .iter()
+            .zip(self.aux_slaves.iter())
+            .all(|(f, a)| *f == 0 || f == a);
+        if sensors.is_empty() || !slaves_match {
+            return Err(self
+                .error(ErrorKind::InvalidInput)
+                .with_register(FifoEnable::ADDR));
+        }
+        self.dev.write_register(FifoEnable::from(sensors))?;
+        self.set_slv3_fifo_enabled(sensors.slaves[3] > 0)?;
+        self.fifo = Some(sensors);
+        self.reset_fifo()
     }
 
-    pub fn get_infos(&mut self) -> Result<RawData, Error<T>> {
-        let mut buf = [0; 14];
-        self.dev.read_bytes(AccelData::ADDR, &mut buf)?;
-        Ok(RawData::from(&buf))
+    pub fn disable_fifo(&mut self) -> Result<(), Error> {
+        self.dev
+            .modify_register(|r: &mut UserCtrl| r.set_fifo_en(false))?;
+        self.dev.write_register(FifoEnable::from(0))?;
+        self.set_slv3_fifo_enabled(false)?;
+        self.fifo = None;
+        Ok(())
+    }
+
+    /// SLV_3_FIFO_EN is in I2C_MST_CTRL, not in FIFO_EN.
+    /// It is written only when it changes, so the FIFO of the other sensors costs no more access.
+    fn set_slv3_fifo_enabled(&mut self, v: bool) -> Result<(), Error> {
+        let current = self.fifo.map(|f| f.slaves[3] > 0).unwrap_or(false);
+        if v == current {
+            return Ok(());
+        }
+        self.dev
+            .modify_register(|r: &mut I2cMstCtrl| r.set_slv3_fifo_en(v))?;
+        Ok(())
+    }
+
+    /// Discards the contents of the FIFO buffer.
+    pub fn reset_fifo(&mut self) -> Result<(), Error> {
+        let mut value = self.dev.modify_register(|r: &mut UserCtrl| {
+            r.set_fifo_en(false);
+            r.set_fifo_reset(true);
+        })?;
+        if self.fifo.is_some() {
+            value.set_fifo_reset(false);
+            value.set_fifo_en(true);
+            self.dev.write_register(value)?;
+        }
+        Ok(())
+    }
+
+    /// Reads all complete frames in the FIFO buffer.
+    /// An incomplete frame is left in the FIFO buffer for the next call.
+    ///
+    /// When the FIFO buffer is full, samples have been lost and the rest is not aligned to frames.
+    /// Then the FIFO buffer is emptied and `ErrorKind::FifoOverflow` is returned.
+    /// Calling this again continues to read from the new samples.
+    pub fn read_fifo(&mut self) -> Result<Vec<FifoFrame>, Error> {
+        let sensors = self.fifo.ok_or_else(|| {
+            self.error(ErrorKind::InvalidInput)
+                .with_register(FifoData::ADDR)
+        })?;
+
+        let count: u16 = self.get_fifo_count()?.into();
+        if count >= FIFO_SIZE {
+            self.reset_fifo()?;
+            return Err(self
+                .error(ErrorKind::FifoOverflow)
+                .with_register(FifoCount::ADDR));
+        }
+
+        let size = sensors.frame_size();
+        let mut buf = vec![0; (count as usize) / size * size];
+        if !buf.is_empty() {
+            self.dev.read_bytes(FifoData::ADDR, &mut buf)?;
+        }
+        Ok(sensors.parse(&buf))
+    }
+
+    /// Lets the host access the auxiliary I2C bus directly, as if the slaves were on the primary bus.
+    /// The auxiliary I2C master is disabled while bypassing.
+    pub fn set_bypass_enabled(&mut self, v: bool) -> Result<(), Error> {
+        if v {
+            self.set_i2c_master_enabled(false)?;
+        }
+        self.dev
+            .modify_register(|r: &mut IntPinCfg| r.set_i2c_bypass_en(v))?;
+        Ok(())
+    }
+
+    /// Starts the auxiliary I2C master, which reads the slaves at every sample.
+    /// The data ready interrupt waits for the data of the slaves.
+    pub fn enable_i2c_master(&mut self, clock: MasterClock) -> Result<(), Error> {
+        self.set_bypass_enabled(false)?;
+        self.dev.modify_register(|r: &mut I2cMstCtrl| {
+            r.set_clk(clock);
+            r.set_wait_for_es(true);
+        })?;
+        self.set_i2c_master_enabled(true)
+    }
+
+    pub fn disable_i2c_master(&mut self) -> Result<(), Error> {
+        self.set_i2c_master_enabled(false)
+    }
+
+    fn set_i2c_master_enabled(&mut self, v: bool) -> Result<(), Error> {
+        self.dev
+            .modify_register(|r: &mut UserCtrl| r.set_i2cmst_en(v))?;
+        Ok(())
     }
 }
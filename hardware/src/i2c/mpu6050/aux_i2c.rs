@@ -0,0 +1,157 @@
+use super::register::*;
+use crate::i2c::{I2cAddr, RegAddr, Register};
+
+use derive_more::Constructor;
+use num_derive::FromPrimitive;
+
+/// The number of EXT_SENS_DATA registers.
+/// The data read from the slaves are stored in them in the order of the slaves.
+pub const EXT_SENS_DATA_SIZE: usize = 24;
+
+/// The maximum number of bytes read from a slave at every sample.
+pub const MAX_SLAVE_LEN: u8 = 15;
+
+/// The slaves sampled periodically by the auxiliary I2C master.
+/// Slave 4 is reserved for single byte transfers.
+#[derive(Debug, Clone, Copy, FromPrimitive, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Slave {
+    Slv0 = 0,
+    Slv1 = 1,
+    Slv2 = 2,
+    Slv3 = 3,
+}
+
+impl Slave {
+    pub fn all() -> [Slave; 4] {
+        [Slave::Slv0, Slave::Slv1, Slave::Slv2, Slave::Slv3]
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// I2C_SLVx_ADDR, which is followed by I2C_SLVx_REG and I2C_SLVx_CTRL.
+    pub(super) fn base(&self) -> RegAddr {
+        RegAddr(I2cSlv0Addr::ADDR.0 + 3 * *self as u8)
+    }
+}
+
+/// The clock speed of the auxiliary I2C bus (I2C_MST_CLK).
+/// The dividers are of the 8MHz internal clock.
+#[derive(Debug, Clone, Copy, FromPrimitive, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum MasterClock {
+    Khz348 = 0,
+    Khz333 = 1,
+    Khz320 = 2,
+    Khz308 = 3,
+    Khz296 = 4,
+    Khz286 = 5,
+    Khz276 = 6,
+    Khz267 = 7,
+    Khz258 = 8,
+    Khz500 = 9,
+    Khz471 = 10,
+    Khz444 = 11,
+    Khz421 = 12,
+    Khz400 = 13,
+    Khz381 = 14,
+    Khz364 = 15,
+}
+
+/// Bytes read from a slave at every sample, starting at the register.
+#[derive(Debug, Constructor, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SlaveRead {
+    pub address: I2cAddr,
+    pub register: u8,
+    /// From 1 to `MAX_SLAVE_LEN`.
+    pub len: u8,
+}
+
+impl SlaveRead {
+    pub fn is_valid(&self) -> bool {
+        self.address.0 < 0x80 && 0 < self.len && self.len <= MAX_SLAVE_LEN
+    }
+
+    /// The values of I2C_SLVx_ADDR, I2C_SLVx_REG and I2C_SLVx_CTRL.
+    pub(super) fn to_registers(self) -> [u8; 3] {
+        let mut ctrl = I2cSlvCtrl::from(0);
+        ctrl.set_en(true);
+        ctrl.set_len(self.len);
+        [0x80 | self.address.0, self.register, ctrl.into()]
+    }
+}
+
+/// Status of the auxiliary I2C master.
+/// The bits are cleared by reading them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MasterStatus {
+    /// The slaves which did not acknowledge, in the order of slave 0 to 4.
+    pub nack: [bool; 5],
+    pub lost_arbitration: bool,
+    /// The single byte transfer of slave 4 has completed.
+    pub slv4_done: bool,
+    /// The FSYNC pin, when it is used as an interrupt.
+    pub pass_through: bool,
+}
+
+impl From<I2cMstStatus> for MasterStatus {
+    fn from(reg: I2cMstStatus) -> Self {
+        Self {
+            nack: [
+                reg.get_slv0_nack(),
+                reg.get_slv1_nack(),
+                reg.get_slv2_nack(),
+                reg.get_slv3_nack(),
+                reg.get_slv4_nack(),
+            ],
+            lost_arbitration: reg.get_lost_arb(),
+            slv4_done: reg.get_slv4_done(),
+            pass_through: reg.get_pass_through(),
+        }
+    }
+}
+
+/// Where the data of the slave are stored in EXT_SENS_DATA,
+/// given the number of bytes read from each slave.
+pub(super) fn ext_sens_range(lens: &[u8; 4], slave: Slave) -> std::ops::Range<usize> {
+    let i = slave.index();
+    let start = lens[..i].iter().map(|n| *n as usize).sum();
+    start..start + lens[i] as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slave_registers() {
+        let read = SlaveRead::new(I2cAddr(0x1e), 0x03, 6);
+        assert!(read.is_valid());
+        assert_eq!(read.to_registers(), [0x9e, 0x03, 0b_1000_0110]);
+        assert_eq!(Slave::Slv2.base(), RegAddr(0x2b));
+
+        assert!(!SlaveRead::new(I2cAddr(0x1e), 0x03, 0).is_valid());
+        assert!(!SlaveRead::new(I2cAddr(0x1e), 0x03, 16).is_valid());
+        assert!(!SlaveRead::new(I2cAddr(0x80), 0x03, 1).is_valid());
+    }
+
+    #[test]
+    fn ext_sens_ranges() {
+        let lens = [6, 0, 2, 1];
+        assert_eq!(ext_sens_range(&lens, Slave::Slv0), 0..6);
+        assert_eq!(ext_sens_range(&lens, Slave::Slv1), 6..6);
+        assert_eq!(ext_sens_range(&lens, Slave::Slv2), 6..8);
+        assert_eq!(ext_sens_range(&lens, Slave::Slv3), 8..9);
+    }
+
+    #[test]
+    fn status() {
+        let status = MasterStatus::from(I2cMstStatus::from(0b_0101_0001));
+        assert_eq!(status.nack, [true, false, false, false, true]);
+        assert!(status.slv4_done);
+        assert!(!status.lost_arbitration);
+        assert!(!status.pass_through);
+    }
+}
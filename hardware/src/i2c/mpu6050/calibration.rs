@@ -0,0 +1,492 @@
+use super::raw_data::*;
+use super::register::*;
+use crate::model::Celsius;
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+/// The axis pointing up while the samples for the calibration are taken.
+/// The accelerometer reads +1g on it at rest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Gravity {
+    XUp,
+    XDown,
+    YUp,
+    YDown,
+    ZUp,
+    ZDown,
+}
+
+impl Gravity {
+    /// Accelerometer output at rest in g.
+    pub fn expected(&self) -> [f64; 3] {
+        match self {
+            Gravity::XUp => [1.0, 0.0, 0.0],
+            Gravity::XDown => [-1.0, 0.0, 0.0],
+            Gravity::YUp => [0.0, 1.0, 0.0],
+            Gravity::YDown => [0.0, -1.0, 0.0],
+            Gravity::ZUp => [0.0, 0.0, 1.0],
+            Gravity::ZDown => [0.0, 0.0, -1.0],
+        }
+    }
+}
+
+/// Errors of the sensors measured at rest.
+/// They are kept in physical units, so they do not depend on the full scale.
+///
+/// The offsets are either written to the offset registers by `MPU6050::write_offsets`,
+/// or subtracted from the measurements by `apply`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Calibration {
+    /// Gyroscope output at rest in deg/s.
+    pub gyro_bias: [f64; 3],
+    /// Accelerometer output at rest minus the gravity in g.
+    pub accel_offset: [f64; 3],
+    /// Mean temperature in degrees C while the samples were taken.
+    #[serde(default)]
+    pub temp: Option<f64>,
+}
+
+impl Calibration {
+    /// Averages the samples taken at rest.
+    /// Returns `None` if there is no sample.
+    pub fn from_samples(
+        samples: &[RawData],
+        accel_fs: AccelFullScale,
+        gyro_fs: GyroFullScale,
+        gravity: Gravity,
+    ) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mean = |f: fn(&RawData) -> i16| {
+            let sum: i64 = samples.iter().map(|s| f(s) as i64).sum();
+            sum as f64 / samples.len() as f64
+        };
+        let accel_unit = unit(&accel_fs);
+        let gyro_unit = unit(&gyro_fs);
+        let [gx, gy, gz] = gravity.expected();
+        let temp: Celsius<f64> = Temperature::from(mean(|s| s.temp.into()).round() as i16).into();
+        Some(Self {
+            gyro_bias: [
+                mean(|s| s.gyro.x) * gyro_unit,
+                mean(|s| s.gyro.y) * gyro_unit,
+                mean(|s| s.gyro.z) * gyro_unit,
+            ],
+            accel_offset: [
+                mean(|s| s.accel.x) * accel_unit - gx,
+                mean(|s| s.accel.y) * accel_unit - gy,
+                mean(|s| s.accel.z) * accel_unit - gz,
+            ],
+            temp: Some(temp.into()),
+        })
+    }
+
+    /// Replaces the gyro bias with the one estimated by the model at the temperature.
+    /// The calibration is returned as it is if the model is empty.
+    pub fn compensated(&self, model: &GyroBiasModel, temp: Celsius<f64>) -> Self {
+        match model.bias(temp) {
+            Some(gyro_bias) => Self {
+                gyro_bias,
+                temp: Some(temp.into()),
+                ..*self
+            },
+            None => *self,
+        }
+    }
+
+    /// Subtracts the offsets from the measurements read at the full scales.
+    pub fn apply(
+        &self,
+        raw: &RawData,
+        accel_fs: AccelFullScale,
+        gyro_fs: GyroFullScale,
+    ) -> RawData {
+        let [ax, ay, az] = lsb(self.accel_offset, unit(&accel_fs));
+        let [gx, gy, gz] = lsb(self.gyro_bias, unit(&gyro_fs));
+        RawData {
+            temp: raw.temp,
+            accel: AccelData {
+                x: raw.accel.x.saturating_sub(ax),
+                y: raw.accel.y.saturating_sub(ay),
+                z: raw.accel.z.saturating_sub(az),
+            },
+            gyro: GyroData {
+                x: raw.gyro.x.saturating_sub(gx),
+                y: raw.gyro.y.saturating_sub(gy),
+                z: raw.gyro.z.saturating_sub(gz),
+            },
+        }
+    }
+
+    /// New values of the offset registers which cancel these offsets.
+    /// The offsets are measured with the current values of the registers,
+    /// so they are subtracted from them.
+    pub(super) fn to_registers(
+        self,
+        accel: AccelOffset,
+        gyro: GyroOffset,
+    ) -> (AccelOffset, GyroOffset) {
+        let [ax, ay, az] = lsb(self.accel_offset, unit(&AccelFullScale::G16));
+        let [gx, gy, gz] = lsb(self.gyro_bias, unit(&GyroFullScale::Deg1000));
+        // 加速度のオフセットは bit 0 が予約されているので元の値を残す
+        let keep_bit0 = |cur: i16, v: i16| (cur.saturating_sub(v) & !1) | (cur & 1);
+        let accel = AccelOffset {
+            x: keep_bit0(accel.x, ax),
+            y: keep_bit0(accel.y, ay),
+            z: keep_bit0(accel.z, az),
+        };
+        let gyro = GyroOffset {
+            x: gyro.x.saturating_sub(gx),
+            y: gyro.y.saturating_sub(gy),
+            z: gyro.z.saturating_sub(gz),
+        };
+        (accel, gyro)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let text = toml::to_string(self).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let text = fs::read_to_string(path)?;
+        toml::from_str(&text).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+/// Gyro bias at a temperature.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BiasPoint {
+    /// Temperature in degrees C.
+    pub temp: f64,
+    /// Gyroscope output at rest in deg/s.
+    pub gyro_bias: [f64; 3],
+}
+
+/// Gyro bias changing with the temperature of the sensor.
+/// The bias is linearly interpolated between the points,
+/// and the nearest point is used out of their range.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GyroBiasModel {
+    points: Vec<BiasPoint>,
+}
+
+impl GyroBiasModel {
+    /// Builds the model from the calibrations logged at various temperatures.
+    /// Calibrations within 1 degree C are averaged into a point.
+    /// Calibrations without the temperature are ignored.
+    pub fn fit<'a, I>(runs: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Calibration>,
+    {
+        let mut runs: Vec<BiasPoint> = runs
+            .into_iter()
+            .filter_map(|c| {
+                c.temp.map(|temp| BiasPoint {
+                    temp,
+                    gyro_bias: c.gyro_bias,
+                })
+            })
+            .collect();
+        runs.sort_by(|a, b| a.temp.total_cmp(&b.temp));
+
+        let mut points = vec![];
+        let mut group: Vec<BiasPoint> = vec![];
+        for p in runs {
+            if let Some(first) = group.first() {
+                if p.temp - first.temp >= 1.0 {
+                    points.push(average(&group));
+                    group.clear();
+                }
+            }
+            group.push(p);
+        }
+        if !group.is_empty() {
+            points.push(average(&group));
+        }
+        Self { points }
+    }
+
+    pub fn points(&self) -> &[BiasPoint] {
+        &self.points
+    }
+
+    /// Gyro bias in deg/s at the temperature.
+    /// Returns `None` if the model has no point.
+    pub fn bias(&self, temp: Celsius<f64>) -> Option<[f64; 3]> {
+        let t: f64 = temp.into();
+        let first = self.points.first()?;
+        let last = self.points.last()?;
+        if t <= first.temp {
+            return Some(first.gyro_bias);
+        }
+        if t >= last.temp {
+            return Some(last.gyro_bias);
+        }
+        let (a, b) = self
+            .points
+            .windows(2)
+            .map(|w| (&w[0], &w[1]))
+            .find(|(_, b)| t < b.temp)?;
+        let r = (t - a.temp) / (b.temp - a.temp);
+        let mut bias = a.gyro_bias;
+        for (v, (x, y)) in bias.iter_mut().zip(a.gyro_bias.iter().zip(&b.gyro_bias)) {
+            *v = x + (y - x) * r;
+        }
+        Some(bias)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let text = toml::to_string(self).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let text = fs::read_to_string(path)?;
+        toml::from_str(&text).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+fn average(points: &[BiasPoint]) -> BiasPoint {
+    let n = points.len() as f64;
+    let mut avg = BiasPoint {
+        temp: 0.0,
+        gyro_bias: [0.0; 3],
+    };
+    for p in points {
+        avg.temp += p.temp / n;
+        for (v, b) in avg.gyro_bias.iter_mut().zip(&p.gyro_bias) {
+            *v += b / n;
+        }
+    }
+    avg
+}
+
+/// The physical value of 1 LSB at the full scale.
+fn unit<F: FullScale>(fs: &F) -> f64 {
+    (fs.max() << 1) as f64 / F::RESOLUTION as f64
+}
+
+fn lsb(v: [f64; 3], unit: f64) -> [i16; 3] {
+    // `as` は範囲外の値を飽和させる
+    v.map(|a| (a / unit).round() as i16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::*;
+
+    fn sample(accel: (i16, i16, i16), gyro: (i16, i16, i16)) -> RawData {
+        RawData {
+            temp: Temperature::from(0),
+            accel: AccelData {
+                x: accel.0,
+                y: accel.1,
+                z: accel.2,
+            },
+            gyro: GyroData {
+                x: gyro.0,
+                y: gyro.1,
+                z: gyro.2,
+            },
+        }
+    }
+
+    #[test]
+    fn from_samples() {
+        // ±2g: 16375 LSB/g, ±250dps: 131 LSB/dps
+        let samples = [
+            sample((100, -200, 16375 + 50), (131, 0, -262)),
+            sample((300, -400, 16375 + 150), (393, 0, -262)),
+        ];
+        let cal = Calibration::from_samples(
+            &samples,
+            AccelFullScale::G2,
+            GyroFullScale::Deg250,
+            Gravity::ZUp,
+        )
+        .expect("There are samples");
+
+        assert_relative_eq!(cal.gyro_bias[0], 2.0, epsilon = 1e-3);
+        assert_relative_eq!(cal.gyro_bias[1], 0.0);
+        assert_relative_eq!(cal.gyro_bias[2], -2.0, epsilon = 1e-3);
+        assert_relative_eq!(cal.accel_offset[0], 200.0 / 16375.0);
+        assert_relative_eq!(cal.accel_offset[1], -300.0 / 16375.0);
+        assert_relative_eq!(cal.accel_offset[2], 100.0 / 16375.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn from_samples_temperature() {
+        let mut samples = [sample((0, 0, 0), (0, 0, 0)); 2];
+        samples[0].temp = Temperature::from(340);
+        samples[1].temp = Temperature::from(-340);
+        let cal = Calibration::from_samples(
+            &samples,
+            AccelFullScale::G2,
+            GyroFullScale::Deg250,
+            Gravity::ZUp,
+        )
+        .unwrap();
+        assert_relative_eq!(cal.temp.unwrap(), 36.53);
+    }
+
+    #[test]
+    fn no_samples() {
+        let cal =
+            Calibration::from_samples(&[], AccelFullScale::G2, GyroFullScale::Deg250, Gravity::ZUp);
+        assert_eq!(cal, None);
+    }
+
+    #[test]
+    fn apply_cancels_offsets() {
+        let raw = sample((200, -300, 16375 + 100), (262, 5, -262));
+        let cal = Calibration::from_samples(
+            &[raw],
+            AccelFullScale::G2,
+            GyroFullScale::Deg250,
+            Gravity::ZUp,
+        )
+        .unwrap();
+
+        let fixed = cal.apply(&raw, AccelFullScale::G2, GyroFullScale::Deg250);
+        assert_eq!(
+            fixed.accel,
+            AccelData {
+                x: 0,
+                y: 0,
+                z: 16375
+            }
+        );
+        assert_eq!(fixed.gyro, GyroData { x: 0, y: 0, z: 0 });
+
+        // ±4g, ±500dps では同じオフセットが半分の LSB になる
+        let fixed = cal.apply(&raw, AccelFullScale::G4, GyroFullScale::Deg500);
+        assert_eq!(fixed.accel.x, 100);
+        assert_eq!(fixed.gyro.x, 131);
+    }
+
+    #[test]
+    fn to_registers_keeps_bit0() {
+        let cal = Calibration {
+            gyro_bias: [1.0, -1.0, 0.0],
+            accel_offset: [10.0 / 2046.875, 0.0, -20.0 / 2046.875],
+            temp: None,
+        };
+        let accel = AccelOffset {
+            x: -1001,
+            y: 500,
+            z: 1234,
+        };
+        let gyro = GyroOffset { x: 0, y: 10, z: 0 };
+        let (accel, gyro) = cal.to_registers(accel, gyro);
+
+        assert_eq!(
+            accel,
+            AccelOffset {
+                x: -1011,
+                y: 500,
+                z: 1254
+            }
+        );
+        assert_eq!(
+            gyro,
+            GyroOffset {
+                x: -33,
+                y: 43,
+                z: 0
+            }
+        );
+    }
+
+    #[test]
+    fn save_and_load() {
+        let cal = Calibration {
+            gyro_bias: [0.5, -1.25, 2.0],
+            accel_offset: [0.01, 0.0, -0.02],
+            temp: Some(30.5),
+        };
+        let path = std::env::temp_dir().join(format!("mpu6050-cal-{}.toml", std::process::id()));
+        cal.save(&path).unwrap();
+        let loaded = Calibration::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, cal);
+    }
+
+    fn run(temp: f64, gyro_bias: [f64; 3]) -> Calibration {
+        Calibration {
+            gyro_bias,
+            temp: Some(temp),
+            ..Calibration::default()
+        }
+    }
+
+    #[test]
+    fn bias_model_fit() {
+        let runs = [
+            run(40.0, [4.0, 0.0, 0.0]),
+            run(20.0, [1.0, 1.0, 0.0]),
+            run(20.5, [3.0, 1.0, 0.0]),
+            Calibration::default(),
+        ];
+        let model = GyroBiasModel::fit(&runs);
+        assert_eq!(
+            model.points(),
+            &[
+                BiasPoint {
+                    temp: 20.25,
+                    gyro_bias: [2.0, 1.0, 0.0]
+                },
+                BiasPoint {
+                    temp: 40.0,
+                    gyro_bias: [4.0, 0.0, 0.0]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn bias_model_interpolates() {
+        let model = GyroBiasModel::fit(&[
+            run(20.0, [0.0, 2.0, -1.0]),
+            run(30.0, [1.0, 4.0, -1.0]),
+            run(40.0, [3.0, 0.0, -1.0]),
+        ]);
+        let bias = |t: f64| model.bias(t.into()).unwrap();
+        assert_eq!(bias(10.0), [0.0, 2.0, -1.0]);
+        assert_eq!(bias(25.0), [0.5, 3.0, -1.0]);
+        assert_eq!(bias(30.0), [1.0, 4.0, -1.0]);
+        assert_eq!(bias(35.0), [2.0, 2.0, -1.0]);
+        assert_eq!(bias(50.0), [3.0, 0.0, -1.0]);
+        assert_eq!(GyroBiasModel::default().bias(25.0.into()), None);
+    }
+
+    #[test]
+    fn compensated() {
+        let model = GyroBiasModel::fit(&[run(20.0, [0.0; 3]), run(40.0, [2.0; 3])]);
+        let cal = Calibration {
+            accel_offset: [0.1; 3],
+            ..run(20.0, [5.0; 3])
+        };
+        let c = cal.compensated(&model, 30.0.into());
+        assert_eq!(c.gyro_bias, [1.0; 3]);
+        assert_eq!(c.accel_offset, [0.1; 3]);
+        assert_eq!(c.temp, Some(30.0));
+
+        let c = cal.compensated(&GyroBiasModel::default(), 30.0.into());
+        assert_eq!(c, cal);
+    }
+
+    #[test]
+    fn bias_model_save_and_load() {
+        let model = GyroBiasModel::fit(&[run(20.0, [0.5; 3]), run(40.0, [2.0; 3])]);
+        let path = std::env::temp_dir().join(format!("mpu6050-bias-{}.toml", std::process::id()));
+        model.save(&path).unwrap();
+        let loaded = GyroBiasModel::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, model);
+    }
+}
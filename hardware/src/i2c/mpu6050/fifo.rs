@@ -0,0 +1,221 @@
+use super::aux_i2c::Slave;
+use super::raw_data::*;
+use super::register::*;
+
+use std::convert::TryInto;
+
+/// The size of the FIFO buffer in bytes.
+pub const FIFO_SIZE: u16 = 1024;
+
+/// Sensor measurements loaded into the FIFO buffer at every sample.
+/// The measurements are written in the order of the sensor registers:
+/// accelerometer, temperature, gyroscope and the slaves of the auxiliary I2C master.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FifoSensors {
+    pub accel: bool,
+    pub temp: bool,
+    pub gyro: bool,
+    /// Number of bytes of EXT_SENS_DATA loaded for each of slave 0 to 3. 0 is not loaded.
+    /// It must be the length read from the slave.
+    pub slaves: [u8; 4],
+}
+
+impl FifoSensors {
+    pub fn new(accel: bool, temp: bool, gyro: bool) -> Self {
+        Self {
+            accel,
+            temp,
+            gyro,
+            slaves: [0; 4],
+        }
+    }
+
+    pub fn all() -> Self {
+        Self::new(true, true, true)
+    }
+
+    /// Also loads the bytes read from the slave.
+    pub fn with_slave(mut self, slave: Slave, len: u8) -> Self {
+        self.slaves[slave.index()] = len;
+        self
+    }
+
+    /// Number of bytes written to the FIFO buffer at every sample.
+    pub fn frame_size(&self) -> usize {
+        let size = |b: bool, n: usize| if b { n } else { 0 };
+        size(self.accel, 6) + size(self.temp, 2) + size(self.gyro, 6) + self.external_size()
+    }
+
+    fn external_size(&self) -> usize {
+        self.slaves.iter().map(|n| *n as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frame_size() == 0
+    }
+
+    /// Splits the bytes read from the FIFO buffer into frames.
+    /// Trailing bytes which do not fill a frame are ignored.
+    pub fn parse(&self, buf: &[u8]) -> Vec<FifoFrame> {
+        let size = self.frame_size();
+        if size == 0 {
+            return vec![];
+        }
+        buf.chunks_exact(size)
+            .map(|c| self.parse_frame(c))
+            .collect()
+    }
+
+    fn parse_frame(&self, mut buf: &[u8]) -> FifoFrame {
+        let mut take = |b: bool, n: usize| {
+            if b {
+                let (head, tail) = buf.split_at(n);
+                buf = tail;
+                Some(head)
+            } else {
+                None
+            }
+        };
+        let accel = take(self.accel, 6);
+        let temp = take(self.temp, 2);
+        let gyro = take(self.gyro, 6);
+        FifoFrame {
+            accel: accel.map(|b| AccelData::from(as_array(b))),
+            temp: temp.map(|b| Temperature::from(as_array(b))),
+            gyro: gyro.map(|b| GyroData::from(as_array(b))),
+            external: buf.to_vec(),
+        }
+    }
+}
+
+impl From<FifoSensors> for FifoEnable {
+    fn from(sensors: FifoSensors) -> Self {
+        let mut v = FifoEnable::from(0);
+        v.set_accel(sensors.accel);
+        v.set_temp(sensors.temp);
+        v.set_xg(sensors.gyro);
+        v.set_yg(sensors.gyro);
+        v.set_zg(sensors.gyro);
+        v.set_slv0(sensors.slaves[0] > 0);
+        v.set_slv1(sensors.slaves[1] > 0);
+        v.set_slv2(sensors.slaves[2] > 0);
+        v
+    }
+}
+
+/// Measurements of one sample read from the FIFO buffer.
+/// Sensors which are not loaded into the FIFO buffer are `None`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FifoFrame {
+    pub accel: Option<AccelData>,
+    pub temp: Option<Temperature>,
+    pub gyro: Option<GyroData>,
+    /// The bytes read from the slaves in the order of slave 0 to 3. Empty if none is loaded.
+    pub external: Vec<u8>,
+}
+
+impl FifoFrame {
+    /// Available only when all of the sensors are loaded into the FIFO buffer.
+    pub fn raw_data(&self) -> Option<RawData> {
+        Some(RawData {
+            temp: self.temp?,
+            gyro: self.gyro?,
+            accel: self.accel?,
+        })
+    }
+}
+
+fn as_array<const N: usize>(buf: &[u8]) -> &[u8; N] {
+    buf.try_into().expect("The length of the slice must be N")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_size() {
+        assert_eq!(FifoSensors::all().frame_size(), 14);
+        assert_eq!(FifoSensors::new(true, false, false).frame_size(), 6);
+        assert_eq!(FifoSensors::new(false, true, false).frame_size(), 2);
+        assert_eq!(FifoSensors::new(false, false, true).frame_size(), 6);
+        assert_eq!(FifoSensors::new(true, false, true).frame_size(), 12);
+        assert!(FifoSensors::new(false, false, false).is_empty());
+    }
+
+    #[test]
+    fn to_register() {
+        let all: u8 = FifoEnable::from(FifoSensors::all()).into();
+        assert_eq!(all, 0b_1111_1000);
+        let accel: u8 = FifoEnable::from(FifoSensors::new(true, false, false)).into();
+        assert_eq!(accel, 0b_0000_1000);
+        let gyro: u8 = FifoEnable::from(FifoSensors::new(false, false, true)).into();
+        assert_eq!(gyro, 0b_0111_0000);
+        let temp: u8 = FifoEnable::from(FifoSensors::new(false, true, false)).into();
+        assert_eq!(temp, 0b_1000_0000);
+        let slaves = FifoSensors::new(false, false, false)
+            .with_slave(Slave::Slv0, 6)
+            .with_slave(Slave::Slv2, 1);
+        assert_eq!(u8::from(FifoEnable::from(slaves)), 0b_0000_0101);
+    }
+
+    #[test]
+    fn parse_external() {
+        let buf = [0, 1, 0, 2, 0, 3, 10, 11, 12, 0, 4, 0, 5, 0, 6, 13, 14, 15];
+        let sensors = FifoSensors::new(true, false, false)
+            .with_slave(Slave::Slv0, 2)
+            .with_slave(Slave::Slv3, 1);
+        assert_eq!(sensors.frame_size(), 9);
+        let frames = sensors.parse(&buf);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].accel, Some(AccelData { x: 1, y: 2, z: 3 }));
+        assert_eq!(frames[0].external, vec![10, 11, 12]);
+        assert_eq!(frames[1].external, vec![13, 14, 15]);
+        assert!(FifoSensors::all().parse(&[0; 14])[0].external.is_empty());
+    }
+
+    #[test]
+    fn parse_all() {
+        let buf = [
+            0, 1, 0, 2, 0, 3, 1, 0, 1, 1, 1, 2, 1, 3, //
+            0, 4, 0, 5, 0, 6, 2, 0, 1, 4, 1, 5, 1, 6, //
+            0, 7, 0, 8,
+        ];
+        let frames = FifoSensors::all().parse(&buf);
+        assert_eq!(frames.len(), 2);
+
+        let raw = frames[0].raw_data().expect("All sensors must be here");
+        assert_eq!(raw.accel, AccelData { x: 1, y: 2, z: 3 });
+        assert_eq!(raw.temp, Temperature::from(0x100));
+        assert_eq!(
+            raw.gyro,
+            GyroData {
+                x: 0x101,
+                y: 0x102,
+                z: 0x103
+            }
+        );
+
+        let raw = frames[1].raw_data().expect("All sensors must be here");
+        assert_eq!(raw.accel, AccelData { x: 4, y: 5, z: 6 });
+        assert_eq!(raw.temp, Temperature::from(0x200));
+    }
+
+    #[test]
+    fn parse_partial() {
+        let buf = [0, 9, 1, 0, 1, 1, 1, 2, 0, 8, 2, 0, 2, 1, 2, 2];
+        let frames = FifoSensors::new(false, true, true).parse(&buf);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].accel, None);
+        assert_eq!(frames[0].temp, Some(Temperature::from(9)));
+        assert_eq!(
+            frames[1].gyro,
+            Some(GyroData {
+                x: 0x200,
+                y: 0x201,
+                z: 0x202
+            })
+        );
+        assert_eq!(frames[1].raw_data(), None);
+    }
+}
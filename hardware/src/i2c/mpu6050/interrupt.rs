@@ -0,0 +1,273 @@
+use super::register::*;
+use crate::error::BusError;
+
+use derive_more::Constructor;
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::digital::v2::InputPin;
+use std::time::{Duration, Instant};
+
+/// The logic level of the INT pin while an interrupt is pending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntLevel {
+    ActiveHigh,
+    ActiveLow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntDrive {
+    PushPull,
+    OpenDrain,
+}
+
+/// Behavior of the INT pin.
+/// `Default` is the state after reset: active high, push-pull and a 50us pulse.
+#[derive(Debug, Constructor, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IntPinConfig {
+    pub level: IntLevel,
+    pub drive: IntDrive,
+    /// Holds the INT pin active until the interrupt is cleared instead of emitting a 50us pulse.
+    pub latch: bool,
+    /// Clears the interrupt status on any read, not only on reading INT_STATUS.
+    pub clear_on_read: bool,
+}
+
+impl Default for IntPinConfig {
+    fn default() -> Self {
+        Self::new(IntLevel::ActiveHigh, IntDrive::PushPull, false, false)
+    }
+}
+
+impl IntPinConfig {
+    /// Whether the INT pin is high while an interrupt is pending.
+    pub fn is_active_high(&self) -> bool {
+        self.level == IntLevel::ActiveHigh
+    }
+
+    /// Applies this config to the register, keeping the FSYNC and bypass bits.
+    pub(super) fn apply(&self, reg: &mut IntPinCfg) {
+        reg.set_int_level(self.level == IntLevel::ActiveLow);
+        reg.set_int_open(self.drive == IntDrive::OpenDrain);
+        reg.set_latch_int_en(self.latch);
+        reg.set_int_rd_clear(self.clear_on_read);
+    }
+}
+
+impl From<IntPinCfg> for IntPinConfig {
+    fn from(reg: IntPinCfg) -> Self {
+        let level = if reg.get_int_level() {
+            IntLevel::ActiveLow
+        } else {
+            IntLevel::ActiveHigh
+        };
+        let drive = if reg.get_int_open() {
+            IntDrive::OpenDrain
+        } else {
+            IntDrive::PushPull
+        };
+        Self::new(level, drive, reg.get_latch_int_en(), reg.get_int_rd_clear())
+    }
+}
+
+/// Sources of the interrupt.
+/// Used both to enable interrupts and to tell which interrupts have been generated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interrupts {
+    pub data_ready: bool,
+    pub fifo_overflow: bool,
+    pub i2c_master: bool,
+    pub motion: bool,
+    pub zero_motion: bool,
+}
+
+impl Interrupts {
+    pub fn data_ready() -> Self {
+        Self {
+            data_ready: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl From<Interrupts> for IntEnable {
+    fn from(v: Interrupts) -> Self {
+        let mut reg = IntEnable::from(0);
+        reg.set_datardy_en(v.data_ready);
+        reg.set_fifo_oflow_en(v.fifo_overflow);
+        reg.set_i2cmst_int_en(v.i2c_master);
+        reg.set_mot_en(v.motion);
+        reg.set_zmot_en(v.zero_motion);
+        reg
+    }
+}
+
+impl From<IntEnable> for Interrupts {
+    fn from(reg: IntEnable) -> Self {
+        Self {
+            data_ready: reg.get_datardy_en(),
+            fifo_overflow: reg.get_fifo_oflow_en(),
+            i2c_master: reg.get_i2cmst_int_en(),
+            motion: reg.get_mot_en(),
+            zero_motion: reg.get_zmot_en(),
+        }
+    }
+}
+
+impl From<IntStatus> for Interrupts {
+    fn from(reg: IntStatus) -> Self {
+        Self {
+            data_ready: reg.get_data_rdy_int(),
+            fifo_overflow: reg.get_fifo_oflow_int(),
+            i2c_master: reg.get_i2c_mst_int(),
+            motion: reg.get_mot_int(),
+            zero_motion: reg.get_zmot_int(),
+        }
+    }
+}
+
+/// A GPIO line connected to the INT pin.
+pub trait InterruptLine {
+    type Error: BusError;
+
+    /// Blocks until the line is at the level or the timeout passes.
+    /// Returns false on timeout.
+    fn wait_for_level(&mut self, high: bool, timeout: Duration) -> Result<bool, Self::Error>;
+}
+
+/// `InterruptLine` polling an input pin at a fixed interval.
+///
+/// The INT pin should be latched (`IntPinConfig::latch`),
+/// otherwise a 50us pulse is easily missed between polls.
+pub struct PolledLine<P, D> {
+    pin: P,
+    delay: D,
+    interval_us: u32,
+}
+
+impl<P, D> PolledLine<P, D>
+where
+    P: InputPin,
+    P::Error: BusError,
+    D: DelayUs<u32>,
+{
+    pub fn new(pin: P, delay: D, interval_us: u32) -> Self {
+        Self {
+            pin,
+            delay,
+            interval_us,
+        }
+    }
+
+    pub fn release(self) -> (P, D) {
+        (self.pin, self.delay)
+    }
+}
+
+impl<P, D> InterruptLine for PolledLine<P, D>
+where
+    P: InputPin,
+    P::Error: BusError,
+    D: DelayUs<u32>,
+{
+    type Error = P::Error;
+
+    fn wait_for_level(&mut self, high: bool, timeout: Duration) -> Result<bool, Self::Error> {
+        let start = Instant::now();
+        loop {
+            if self.pin.is_high()? == high {
+                return Ok(true);
+            }
+            if start.elapsed() >= timeout {
+                return Ok(false);
+            }
+            self.delay.delay_us(self.interval_us);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::io;
+
+    #[test]
+    fn pin_config_to_register() {
+        let mut reg = IntPinCfg::from(0b_0000_0010);
+        let cfg = IntPinConfig::new(IntLevel::ActiveLow, IntDrive::OpenDrain, true, true);
+        cfg.apply(&mut reg);
+        assert_eq!(u8::from(reg), 0b_1111_0010);
+        assert_eq!(IntPinConfig::from(reg), cfg);
+
+        IntPinConfig::default().apply(&mut reg);
+        assert_eq!(u8::from(reg), 0b_0000_0010);
+    }
+
+    #[test]
+    fn interrupts_to_register() {
+        let all = Interrupts {
+            data_ready: true,
+            fifo_overflow: true,
+            i2c_master: true,
+            motion: true,
+            zero_motion: true,
+        };
+        assert_eq!(u8::from(IntEnable::from(all)), 0b_0111_1001);
+        assert_eq!(Interrupts::from(IntEnable::from(all)), all);
+        assert_eq!(u8::from(IntEnable::from(Interrupts::data_ready())), 1);
+        assert!(Interrupts::default().is_empty());
+    }
+
+    #[test]
+    fn interrupts_from_status() {
+        let status = Interrupts::from(IntStatus::from(0b_0101_0000));
+        assert!(status.motion);
+        assert!(status.fifo_overflow);
+        assert!(!status.data_ready);
+        assert!(!status.zero_motion);
+        assert!(!status.i2c_master);
+    }
+
+    /// Goes high after being read as many times as the count.
+    struct CountdownPin(Cell<usize>);
+
+    impl InputPin for CountdownPin {
+        type Error = io::Error;
+
+        fn is_high(&self) -> Result<bool, Self::Error> {
+            let n = self.0.get();
+            self.0.set(n.saturating_sub(1));
+            Ok(n == 0)
+        }
+
+        fn is_low(&self) -> Result<bool, Self::Error> {
+            self.is_high().map(|v| !v)
+        }
+    }
+
+    struct NoDelay;
+
+    impl DelayUs<u32> for NoDelay {
+        fn delay_us(&mut self, _: u32) {}
+    }
+
+    #[test]
+    fn polled_line_waits() {
+        let pin = CountdownPin(Cell::new(3));
+        let mut line = PolledLine::new(pin, NoDelay, 10);
+        assert!(line.wait_for_level(true, Duration::from_secs(1)).unwrap());
+        let (pin, _) = line.release();
+        assert_eq!(pin.0.get(), 0);
+    }
+
+    #[test]
+    fn polled_line_timeout() {
+        let pin = CountdownPin(Cell::new(usize::MAX));
+        let mut line = PolledLine::new(pin, NoDelay, 10);
+        assert!(!line.wait_for_level(true, Duration::ZERO).unwrap());
+        assert!(line.wait_for_level(false, Duration::ZERO).unwrap());
+    }
+}
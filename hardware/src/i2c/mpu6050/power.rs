@@ -0,0 +1,105 @@
+use derive_more::Constructor;
+use num_derive::FromPrimitive;
+
+/// Frequency of wake-ups in the accelerometer only low power mode (LP_WAKE_CTRL).
+#[derive(Debug, Clone, Copy, FromPrimitive, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum WakeFrequency {
+    Hz1_25 = 0,
+    Hz5 = 1,
+    Hz20 = 2,
+    Hz40 = 3,
+}
+
+/// Axes put into standby mode (PWR_MGMT_2).
+/// Measurements of an axis in standby mode are not updated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Standby {
+    pub accel_x: bool,
+    pub accel_y: bool,
+    pub accel_z: bool,
+    pub gyro_x: bool,
+    pub gyro_y: bool,
+    pub gyro_z: bool,
+}
+
+impl Standby {
+    /// All of the gyroscope axes, as in the accelerometer only low power mode.
+    pub fn gyro() -> Self {
+        Self {
+            gyro_x: true,
+            gyro_y: true,
+            gyro_z: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// Motion detection (MOT_THR and MOT_DUR).
+/// Motion is detected when the absolute value of any of the high-pass filtered accelerometer
+/// measurements exceeds the threshold for the duration.
+#[derive(Debug, Constructor, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MotionDetection {
+    /// Threshold in mg. It is rounded down to a multiple of 2mg, up to 510mg.
+    pub threshold_mg: u16,
+    /// Duration in ms at 1kHz accelerometer rate.
+    pub duration_ms: u8,
+}
+
+impl MotionDetection {
+    /// The value of MOT_THR, 1 LSB = 2mg.
+    pub(super) fn threshold(&self) -> u8 {
+        (self.threshold_mg / 2).min(u8::MAX as u16) as u8
+    }
+}
+
+/// Zero motion detection (ZRMOT_THR and ZRMOT_DUR).
+/// Zero motion is detected when the absolute values of all of the high-pass filtered
+/// accelerometer measurements stay below the threshold for the duration.
+#[derive(Debug, Constructor, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ZeroMotionDetection {
+    /// Threshold in mg. It is rounded down to a multiple of 2mg, up to 510mg.
+    pub threshold_mg: u16,
+    /// Duration in ms. It is rounded down to a multiple of 64ms, up to 16320ms.
+    pub duration_ms: u16,
+}
+
+impl ZeroMotionDetection {
+    /// The value of ZRMOT_THR, 1 LSB = 2mg.
+    pub(super) fn threshold(&self) -> u8 {
+        (self.threshold_mg / 2).min(u8::MAX as u16) as u8
+    }
+
+    /// The value of ZRMOT_DUR, 1 LSB = 64ms.
+    pub(super) fn duration(&self) -> u8 {
+        (self.duration_ms / 64).min(u8::MAX as u16) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn motion_threshold() {
+        assert_eq!(MotionDetection::new(0, 1).threshold(), 0);
+        assert_eq!(MotionDetection::new(41, 1).threshold(), 20);
+        assert_eq!(MotionDetection::new(510, 1).threshold(), 255);
+        assert_eq!(MotionDetection::new(2000, 1).threshold(), 255);
+    }
+
+    #[test]
+    fn zero_motion_registers() {
+        let z = ZeroMotionDetection::new(41, 200);
+        assert_eq!((z.threshold(), z.duration()), (20, 3));
+        let z = ZeroMotionDetection::new(2000, 20000);
+        assert_eq!((z.threshold(), z.duration()), (255, 255));
+    }
+
+    #[test]
+    fn standby_gyro() {
+        let s = Standby::gyro();
+        assert!(s.gyro_x && s.gyro_y && s.gyro_z);
+        assert!(!(s.accel_x || s.accel_y || s.accel_z));
+    }
+}
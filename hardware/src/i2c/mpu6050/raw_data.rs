@@ -1,9 +1,14 @@
-use crate::model::sensor::{AccelInfo, GyroInfo};
+use crate::model::sensor::{AccelInfo, GyroInfo, Measurement};
+use crate::model::{Accel3D, Celsius, Gyro3D};
 
 use derive_more::{From, Into};
 use num_derive::FromPrimitive;
+use num_traits::{Float, FloatConst};
 use std::ops::{Div, Mul};
 
+/// Standard gravity in m/s².
+pub const GRAVITY: f64 = 9.80665;
+
 pub trait FullScale {
     const RESOLUTION: i32 = 65500;
 
@@ -28,17 +33,48 @@ pub trait FullScale {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct RawData {
     pub temp: Temperature,
     pub gyro: GyroData,
     pub accel: AccelData,
 }
 
+impl RawData {
+    pub fn to_measurement<V>(
+        &self,
+        accel_fs: AccelFullScale,
+        gyro_fs: GyroFullScale,
+    ) -> Measurement<V>
+    where
+        V: Float + FloatConst + From<i32>,
+    {
+        Measurement {
+            accel: self.accel.to_accel3d(accel_fs),
+            gyro: self.gyro.to_gyro3d(gyro_fs),
+            temp: self.temp.celsius(),
+        }
+    }
+}
+
 #[derive(Debug, From, Into, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Temperature(i16);
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+impl Temperature {
+    /// Temperature in degrees C = raw / 340 + 36.53
+    pub fn celsius<V: Float>(&self) -> Celsius<V> {
+        let cast = |v: f64| V::from(v).expect("A float must be casted to V");
+        (cast(self.0 as f64) / cast(340.0) + cast(36.53)).into()
+    }
+}
+
+impl<V: Float> From<Temperature> for Celsius<V> {
+    fn from(t: Temperature) -> Self {
+        t.celsius()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct GyroData {
     pub x: i16,
     pub y: i16,
@@ -56,6 +92,15 @@ impl GyroData {
         let (x, y, z) = fs.scaled(self.x, self.y, self.z);
         GyroInfo::new(x, y, z)
     }
+
+    /// Angular velocity in deg/s.
+    pub fn to_gyro3d<V>(&self, fs: GyroFullScale) -> Gyro3D<V>
+    where
+        V: Float + FloatConst + From<i32>,
+    {
+        let (x, y, z) = fs.scaled::<V>(self.x, self.y, self.z);
+        Gyro3D::new(x.into(), y.into(), z.into())
+    }
 }
 
 #[derive(Debug, Clone, Copy, FromPrimitive, PartialEq, Eq, Hash)]
@@ -73,7 +118,7 @@ impl FullScale for GyroFullScale {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct AccelData {
     pub x: i16,
     pub y: i16,
@@ -91,6 +136,16 @@ impl AccelData {
         let (x, y, z) = fs.scaled(self.x, self.y, self.z);
         AccelInfo::new(x, y, z)
     }
+
+    /// Acceleration in m/s².
+    pub fn to_accel3d<V>(&self, fs: AccelFullScale) -> Accel3D<V>
+    where
+        V: Float + From<i32>,
+    {
+        let g = <V as num_traits::NumCast>::from(GRAVITY).expect("A float must be casted to V");
+        let (x, y, z) = fs.scaled::<V>(self.x, self.y, self.z);
+        Accel3D::new((x * g).into(), (y * g).into(), (z * g).into())
+    }
 }
 
 #[derive(Debug, Clone, Copy, FromPrimitive, PartialEq, Eq, Hash)]
@@ -158,6 +213,38 @@ mod tests {
         assert_eq!(GyroFullScale::Deg2000.max(), 2000);
     }
 
+    #[test]
+    fn temperature_celsius() {
+        assert_ulps_eq!(f64::from(Temperature(0).celsius::<f64>()), 36.53);
+        assert_ulps_eq!(f64::from(Temperature(340).celsius::<f64>()), 37.53);
+        let c: Celsius<f32> = Temperature(-340 * 10).into();
+        assert_ulps_eq!(f32::from(c), 26.53);
+    }
+
+    #[test]
+    fn to_measurement() {
+        let raw = RawData {
+            temp: Temperature(0),
+            accel: AccelData {
+                x: 0,
+                y: -16375,
+                z: 16375,
+            },
+            gyro: GyroData {
+                x: 131,
+                y: 0,
+                z: -262,
+            },
+        };
+        let m = raw.to_measurement::<f64>(AccelFullScale::G2, GyroFullScale::Deg250);
+        assert_ulps_eq!(f64::from(m.accel.x()), 0.0);
+        assert_ulps_eq!(f64::from(m.accel.y()), -GRAVITY);
+        assert_ulps_eq!(f64::from(m.accel.z()), GRAVITY);
+        assert_ulps_eq!(f64::from(m.gyro.x()), 1.0);
+        assert_ulps_eq!(f64::from(m.gyro.z()), -2.0);
+        assert_ulps_eq!(f64::from(m.temp), 36.53);
+    }
+
     #[test]
     fn accel_scaled() {
         let mut rnd = rand::thread_rng();
@@ -5,397 +5,572 @@ mod test_addresses;
 #[cfg(test)]
 mod tests;
 
+use super::aux_i2c::*;
+use super::power::*;
 use super::raw_data::*;
+use crate::i2c::bitfield::Bitfield;
 use crate::i2c::register_io::*;
+use crate::{bitfield_enum, register};
 pub use attributes::*;
 use util::SingleByte;
 
 use core::fmt::Debug;
-use derive_more::{From, Into};
-use num_traits::FromPrimitive;
 use std::convert::TryInto;
 
-/// This register configures the external Frame Synchronization (FSYNC) pin sampling and
-/// the Digital Low Pass Filter (DLPF) setting for both the gyroscopes and accelerometers.
-#[derive(Debug, From, Into, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct Configure(u8);
+bitfield_enum!(
+    AccelFullScale,
+    AccelHighPassFilter,
+    ClockSel,
+    DigitalLowPassFilterCfg,
+    FrameSync,
+    GyroFullScale,
+    MasterClock,
+    WakeFrequency,
+);
 
-impl Register for Configure {
-    const ADDR: RegAddr = RegAddr(0x1a);
-}
-
-impl Configure {
-    pub fn get_fsync(&self) -> FrameSync {
-        FrameSync::from_u8(self.0.get_with_mask(0b111, 3))
-            .expect("A value of 3 bits must be converted to FSYNC.")
-    }
-
-    pub fn get_dlpf(&self) -> DigitalLowPassFilterCfg {
-        DigitalLowPassFilterCfg::from_u8(self.0.get_with_mask(0b111, 0))
-            .expect("A value of 3 bits must be converted to DLPF_CGF.")
+impl Bitfield for FlagsXYZ {
+    fn from_bits(bits: u8) -> Self {
+        FlagsXYZ(bits)
     }
 
-    pub fn set_fsync(&mut self, v: FrameSync) {
-        self.0 = self.0.set_with_mask(0b111, 3, v as u8);
+    fn into_bits(self) -> u8 {
+        self.as_u8()
     }
-
-    pub fn set_dlpf(&mut self, v: DigitalLowPassFilterCfg) {
-        self.0 = self.0.set_with_mask(0b111, 0, v as u8);
-    }
-}
-
-/// This register is used to trigger gyroscope self-test and
-/// configure the gyroscopes’ full scale range.
-#[derive(Debug, From, Into, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct GyroConfig(u8);
-
-impl Register for GyroConfig {
-    const ADDR: RegAddr = RegAddr(0x1b);
 }
 
-impl GyroConfig {
-    pub fn get_xyz(&self) -> FlagsXYZ {
-        FlagsXYZ(self.0.get_with_mask(0b111, 5))
-    }
-
-    pub fn get_scale(&self) -> GyroFullScale {
-        GyroFullScale::from_u8(self.0.get_with_mask(0b11, 3))
-            .expect("A value of 2 bits must be converted to GyroFullScale.")
-    }
-
-    pub fn set_xyz(&mut self, v: FlagsXYZ) {
-        self.0 = self.0.set_with_mask(0b111, 5, v.as_u8());
-    }
-
-    pub fn set_scale(&mut self, v: GyroFullScale) {
-        self.0 = self.0.set_with_mask(0b11, 3, v as u8);
+register! {
+    /// This register specifies the divider from the gyroscope output rate
+    /// used to generate the Sample Rate for the MPU-60X0.
+    /// The sensor register output, FIFO output, DMP sampling and Motion detection are
+    /// all based on the Sample Rate.
+    /// The Sample Rate is generated by dividing the gyroscope output rate by SMPLRT_DIV:
+    ///     Sample Rate = Gyroscope Output Rate / (1 + SMPLRT_DIV)
+    /// where Gyroscope Output Rate = 8kHz when the DLPF is disabled (DLPF_CFG = 0 or 7),
+    /// and 1kHz when the DLPF is enabled (see Register 26).
+    ///
+    /// Note:
+    /// The accelerometer output rate is 1kHz.
+    /// This means that for a Sample Rate greater than 1kHz,
+    /// the same accelerometer sample may be output to the FIFO, DMP, and sensor registers more than once.
+    /// For a diagram of the gyroscope and accelerometer signal paths,
+    /// see Section 8 of the MPU6000/MPU-6050 Product Specification document.
+    pub struct SampleRateDivider @ 0x19 {
+        value: u8 [7:0],
     }
 }
 
-/// This register is used to trigger accelerometer self test and
-/// configure the accelerometer full scale range.
-/// This register also configures the Digital High Pass Filter (DHPF).
-#[derive(Debug, From, Into, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct AccelConfig(u8);
-
-impl Register for AccelConfig {
-    const ADDR: RegAddr = RegAddr(0x1c);
-}
-
-impl AccelConfig {
-    pub fn get_xyz(&self) -> FlagsXYZ {
-        FlagsXYZ(self.0.get_with_mask(0b111, 5))
-    }
-
-    pub fn get_scale(&self) -> AccelFullScale {
-        AccelFullScale::from_u8(self.0.get_with_mask(0b11, 3))
-            .expect("A value of 2 bits must be converted to AccelFullScale.")
-    }
-
-    pub fn set_xyz(&mut self, v: FlagsXYZ) {
-        self.0 = self.0.set_with_mask(0b111, 5, v.as_u8());
-    }
-
-    pub fn set_scale(&mut self, v: AccelFullScale) {
-        self.0 = self.0.set_with_mask(0b11, 3, v as u8);
+register! {
+    /// This register configures the external Frame Synchronization (FSYNC) pin sampling and
+    /// the Digital Low Pass Filter (DLPF) setting for both the gyroscopes and accelerometers.
+    pub struct Configure @ 0x1a {
+        dlpf: DigitalLowPassFilterCfg [2:0],
+        fsync: FrameSync [5:3],
     }
 }
 
-/// This register allows the user to configure the power mode and
-/// clock source. It also provides a bit for resetting the entire device,
-/// and a bit for disabling the temperature sensor.
-#[derive(Debug, From, Into, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct PwrMgmt1(u8);
-
-impl Register for PwrMgmt1 {
-    const ADDR: RegAddr = RegAddr(0x6b);
-}
-
-impl PwrMgmt1 {
-    pub fn get_clksel(&self) -> ClockSel {
-        ClockSel::from_u8(self.0.get_with_mask(0b111, 0))
-            .expect("A value of 3 bits must be converted to ClockSel.")
-    }
-
-    pub fn set_clksel(&mut self, v: ClockSel) {
-        self.0 = self.0.set_with_mask(0b111, 0, v as u8);
-    }
-
-    /// When set to 1, this bit disables the temperature sensor.
-    pub fn get_tempdis(&self) -> bool {
-        self.0.get(3)
+register! {
+    /// This register is used to trigger gyroscope self-test and
+    /// configure the gyroscopes’ full scale range.
+    pub struct GyroConfig @ 0x1b {
+        scale: GyroFullScale [4:3],
+        /// XG_ST, YG_ST and ZG_ST.
+        xyz: FlagsXYZ [7:5],
     }
-
-    pub fn set_tempdis(&mut self, v: bool) {
-        self.0 = self.0.set(3, v);
-    }
-
-    /// When this bit is set to 1 and SLEEP is disabled,
-    /// the MPU-60X0 will cycle between sleep mode and
-    /// waking up to take a single sample of data from
-    /// active sensors at a rate determined by LP_WAKE_CTRL (register 108).
-    pub fn get_cycle(&self) -> bool {
-        self.0.get(5)
-    }
-
-    pub fn set_cycle(&mut self, v: bool) {
-        self.0 = self.0.set(5, v);
-    }
-
-    /// When set to 1, this bit puts the MPU-60X0 into sleep mode.
-    pub fn get_sleep(&self) -> bool {
-        self.0.get(6)
-    }
-
-    pub fn set_sleep(&mut self, v: bool) {
-        self.0 = self.0.set(6, v);
-    }
-
-    /// When set to 1, this bit resets all internal registers to their default values.
-    /// The bit automatically clears to 0 once the reset is done.
-    /// The default values for each register can be found in Section 3.
-    pub fn get_device_reset(&self) -> bool {
-        self.0.get(7)
-    }
-
-    pub fn set_device_reset(&mut self, v: bool) {
-        self.0 = self.0.set(7, v);
-    }
-}
-
-/// This register specifies the divider from the gyroscope output rate
-/// used to generate the Sample Rate for the MPU-60X0.
-/// The sensor register output, FIFO output, DMP sampling and Motion detection are
-/// all based on the Sample Rate.
-/// The Sample Rate is generated by dividing the gyroscope output rate by SMPLRT_DIV:
-///     Sample Rate = Gyroscope Output Rate / (1 + SMPLRT_DIV)
-/// where Gyroscope Output Rate = 8kHz when the DLPF is disabled (DLPF_CFG = 0 or 7),
-/// and 1kHz when the DLPF is enabled (see Register 26).
-///
-/// Note:
-/// The accelerometer output rate is 1kHz.
-/// This means that for a Sample Rate greater than 1kHz,
-/// the same accelerometer sample may be output to the FIFO, DMP, and sensor registers more than once.
-/// For a diagram of the gyroscope and accelerometer signal paths,
-/// see Section 8 of the MPU6000/MPU-6050 Product Specification document.
-#[derive(Debug, From, Into, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct SampleRateDivider(u8);
-
-impl Register for SampleRateDivider {
-    const ADDR: RegAddr = RegAddr(0x19);
 }
 
-impl SampleRateDivider {
-    pub fn get_value(&self) -> u8 {
-        self.0
+register! {
+    /// This register is used to trigger accelerometer self test and
+    /// configure the accelerometer full scale range.
+    /// This register also configures the Digital High Pass Filter (DHPF).
+    pub struct AccelConfig @ 0x1c {
+        hpf: AccelHighPassFilter [2:0],
+        scale: AccelFullScale [4:3],
+        /// XA_ST, YA_ST and ZA_ST.
+        xyz: FlagsXYZ [7:5],
     }
+}
 
-    pub fn set_value(&mut self, v: u8) {
-        self.0 = v;
+register! {
+    /// This register configures the detection threshold for Motion interrupt generation.
+    /// The unit of MOT_THR is 1LSB = 2mg.
+    pub struct MotThr @ 0x1f {
+        value: u8 [7:0],
     }
 }
 
-/// This register allows the user to enable and disable the FIFO buffer,
-/// I2C Master Mode, and primary I2C interface.
-/// The FIFO buffer, I2C Master, sensor signal paths and sensor registers
-/// can also be reset using this register.
-#[derive(Debug, From, Into, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct UserCtrl(u8);
-
-impl Register for UserCtrl {
-    const ADDR: RegAddr = RegAddr(0x6a);
+register! {
+    /// This register configures the duration counter threshold for Motion interrupt generation.
+    /// The duration counter ticks at 1 kHz, therefore MOT_DUR has a unit of 1LSB = 1ms.
+    pub struct MotDur @ 0x20 {
+        value: u8 [7:0],
+    }
 }
 
-impl UserCtrl {
-    /// When set to 1, this bit resets the signal paths for all sensors
-    /// (gyroscopes, accelerometers, and temperature sensor).
-    /// This operation will also clear the sensor registers.
-    /// This bit automatically clears to 0 after the reset has been triggered.
-    /// When resetting only the signal path (and not the sensor registers),
-    /// please use Register 104, SIGNAL_PATH_RESET.
-    pub fn get_sigcond_reset(&self) -> bool {
-        self.0.get(0)
+register! {
+    /// This register configures the detection threshold for Zero Motion interrupt generation.
+    /// The unit of ZRMOT_THR is 1LSB = 2mg.
+    pub struct ZrmotThr @ 0x21 {
+        value: u8 [7:0],
     }
+}
 
-    pub fn set_sigcond_reset(&mut self, v: bool) {
-        self.0 = self.0.set(0, v);
+register! {
+    /// This register configures the duration counter threshold for Zero Motion interrupt generation.
+    /// The duration counter ticks at 16 Hz, therefore ZRMOT_DUR has a unit of 1LSB = 64ms.
+    pub struct ZrmotDur @ 0x22 {
+        value: u8 [7:0],
     }
+}
 
-    /// This bit resets the I2C Master when set to 1 while I2C_MST_EN equals 0.
-    /// This bit automatically clears to 0 after the reset has been triggered.
-    pub fn get_i2cmst_reset(&self) -> bool {
-        self.0.get(1)
+register! {
+    /// This register determines which sensor measurements are loaded into the FIFO buffer.
+    pub struct FifoEnable @ 0x23 {
+        slv0: bool [0],
+        slv1: bool [1],
+        slv2: bool [2],
+        accel: bool [3],
+        zg: bool [4],
+        yg: bool [5],
+        xg: bool [6],
+        temp: bool [7],
     }
+}
 
-    pub fn set_i2cmst_reset(&mut self, v: bool) {
-        self.0 = self.0.set(1, v);
+register! {
+    /// This register configures the auxiliary I2C bus for single-master or multi-master control.
+    pub struct I2cMstCtrl @ 0x24 {
+        /// I2C_MST_CLK configures a divider on the MPU-60X0 internal 8MHz clock.
+        /// It sets the I2C master clock speed.
+        clk: MasterClock [3:0],
+        /// This bit controls the I2C Master's transition from one slave read to the next slave read.
+        /// If the bit equals 0, there will be a restart between reads.
+        /// If the bit equals 1, there will be a stop followed by a start of the following read.
+        i2c_mst_p_nsr: bool [4],
+        /// When set to 1, this bit enables EXT_SENS_DATA registers associated with Slave 3
+        /// to be written into the FIFO buffer.
+        slv3_fifo_en: bool [5],
+        /// When set to 1, this bit delays the Data Ready interrupt until
+        /// External Sensor data from the Slave devices are loaded into the EXT_SENS_DATA registers.
+        wait_for_es: bool [6],
+        /// When set to 1, this bit enables multi-master capability.
+        mult_mst_en: bool [7],
     }
+}
 
-    /// This bit resets the FIFO buffer when set to 1 while FIFO_EN equals 0.
-    /// This bit automatically clears to 0 after the reset has been triggered.
-    pub fn get_fifo_reset(&self) -> bool {
-        self.0.get(2)
+register! {
+    /// The address of slave 0 on the auxiliary I2C bus (I2C_SLV0_ADDR).
+    pub struct I2cSlv0Addr @ 0x25 {
+        /// The 7 bit address of the slave.
+        addr: u8 [6:0],
+        /// When set to 1, the transfer is a read operation. When cleared to 0, it is a write operation.
+        rnw: bool [7],
     }
+}
 
-    pub fn set_fifo_reset(&mut self, v: bool) {
-        self.0 = self.0.set(2, v);
+register! {
+    /// The register of slave 0 where the transfer starts (I2C_SLV0_REG).
+    pub struct I2cSlv0Reg @ 0x26 {
+        value: u8 [7:0],
     }
+}
 
-    /// When set to 1, this bit enables I2C Master Mode.
-    /// When this bit is cleared to 0, the auxiliary I2C bus lines
-    /// (AUX_DA and AUX_CL) are logically driven by the primary I2C bus (SDA and SCL).
-    pub fn get_i2cmst_en(&self) -> bool {
-        self.0.get(5)
+register! {
+    /// The address of slave 1 on the auxiliary I2C bus (I2C_SLV1_ADDR).
+    pub struct I2cSlv1Addr @ 0x28 {
+        /// The 7 bit address of the slave.
+        addr: u8 [6:0],
+        /// When set to 1, the transfer is a read operation. When cleared to 0, it is a write operation.
+        rnw: bool [7],
     }
+}
 
-    pub fn set_i2cmst_en(&mut self, v: bool) {
-        self.0 = self.0.set(5, v);
+register! {
+    /// The register of slave 1 where the transfer starts (I2C_SLV1_REG).
+    pub struct I2cSlv1Reg @ 0x29 {
+        value: u8 [7:0],
     }
+}
 
-    /// When set to 1, this bit enables FIFO operations.
-    /// When this bit is cleared to 0, the FIFO buffer is disabled.
-    /// The FIFO buffer cannot be written to or read from while disabled.
-    /// The FIFO buffer’s state does not change unless the MPU-60X0 is power cycled.
-    pub fn get_fifo_en(&self) -> bool {
-        self.0.get(6)
+register! {
+    /// The address of slave 2 on the auxiliary I2C bus (I2C_SLV2_ADDR).
+    pub struct I2cSlv2Addr @ 0x2b {
+        /// The 7 bit address of the slave.
+        addr: u8 [6:0],
+        /// When set to 1, the transfer is a read operation. When cleared to 0, it is a write operation.
+        rnw: bool [7],
     }
+}
 
-    pub fn set_fifo_en(&mut self, v: bool) {
-        self.0 = self.0.set(6, v);
+register! {
+    /// The register of slave 2 where the transfer starts (I2C_SLV2_REG).
+    pub struct I2cSlv2Reg @ 0x2c {
+        value: u8 [7:0],
     }
 }
 
-/// This register enables interrupt generation by interrupt sources.
-/// For information regarding the interrupt status for each interrupt generation source,
-/// please refer to Register 58.
-/// Further information regarding I2C Master interrupt generation can be found in Register 54.
-#[derive(Debug, From, Into, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct IntEnable(u8);
-
-impl Register for IntEnable {
-    const ADDR: RegAddr = RegAddr(0x38);
+register! {
+    /// The address of slave 3 on the auxiliary I2C bus (I2C_SLV3_ADDR).
+    pub struct I2cSlv3Addr @ 0x2e {
+        /// The 7 bit address of the slave.
+        addr: u8 [6:0],
+        /// When set to 1, the transfer is a read operation. When cleared to 0, it is a write operation.
+        rnw: bool [7],
+    }
 }
 
-impl IntEnable {
-    /// When set to 1, this bit enables the Data Ready interrupt,
-    /// which occurs each time a write operation to all of the sensor registers has been completed.
-    pub fn get_datardy_en(&self) -> bool {
-        self.0.get(0)
+register! {
+    /// The register of slave 3 where the transfer starts (I2C_SLV3_REG).
+    pub struct I2cSlv3Reg @ 0x2f {
+        value: u8 [7:0],
     }
+}
 
-    pub fn set_datardy_en(&mut self, v: bool) {
-        self.0 = self.0.set(0, v);
+register! {
+    /// The value of I2C_SLVx_CTRL of slave 0 to 3.
+    pub struct I2cSlvCtrl {
+        /// The number of bytes transferred to and from the slave.
+        len: u8 [3:0],
+        /// Specifies the grouping order of word pairs received from registers.
+        /// When 0, bytes from register addresses 0 and 1, 2 and 3, etc (even, then odd) form a word.
+        /// When 1, bytes from register addresses 1 and 2, 3 and 4, etc (odd, then even) form a word.
+        grp: bool [4],
+        /// When set to 1, the transaction will read or write data only.
+        /// When cleared to 0, the transaction will write a register address prior to reading or writing data.
+        reg_dis: bool [5],
+        /// When set to 1, this bit enables byte swapping.
+        /// When byte swapping is enabled, the high and low bytes of a word pair are swapped.
+        byte_sw: bool [6],
+        /// When set to 1, this bit enables the slave for data transfer operations.
+        en: bool [7],
     }
+}
 
-    /// When set to 1, this bit enables any of the I2C Master
-    /// interrupt sources to generate an interrupt.
-    pub fn get_i2cmst_int_en(&self) -> bool {
-        self.0.get(3)
+register! {
+    /// The address of slave 4 on the auxiliary I2C bus (I2C_SLV4_ADDR).
+    pub struct I2cSlv4Addr @ 0x31 {
+        /// The 7 bit address of the slave.
+        addr: u8 [6:0],
+        /// When set to 1, the transfer is a read operation. When cleared to 0, it is a write operation.
+        rnw: bool [7],
     }
+}
 
-    pub fn set_i2cmst_int_en(&mut self, v: bool) {
-        self.0 = self.0.set(3, v);
+register! {
+    /// The register of slave 4 where the transfer starts (I2C_SLV4_REG).
+    pub struct I2cSlv4Reg @ 0x32 {
+        value: u8 [7:0],
     }
+}
 
-    /// When set to 1, this bit enables a FIFO buffer overflow to generate an interrupt.
-    pub fn get_fifo_oflow_en(&self) -> bool {
-        self.0.get(4)
+register! {
+    /// The byte written to slave 4 when slave 4 is set to write mode (I2C_SLV4_DO).
+    pub struct I2cSlv4Do @ 0x33 {
+        value: u8 [7:0],
     }
+}
 
-    pub fn set_fifo_oflow_en(&mut self, v: bool) {
-        self.0 = self.0.set(4, v);
+register! {
+    /// This register configures the single byte transfer of slave 4.
+    pub struct I2cSlv4Ctrl @ 0x34 volatile {
+        /// Configures the reduced access rate of I2C slaves relative to the Sample Rate.
+        /// When a slave's access rate is decreased relative to the Sample Rate,
+        /// the slave is accessed every 1 / (1 + I2C_MST_DLY) samples.
+        i2c_mst_dly: u8 [4:0],
+        /// When set to 1, the transaction will read or write data.
+        /// When cleared to 0, the transaction will read or write a register address.
+        reg_dis: bool [5],
+        /// When set to 1, this bit enables the generation of an interrupt signal
+        /// upon completion of a Slave 4 transaction.
+        int_en: bool [6],
+        /// When set to 1, this bit enables Slave 4 for data transfer operations.
+        /// The bit is cleared when the transfer completes.
+        en: bool [7],
     }
+}
 
-    /// When set to 1, this bit enables Motion detection to generate an interrupt.
-    pub fn get_mot_en(&self) -> bool {
-        self.0.get(6)
+register! {
+    /// This register stores the data read from Slave 4.
+    pub struct I2cSlv4Di @ 0x35 volatile {
+        value: u8 [7:0],
     }
+}
 
-    pub fn set_mot_en(&mut self, v: bool) {
-        self.0 = self.0.set(6, v);
+register! {
+    /// This register shows the status of the interrupt generating signals in the I2C Master
+    /// within the MPU-60X0. Reading this register will clear all the status bits in the register.
+    pub struct I2cMstStatus @ 0x36 volatile {
+        slv0_nack: bool [0],
+        slv1_nack: bool [1],
+        slv2_nack: bool [2],
+        slv3_nack: bool [3],
+        slv4_nack: bool [4],
+        /// This bit automatically sets to 1 when the I2C Master has lost arbitration of
+        /// the auxiliary I2C bus (an error condition).
+        lost_arb: bool [5],
+        /// Automatically sets to 1 when a Slave 4 transaction has completed.
+        slv4_done: bool [6],
+        /// This bit reflects the status of the FSYNC interrupt from an external device
+        /// into the MPU-60X0.
+        pass_through: bool [7],
     }
 }
 
-/// This register determines which sensor measurements are loaded into the FIFO buffer.
-#[derive(Debug, From, Into, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct FifoEnable(u8);
-
-impl Register for FifoEnable {
-    const ADDR: RegAddr = RegAddr(0x23);
+register! {
+    /// This register configures the behavior of the interrupt signals at the INT pins.
+    /// This register is also used to enable the FSYNC Pin to be used as an interrupt to the host application processor,
+    /// as well as to enable Bypass Mode on the I2C Master.
+    /// This bit also enables the clock output.
+    pub struct IntPinCfg @ 0x37 {
+        /// When this bit is equal to 1 and I2C_MST_EN (Register 106 bit[5]) is equal to 0,
+        /// the host application processor will be able to directly access the auxiliary I2C bus of the MPU-60X0.
+        /// When this bit is equal to 0, the host application processor will not be able to
+        /// directly access the auxiliary I2C bus of the MPU-60X0 regardless of the state of I2C_MST_EN.
+        i2c_bypass_en: bool [1],
+        /// When equal to 0, this bit disables the FSYNC pin from causing an interrupt to the host processor.
+        /// When equal to 1, this bit enables the FSYNC pin to be used as an interrupt to the host processor.
+        fsync_int_en: bool [2],
+        /// When this bit is equal to 0, the logic level for the FSYNC pin
+        /// (when used as an interrupt to the host processor) is active high.
+        /// When this bit is equal to 1, the logic level for the FSYNC pin
+        /// (when used as an interrupt to the host processor) is active low.
+        fsync_int_level: bool [3],
+        /// When this bit is equal to 0, interrupt status bits are cleared only by reading INT_STATUS (Register 58).
+        /// When this bit is equal to 1, interrupt status bits are cleared on any read operation.
+        int_rd_clear: bool [4],
+        /// When this bit is equal to 0, the INT pin emits a 50us long pulse.
+        /// When this bit is equal to 1, the INT pin is held high until the interrupt is cleared.
+        latch_int_en: bool [5],
+        /// When this bit is equal to 0, the INT pin is configured as push-pull.
+        /// When this bit is equal to 1, the INT pin is configured as open drain.
+        int_open: bool [6],
+        /// When this bit is equal to 0, the logic level for the INT pin is active high.
+        /// When this bit is equal to 1, the logic level for the INT pin is active low.
+        int_level: bool [7],
+    }
 }
 
-impl FifoEnable {
-    pub fn get_slv0(&self) -> bool {
-        self.0.get(0)
+register! {
+    /// This register enables interrupt generation by interrupt sources.
+    /// For information regarding the interrupt status for each interrupt generation source,
+    /// please refer to Register 58.
+    /// Further information regarding I2C Master interrupt generation can be found in Register 54.
+    pub struct IntEnable @ 0x38 {
+        /// When set to 1, this bit enables the Data Ready interrupt,
+        /// which occurs each time a write operation to all of the sensor registers has been completed.
+        datardy_en: bool [0],
+        /// When set to 1, this bit enables any of the I2C Master
+        /// interrupt sources to generate an interrupt.
+        i2cmst_int_en: bool [3],
+        /// When set to 1, this bit enables a FIFO buffer overflow to generate an interrupt.
+        fifo_oflow_en: bool [4],
+        /// When set to 1, this bit enables Zero Motion detection to generate an interrupt.
+        zmot_en: bool [5],
+        /// When set to 1, this bit enables Motion detection to generate an interrupt.
+        mot_en: bool [6],
     }
+}
 
-    pub fn set_slv0(&mut self, v: bool) {
-        self.0 = self.0.set(0, v);
+register! {
+    /// This register shows the interrupt status of each interrupt generation source.
+    /// Each bit will clear after the register is read.
+    pub struct IntStatus @ 0x3a volatile {
+        /// This bit automatically sets to 1 when a Data Ready interrupt is generated.
+        data_rdy_int: bool [0],
+        /// This bit automatically sets to 1 when an I2C Master interrupt has been generated.
+        /// For a list of I2C Master interrupts, please refer to Register 54.
+        i2c_mst_int: bool [3],
+        /// This bit automatically sets to 1 when a FIFO buffer overflow interrupt has been generated.
+        fifo_oflow_int: bool [4],
+        /// This bit automatically sets to 1 when a Zero Motion interrupt has been generated.
+        zmot_int: bool [5],
+        /// This bit automatically sets to 1 when a Motion interrupt has been generated.
+        mot_int: bool [6],
     }
+}
 
-    pub fn get_slv1(&self) -> bool {
-        self.0.get(1)
-    }
+/// EXT_SENS_DATA_00.
+/// The following registers up to EXT_SENS_DATA_23 store data read from the external sensors
+/// by the Slave 0, 1, 2, and 3 on the auxiliary I2C interface.
+pub const EXT_SENS_DATA_00: RegAddr = RegAddr(0x49);
 
-    pub fn set_slv1(&mut self, v: bool) {
-        self.0 = self.0.set(1, v);
+register! {
+    /// The byte written to slave 0 when slave 0 is set to write mode (I2C_SLV0_DO).
+    pub struct I2cSlv0Do @ 0x63 {
+        value: u8 [7:0],
     }
+}
 
-    pub fn get_slv2(&self) -> bool {
-        self.0.get(2)
+register! {
+    /// The byte written to slave 1 when slave 1 is set to write mode (I2C_SLV1_DO).
+    pub struct I2cSlv1Do @ 0x64 {
+        value: u8 [7:0],
     }
+}
 
-    pub fn set_slv2(&mut self, v: bool) {
-        self.0 = self.0.set(2, v);
+register! {
+    /// The byte written to slave 2 when slave 2 is set to write mode (I2C_SLV2_DO).
+    pub struct I2cSlv2Do @ 0x65 {
+        value: u8 [7:0],
     }
+}
 
-    pub fn get_accel(&self) -> bool {
-        self.0.get(3)
+register! {
+    /// The byte written to slave 3 when slave 3 is set to write mode (I2C_SLV3_DO).
+    pub struct I2cSlv3Do @ 0x66 {
+        value: u8 [7:0],
     }
+}
 
-    pub fn set_accel(&mut self, v: bool) {
-        self.0 = self.0.set(3, v);
+register! {
+    /// This register allows the user to configure the delay of the external sensor data shadowing
+    /// and the reduced access rate of the slaves.
+    pub struct I2cMstDelayCtrl @ 0x67 {
+        /// When enabled, slave 0 will only be accessed at a decreased rate (see I2C_MST_DLY).
+        i2c_slv0_dly_en: bool [0],
+        i2c_slv1_dly_en: bool [1],
+        i2c_slv2_dly_en: bool [2],
+        i2c_slv3_dly_en: bool [3],
+        i2c_slv4_dly_en: bool [4],
+        /// When set, this bit delays shadowing of external sensor data until all data has been received.
+        delay_es_shadow: bool [7],
     }
+}
 
-    pub fn get_zg(&self) -> bool {
-        self.0.get(4)
+register! {
+    /// This register is used to reset the analog and digital signal paths of the gyroscope,
+    /// accelerometer, and temperature sensors.
+    /// The reset will revert the signal path analog to digital converters and filters to their
+    /// power up configurations.
+    /// This register does not clear the sensor registers.
+    pub struct SignalPathReset @ 0x68 volatile {
+        temp_reset: bool [0],
+        accel_reset: bool [1],
+        gyro_reset: bool [2],
     }
+}
 
-    pub fn set_zg(&mut self, v: bool) {
-        self.0 = self.0.set(4, v);
+register! {
+    /// This register is used to add delay to the accelerometer power on time.
+    /// It is also used to configure the Free Fall and Motion detection decrement rate.
+    pub struct MotDetectCtrl @ 0x69 {
+        /// The decrement rate of the Motion detection counter. 0 resets the counter.
+        mot_count: u8 [1:0],
+        /// The decrement rate of the Free Fall detection counter. 0 resets the counter.
+        ff_count: u8 [3:2],
+        /// Additional power-on delay of the accelerometer in ms, from 0 to 3.
+        accel_on_delay: u8 [5:4],
     }
+}
 
-    pub fn get_yg(&self) -> bool {
-        self.0.get(5)
+register! {
+    /// This register allows the user to enable and disable the FIFO buffer,
+    /// I2C Master Mode, and primary I2C interface.
+    /// The FIFO buffer, I2C Master, sensor signal paths and sensor registers
+    /// can also be reset using this register.
+    pub struct UserCtrl @ 0x6a volatile {
+        /// When set to 1, this bit resets the signal paths for all sensors
+        /// (gyroscopes, accelerometers, and temperature sensor).
+        /// This operation will also clear the sensor registers.
+        /// This bit automatically clears to 0 after the reset has been triggered.
+        /// When resetting only the signal path (and not the sensor registers),
+        /// please use Register 104, SIGNAL_PATH_RESET.
+        sigcond_reset: bool [0],
+        /// This bit resets the I2C Master when set to 1 while I2C_MST_EN equals 0.
+        /// This bit automatically clears to 0 after the reset has been triggered.
+        i2cmst_reset: bool [1],
+        /// This bit resets the FIFO buffer when set to 1 while FIFO_EN equals 0.
+        /// This bit automatically clears to 0 after the reset has been triggered.
+        fifo_reset: bool [2],
+        /// MPU-6000 only. It must be 0 on the MPU-6050.
+        i2c_if_dis: bool [4],
+        /// When set to 1, this bit enables I2C Master Mode.
+        /// When this bit is cleared to 0, the auxiliary I2C bus lines
+        /// (AUX_DA and AUX_CL) are logically driven by the primary I2C bus (SDA and SCL).
+        i2cmst_en: bool [5],
+        /// When set to 1, this bit enables FIFO operations.
+        /// When this bit is cleared to 0, the FIFO buffer is disabled.
+        /// The FIFO buffer cannot be written to or read from while disabled.
+        /// The FIFO buffer’s state does not change unless the MPU-60X0 is power cycled.
+        fifo_en: bool [6],
     }
+}
 
-    pub fn set_yg(&mut self, v: bool) {
-        self.0 = self.0.set(5, v);
+register! {
+    /// This register allows the user to configure the power mode and
+    /// clock source. It also provides a bit for resetting the entire device,
+    /// and a bit for disabling the temperature sensor.
+    pub struct PwrMgmt1 @ 0x6b {
+        clksel: ClockSel [2:0],
+        /// When set to 1, this bit disables the temperature sensor.
+        tempdis: bool [3],
+        /// When this bit is set to 1 and SLEEP is disabled,
+        /// the MPU-60X0 will cycle between sleep mode and
+        /// waking up to take a single sample of data from
+        /// active sensors at a rate determined by LP_WAKE_CTRL (register 108).
+        cycle: bool [5],
+        /// When set to 1, this bit puts the MPU-60X0 into sleep mode.
+        sleep: bool [6],
+        /// When set to 1, this bit resets all internal registers to their default values.
+        /// The bit automatically clears to 0 once the reset is done.
+        /// The default values for each register can be found in Section 3.
+        device_reset: bool [7],
     }
+}
 
-    pub fn get_xg(&self) -> bool {
-        self.0.get(6)
+register! {
+    /// This register allows the user to configure the frequency of wake-ups in
+    /// Accelerometer Only Low Power Mode.
+    /// This register also allows the user to put individual axes of the accelerometer and
+    /// gyroscope into standby mode.
+    pub struct PwrMgmt2 @ 0x6c {
+        /// STBY_XG, STBY_YG and STBY_ZG.
+        stby_gyro: FlagsXYZ [2:0],
+        /// STBY_XA, STBY_YA and STBY_ZA.
+        stby_accel: FlagsXYZ [5:3],
+        lp_wake_ctrl: WakeFrequency [7:6],
     }
+}
 
-    pub fn set_xg(&mut self, v: bool) {
-        self.0 = self.0.set(6, v);
+impl From<Standby> for PwrMgmt2 {
+    fn from(v: Standby) -> Self {
+        let mut reg = PwrMgmt2(0);
+        reg.set_stby_accel(FlagsXYZ::new(v.accel_x, v.accel_y, v.accel_z));
+        reg.set_stby_gyro(FlagsXYZ::new(v.gyro_x, v.gyro_y, v.gyro_z));
+        reg
     }
+}
 
-    pub fn get_temp(&self) -> bool {
-        self.0.get(7)
+impl From<PwrMgmt2> for Standby {
+    fn from(reg: PwrMgmt2) -> Self {
+        let accel = reg.get_stby_accel();
+        let gyro = reg.get_stby_gyro();
+        Self {
+            accel_x: accel.x(),
+            accel_y: accel.y(),
+            accel_z: accel.z(),
+            gyro_x: gyro.x(),
+            gyro_y: gyro.y(),
+            gyro_z: gyro.z(),
+        }
     }
+}
 
-    pub fn set_temp(&mut self, v: bool) {
-        self.0 = self.0.set(7, v);
+register! {
+    /// This register is used to read and write data from the FIFO buffer.
+    pub struct FifoData @ 0x74 volatile {
+        value: u8 [7:0],
     }
 }
 
-/// This register is used to read and write data from the FIFO buffer.
-#[derive(Debug, From, Into, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct FifoData(u8);
-
-impl Register for FifoData {
-    const ADDR: RegAddr = RegAddr(0x74);
+register! {
+    /// This register is used to verify the identity of the device.
+    /// The contents of WHO_AM_I are the upper 6 bits of the device's 7-bit I2C address.
+    /// The least significant bit of the I2C address is determined by the value of the AD0 pin,
+    /// which is not reflected in this register.
+    /// The default value of the register is 0x68.
+    pub struct WhoAmI @ 0x75 {
+        value: u8 [7:0],
+    }
 }
 
 // ----------------------------------------------------------------
@@ -460,6 +635,87 @@ impl From<&[u8; 14]> for RawData {
     }
 }
 
+/// Factory self-test codes (SELF_TEST_X, SELF_TEST_Y, SELF_TEST_Z and SELF_TEST_A).
+/// Each code is 5 bits and used to compute the factory trim of the axis.
+/// The accelerometer codes are split into the upper 3 bits in SELF_TEST_X/Y/Z
+/// and the lower 2 bits in SELF_TEST_A.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SelfTestCodes {
+    pub accel: [u8; 3],
+    pub gyro: [u8; 3],
+}
+
+impl RegisterRange for SelfTestCodes {
+    const ADDR: RegAddr = RegAddr(0x0d);
+}
+
+impl From<&[u8; 4]> for SelfTestCodes {
+    fn from(data: &[u8; 4]) -> Self {
+        let a = data[3];
+        let accel = |i: usize, shift: usize| {
+            (data[i].get_with_mask(0b111, 5) << 2) | a.get_with_mask(0b11, shift)
+        };
+        let gyro = |i: usize| data[i].get_with_mask(0b1_1111, 0);
+        Self {
+            accel: [accel(0, 4), accel(1, 2), accel(2, 0)],
+            gyro: [gyro(0), gyro(1), gyro(2)],
+        }
+    }
+}
+
+/// Offsets subtracted from the accelerometer measurements (XA_OFFS_H to ZA_OFFS_L).
+/// The values are in units of the ±16g full scale and preset by the factory.
+/// Bit 0 of each axis is reserved and must be kept as it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AccelOffset {
+    pub x: i16,
+    pub y: i16,
+    pub z: i16,
+}
+
+impl RegisterRange for AccelOffset {
+    const ADDR: RegAddr = RegAddr(0x06);
+}
+
+impl From<&[u8; 6]> for AccelOffset {
+    fn from(data: &[u8; 6]) -> Self {
+        let (x, y, z) = take2x3(data);
+        Self { x, y, z }
+    }
+}
+
+impl From<AccelOffset> for [u8; 6] {
+    fn from(v: AccelOffset) -> Self {
+        put2x3(v.x, v.y, v.z)
+    }
+}
+
+/// Offsets subtracted from the gyroscope measurements (XG_OFFS_USRH to ZG_OFFS_USRL).
+/// The values are in units of the ±1000dps full scale and 0 after reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GyroOffset {
+    pub x: i16,
+    pub y: i16,
+    pub z: i16,
+}
+
+impl RegisterRange for GyroOffset {
+    const ADDR: RegAddr = RegAddr(0x13);
+}
+
+impl From<&[u8; 6]> for GyroOffset {
+    fn from(data: &[u8; 6]) -> Self {
+        let (x, y, z) = take2x3(data);
+        Self { x, y, z }
+    }
+}
+
+impl From<GyroOffset> for [u8; 6] {
+    fn from(v: GyroOffset) -> Self {
+        put2x3(v.x, v.y, v.z)
+    }
+}
+
 fn take2x3(data: &[u8; 6]) -> (i16, i16, i16) {
     (
         i16::from_be_bytes([data[0], data[1]]),
@@ -467,3 +723,10 @@ fn take2x3(data: &[u8; 6]) -> (i16, i16, i16) {
         i16::from_be_bytes([data[4], data[5]]),
     )
 }
+
+fn put2x3(x: i16, y: i16, z: i16) -> [u8; 6] {
+    let [x0, x1] = x.to_be_bytes();
+    let [y0, y1] = y.to_be_bytes();
+    let [z0, z1] = z.to_be_bytes();
+    [x0, x1, y0, y1, z0, z1]
+}
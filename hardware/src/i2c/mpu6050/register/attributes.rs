@@ -6,7 +6,7 @@ use embedded_time::{duration::*, rate::*};
 use num_derive::FromPrimitive;
 use util::SingleByte;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct FlagsXYZ(pub(super) u8);
 
 impl FlagsXYZ {
@@ -31,6 +31,16 @@ impl FlagsXYZ {
     }
 }
 
+impl Debug for FlagsXYZ {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FlagsXYZ")
+            .field("x", &self.x())
+            .field("y", &self.y())
+            .field("z", &self.z())
+            .finish()
+    }
+}
+
 /// Upon power up, the MPU-60X0 clock source defaults to the internal oscillator.
 /// However, it is highly recommended that the device be configured to use one of the gyroscopes
 /// (or an external clock source) as the clock reference for improved stability.
@@ -48,6 +58,23 @@ pub enum ClockSel {
     Stop = 7,
 }
 
+/// The Digital High Pass Filter of the accelerometer.
+/// Its output is used by the motion detectors.
+/// In the Hold setting, the filter holds the present sample and
+/// outputs the difference between the input sample and the held sample.
+#[derive(Debug, Clone, Copy, FromPrimitive, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum AccelHighPassFilter {
+    Reset = 0,
+    Hz5 = 1,
+    Hz2_5 = 2,
+    Hz1_25 = 3,
+    Hz0_63 = 4,
+    Reserved5 = 5,
+    Reserved6 = 6,
+    Hold = 7,
+}
+
 #[derive(Debug, Clone, Copy, FromPrimitive, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum FrameSync {
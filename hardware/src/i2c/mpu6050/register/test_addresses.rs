@@ -2,17 +2,51 @@ use super::*;
 
 #[test]
 fn all_registers() {
+    assert_eq!(RegAddr(0x06), AccelOffset::ADDR);
+    assert_eq!(RegAddr(0x0D), SelfTestCodes::ADDR);
+    assert_eq!(RegAddr(0x13), GyroOffset::ADDR);
     assert_eq!(RegAddr(0x19), SampleRateDivider::ADDR);
     assert_eq!(RegAddr(0x1A), Configure::ADDR);
     assert_eq!(RegAddr(0x1B), GyroConfig::ADDR);
     assert_eq!(RegAddr(0x1C), AccelConfig::ADDR);
+    assert_eq!(RegAddr(0x1F), MotThr::ADDR);
+    assert_eq!(RegAddr(0x20), MotDur::ADDR);
+    assert_eq!(RegAddr(0x21), ZrmotThr::ADDR);
+    assert_eq!(RegAddr(0x22), ZrmotDur::ADDR);
     assert_eq!(RegAddr(0x23), FifoEnable::ADDR);
+    assert_eq!(RegAddr(0x24), I2cMstCtrl::ADDR);
+    assert_eq!(RegAddr(0x25), I2cSlv0Addr::ADDR);
+    assert_eq!(RegAddr(0x26), I2cSlv0Reg::ADDR);
+    assert_eq!(RegAddr(0x28), I2cSlv1Addr::ADDR);
+    assert_eq!(RegAddr(0x29), I2cSlv1Reg::ADDR);
+    assert_eq!(RegAddr(0x2B), I2cSlv2Addr::ADDR);
+    assert_eq!(RegAddr(0x2C), I2cSlv2Reg::ADDR);
+    assert_eq!(RegAddr(0x2E), I2cSlv3Addr::ADDR);
+    assert_eq!(RegAddr(0x2F), I2cSlv3Reg::ADDR);
+    assert_eq!(RegAddr(0x31), I2cSlv4Addr::ADDR);
+    assert_eq!(RegAddr(0x32), I2cSlv4Reg::ADDR);
+    assert_eq!(RegAddr(0x33), I2cSlv4Do::ADDR);
+    assert_eq!(RegAddr(0x34), I2cSlv4Ctrl::ADDR);
+    assert_eq!(RegAddr(0x35), I2cSlv4Di::ADDR);
+    assert_eq!(RegAddr(0x36), I2cMstStatus::ADDR);
+    assert_eq!(RegAddr(0x37), IntPinCfg::ADDR);
     assert_eq!(RegAddr(0x38), IntEnable::ADDR);
+    assert_eq!(RegAddr(0x3A), IntStatus::ADDR);
     assert_eq!(RegAddr(0x3B), AccelData::ADDR);
     assert_eq!(RegAddr(0x41), Temperature::ADDR);
     assert_eq!(RegAddr(0x43), GyroData::ADDR);
+    assert_eq!(RegAddr(0x49), EXT_SENS_DATA_00);
+    assert_eq!(RegAddr(0x63), I2cSlv0Do::ADDR);
+    assert_eq!(RegAddr(0x64), I2cSlv1Do::ADDR);
+    assert_eq!(RegAddr(0x65), I2cSlv2Do::ADDR);
+    assert_eq!(RegAddr(0x66), I2cSlv3Do::ADDR);
+    assert_eq!(RegAddr(0x67), I2cMstDelayCtrl::ADDR);
+    assert_eq!(RegAddr(0x68), SignalPathReset::ADDR);
+    assert_eq!(RegAddr(0x69), MotDetectCtrl::ADDR);
     assert_eq!(RegAddr(0x6A), UserCtrl::ADDR);
     assert_eq!(RegAddr(0x6B), PwrMgmt1::ADDR);
+    assert_eq!(RegAddr(0x6C), PwrMgmt2::ADDR);
     assert_eq!(RegAddr(0x72), FifoCount::ADDR);
     assert_eq!(RegAddr(0x74), FifoData::ADDR);
+    assert_eq!(RegAddr(0x75), WhoAmI::ADDR);
 }
@@ -1,4 +1,5 @@
 use super::*;
+use num_traits::FromPrimitive;
 
 #[test]
 fn configure_from_u8() {
@@ -87,6 +88,119 @@ fn accel_cfg_from_u8() {
     }
 }
 
+#[test]
+fn accel_cfg_hpf() {
+    for a in 0..8 {
+        let hpf = AccelHighPassFilter::from_u8(a).expect("Must be !");
+        let mut c = AccelConfig::from(0b_1111_1000);
+        c.set_hpf(hpf);
+        assert_eq!(u8::from(c), 0b_1111_1000 | a);
+        assert_eq!(c.get_hpf(), hpf);
+        assert_eq!(c.get_scale(), AccelFullScale::G16);
+    }
+}
+
+#[test]
+fn pwr_mgmt2_from_u8() {
+    for wake in 0..4 {
+        for a in 0..8 {
+            for g in 0..8 {
+                let mut o = PwrMgmt2(0);
+                o.set_lp_wake_ctrl(WakeFrequency::from_u8(wake).unwrap());
+                o.set_stby_accel(FlagsXYZ(a));
+                o.set_stby_gyro(FlagsXYZ(g));
+
+                let c: u8 = o.into();
+                assert_eq!(c, wake << 6 | a << 3 | g);
+
+                let o = PwrMgmt2::from(c);
+                assert_eq!(wake, o.get_lp_wake_ctrl() as u8);
+                assert_eq!(FlagsXYZ(a), o.get_stby_accel());
+                assert_eq!(FlagsXYZ(g), o.get_stby_gyro());
+            }
+        }
+    }
+}
+
+#[test]
+fn standby_to_pwr_mgmt2() {
+    let standby = Standby {
+        accel_x: true,
+        gyro_y: true,
+        gyro_z: true,
+        ..Standby::default()
+    };
+    let reg = PwrMgmt2::from(standby);
+    assert_eq!(u8::from(reg), 0b_0010_0011);
+    assert_eq!(Standby::from(reg), standby);
+}
+
+#[test]
+fn i2c_mst_ctrl_from_u8() {
+    for clk in 0..16 {
+        for flags in 0..16 {
+            let mut o = I2cMstCtrl(0);
+            o.set_clk(MasterClock::from_u8(clk).unwrap());
+            o.set_i2c_mst_p_nsr(flags & 1 != 0);
+            o.set_slv3_fifo_en(flags & 2 != 0);
+            o.set_wait_for_es(flags & 4 != 0);
+            o.set_mult_mst_en(flags & 8 != 0);
+
+            let c: u8 = o.into();
+            assert_eq!(c, flags << 4 | clk);
+
+            let o = I2cMstCtrl::from(c);
+            assert_eq!(clk, o.get_clk() as u8);
+            assert_eq!(flags & 1 != 0, o.get_i2c_mst_p_nsr());
+            assert_eq!(flags & 2 != 0, o.get_slv3_fifo_en());
+            assert_eq!(flags & 4 != 0, o.get_wait_for_es());
+            assert_eq!(flags & 8 != 0, o.get_mult_mst_en());
+        }
+    }
+}
+
+#[test]
+fn i2c_slv_ctrl_from_u8() {
+    for len in 0..16 {
+        for flags in 0..16 {
+            let mut o = I2cSlvCtrl(0);
+            o.set_len(len);
+            o.set_grp(flags & 1 != 0);
+            o.set_reg_dis(flags & 2 != 0);
+            o.set_byte_sw(flags & 4 != 0);
+            o.set_en(flags & 8 != 0);
+
+            let c: u8 = o.into();
+            assert_eq!(c, flags << 4 | len);
+
+            let o = I2cSlvCtrl::from(c);
+            assert_eq!(len, o.get_len());
+            assert_eq!(flags & 1 != 0, o.get_grp());
+            assert_eq!(flags & 2 != 0, o.get_reg_dis());
+            assert_eq!(flags & 4 != 0, o.get_byte_sw());
+            assert_eq!(flags & 8 != 0, o.get_en());
+        }
+    }
+}
+
+#[test]
+fn i2c_slv_addr_from_u8() {
+    for addr in 0..0x80 {
+        for rnw in [true, false] {
+            let mut o = I2cSlv2Addr(0);
+            o.set_addr(addr);
+            o.set_rnw(rnw);
+
+            let c: u8 = o.into();
+            assert_eq!(c, (rnw as u8) << 7 | addr);
+
+            let o = I2cSlv2Addr::from(c);
+            assert_eq!(addr, o.get_addr());
+            assert_eq!(rnw, o.get_rnw());
+        }
+    }
+}
+
 #[test]
 fn pwr_mgmt1_from_u8() {
     for clksel in 0..8 {
@@ -160,30 +274,72 @@ fn int_enable_from_u8() {
     for datardy_en in [true, false] {
         for i2cmst_int_en in [true, false] {
             for fifo_oflow_en in [true, false] {
-                for mot_en in [true, false] {
-                    let mut o = IntEnable(0);
-                    o.set_datardy_en(datardy_en);
-                    o.set_i2cmst_int_en(i2cmst_int_en);
-                    o.set_fifo_oflow_en(fifo_oflow_en);
-                    o.set_mot_en(mot_en);
-
-                    let c: u8 = o.into();
-                    assert_eq!(datardy_en, c.get(0));
-                    assert_eq!(i2cmst_int_en, c.get(3));
-                    assert_eq!(fifo_oflow_en, c.get(4));
-                    assert_eq!(mot_en, c.get(6));
-
-                    let o = IntEnable::from(c);
-                    assert_eq!(datardy_en, o.get_datardy_en());
-                    assert_eq!(i2cmst_int_en, o.get_i2cmst_int_en());
-                    assert_eq!(fifo_oflow_en, o.get_fifo_oflow_en());
-                    assert_eq!(mot_en, o.get_mot_en());
+                for zmot_en in [true, false] {
+                    for mot_en in [true, false] {
+                        let mut o = IntEnable(0);
+                        o.set_datardy_en(datardy_en);
+                        o.set_i2cmst_int_en(i2cmst_int_en);
+                        o.set_fifo_oflow_en(fifo_oflow_en);
+                        o.set_zmot_en(zmot_en);
+                        o.set_mot_en(mot_en);
+
+                        let c: u8 = o.into();
+                        assert_eq!(datardy_en, c.get(0));
+                        assert_eq!(i2cmst_int_en, c.get(3));
+                        assert_eq!(fifo_oflow_en, c.get(4));
+                        assert_eq!(zmot_en, c.get(5));
+                        assert_eq!(mot_en, c.get(6));
+
+                        let o = IntEnable::from(c);
+                        assert_eq!(datardy_en, o.get_datardy_en());
+                        assert_eq!(i2cmst_int_en, o.get_i2cmst_int_en());
+                        assert_eq!(fifo_oflow_en, o.get_fifo_oflow_en());
+                        assert_eq!(zmot_en, o.get_zmot_en());
+                        assert_eq!(mot_en, o.get_mot_en());
+                    }
                 }
             }
         }
     }
 }
 
+#[test]
+fn int_pin_cfg_from_u8() {
+    for c in 0..=255 {
+        let o = IntPinCfg::from(c);
+        assert_eq!(c.get(1), o.get_i2c_bypass_en());
+        assert_eq!(c.get(2), o.get_fsync_int_en());
+        assert_eq!(c.get(3), o.get_fsync_int_level());
+        assert_eq!(c.get(4), o.get_int_rd_clear());
+        assert_eq!(c.get(5), o.get_latch_int_en());
+        assert_eq!(c.get(6), o.get_int_open());
+        assert_eq!(c.get(7), o.get_int_level());
+
+        let mut a = IntPinCfg::from(c & 1);
+        a.set_i2c_bypass_en(o.get_i2c_bypass_en());
+        a.set_fsync_int_en(o.get_fsync_int_en());
+        a.set_fsync_int_level(o.get_fsync_int_level());
+        a.set_int_rd_clear(o.get_int_rd_clear());
+        a.set_latch_int_en(o.get_latch_int_en());
+        a.set_int_open(o.get_int_open());
+        a.set_int_level(o.get_int_level());
+
+        assert_eq!(a, o);
+    }
+}
+
+#[test]
+fn int_status_from_u8() {
+    for c in 0..=255 {
+        let o = IntStatus::from(c);
+        assert_eq!(c.get(0), o.get_data_rdy_int());
+        assert_eq!(c.get(3), o.get_i2c_mst_int());
+        assert_eq!(c.get(4), o.get_fifo_oflow_int());
+        assert_eq!(c.get(5), o.get_zmot_int());
+        assert_eq!(c.get(6), o.get_mot_int());
+    }
+}
+
 #[test]
 fn fifo_enable_from_u8() {
     for c in 0..=255 {
@@ -240,3 +396,73 @@ fn raw_data_from_buf() {
         }
     )
 }
+
+#[test]
+fn offsets_to_buf() {
+    let buf = [0x12, 0x34, 0xff, 0xfe, 0x80, 0x00];
+    let accel = AccelOffset::from(&buf);
+    assert_eq!(accel.x, 0x1234);
+    assert_eq!(accel.y, -2);
+    assert_eq!(accel.z, i16::MIN);
+    assert_eq!(<[u8; 6]>::from(accel), buf);
+
+    let gyro = GyroOffset::from(&buf);
+    assert_eq!(<[u8; 6]>::from(gyro), buf);
+}
+
+#[test]
+fn self_test_codes_from_buf() {
+    // XA: 101_10, YA: 010_01, ZA: 111_11
+    let buf = [0b_1011_0001, 0b_0100_0010, 0b_1111_1111, 0b_0010_0111];
+    let codes = SelfTestCodes::from(&buf);
+    assert_eq!(codes.accel, [22, 9, 31]);
+    assert_eq!(codes.gyro, [17, 2, 31]);
+}
+
+#[test]
+fn mot_detect_ctrl_from_u8() {
+    let mut o = MotDetectCtrl(0);
+    o.set_accel_on_delay(3);
+    o.set_ff_count(2);
+    o.set_mot_count(1);
+    assert_eq!(u8::from(o), 0b_0011_1001);
+    assert_eq!(MotDetectCtrl::from(0b_1100_0110).get_ff_count(), 1);
+}
+
+#[test]
+fn decoded_debug() {
+    let v = PwrMgmt1::from(0b_0100_1001);
+    assert_eq!(
+        format!("{:?}", v),
+        "PwrMgmt1 { clksel: Xgyro, tempdis: true, cycle: false, sleep: true, device_reset: false }"
+    );
+    let v = GyroConfig::from(0b_1001_1000);
+    assert_eq!(
+        format!("{:?}", v),
+        "GyroConfig { scale: Deg2000, xyz: FlagsXYZ { x: true, y: false, z: false } }"
+    );
+}
+
+#[test]
+fn volatile_registers() {
+    let volatile = [
+        I2cSlv4Ctrl::VOLATILE,
+        I2cSlv4Di::VOLATILE,
+        I2cMstStatus::VOLATILE,
+        IntStatus::VOLATILE,
+        SignalPathReset::VOLATILE,
+        UserCtrl::VOLATILE,
+        FifoData::VOLATILE,
+    ];
+    assert!(volatile.iter().all(|v| *v));
+
+    let cached = [
+        PwrMgmt1::VOLATILE,
+        PwrMgmt2::VOLATILE,
+        AccelConfig::VOLATILE,
+        GyroConfig::VOLATILE,
+        IntPinCfg::VOLATILE,
+        IntEnable::VOLATILE,
+    ];
+    assert!(cached.iter().all(|v| !*v));
+}
@@ -0,0 +1,148 @@
+use super::raw_data::*;
+use super::register::*;
+
+/// The self-test passes when the response is within this percentage of the factory trim.
+pub const TOLERANCE: f64 = 14.0;
+
+/// The full scales the factory trims are given for.
+pub const ACCEL_FULL_SCALE: AccelFullScale = AccelFullScale::G8;
+pub const GYRO_FULL_SCALE: GyroFullScale = GyroFullScale::Deg250;
+
+/// The self-test result of an axis.
+/// The values are in LSB at the self-test full scales.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisResult {
+    pub factory_trim: f64,
+    /// Output with the self-test enabled minus output with it disabled.
+    pub response: f64,
+}
+
+impl AxisResult {
+    /// Change of the response from the factory trim in percent.
+    /// `None` if the factory trim is not available.
+    pub fn change(&self) -> Option<f64> {
+        if self.factory_trim == 0.0 {
+            None
+        } else {
+            Some((self.response - self.factory_trim) / self.factory_trim * 100.0)
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.change().map(|c| c.abs() <= TOLERANCE).unwrap_or(false)
+    }
+}
+
+/// Results of the self-test in the order of X, Y and Z axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SelfTestReport {
+    pub accel: [AxisResult; 3],
+    pub gyro: [AxisResult; 3],
+}
+
+impl SelfTestReport {
+    pub(super) fn new(codes: SelfTestCodes, disabled: RawData, enabled: RawData) -> Self {
+        let response = |e: i16, d: i16| e as f64 - d as f64;
+        let accel = [
+            response(enabled.accel.x, disabled.accel.x),
+            response(enabled.accel.y, disabled.accel.y),
+            response(enabled.accel.z, disabled.accel.z),
+        ];
+        let gyro = [
+            response(enabled.gyro.x, disabled.gyro.x),
+            response(enabled.gyro.y, disabled.gyro.y),
+            response(enabled.gyro.z, disabled.gyro.z),
+        ];
+        let result = |factory_trim, response| AxisResult {
+            factory_trim,
+            response,
+        };
+        Self {
+            accel: [0, 1, 2].map(|i| result(accel_trim(codes.accel[i]), accel[i])),
+            gyro: [0, 1, 2].map(|i| result(gyro_trim(i, codes.gyro[i]), gyro[i])),
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.accel
+            .iter()
+            .chain(self.gyro.iter())
+            .all(|r| r.passed())
+    }
+}
+
+/// FT[Xa] = 4096 * 0.34 * (0.92 / 0.34) ^ ((XA_TEST - 1) / (2^5 - 2))
+fn accel_trim(code: u8) -> f64 {
+    if code == 0 {
+        return 0.0;
+    }
+    let exp = (code as f64 - 1.0) / 30.0;
+    4096.0 * 0.34 * (0.92_f64 / 0.34).powf(exp)
+}
+
+/// FT[Xg] = 25 * 131 * 1.046 ^ (XG_TEST - 1).
+/// It is negative on Y axis.
+fn gyro_trim(axis: usize, code: u8) -> f64 {
+    if code == 0 {
+        return 0.0;
+    }
+    let v = 25.0 * 131.0 * 1.046_f64.powi(code as i32 - 1);
+    if axis == 1 {
+        -v
+    } else {
+        v
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::*;
+
+    fn raw(accel: [i16; 3], gyro: [i16; 3]) -> RawData {
+        RawData {
+            temp: 0.into(),
+            accel: AccelData {
+                x: accel[0],
+                y: accel[1],
+                z: accel[2],
+            },
+            gyro: GyroData {
+                x: gyro[0],
+                y: gyro[1],
+                z: gyro[2],
+            },
+        }
+    }
+
+    #[test]
+    fn factory_trims() {
+        assert_relative_eq!(accel_trim(1), 4096.0 * 0.34);
+        assert_relative_eq!(accel_trim(31), 4096.0 * 0.92, epsilon = 1e-9);
+        assert_relative_eq!(gyro_trim(0, 1), 3275.0);
+        assert_relative_eq!(gyro_trim(1, 2), -3275.0 * 1.046);
+        assert_eq!(accel_trim(0), 0.0);
+        assert_eq!(gyro_trim(2, 0), 0.0);
+    }
+
+    #[test]
+    fn report() {
+        let codes = SelfTestCodes {
+            accel: [1, 1, 0],
+            gyro: [1, 1, 1],
+        };
+        let disabled = raw([100, 100, 4096], [10, -10, 0]);
+        let enabled = raw([100 + 1393, 100 + 1000, 4096 + 1000], [3285, -3285, 3800]);
+        let report = SelfTestReport::new(codes, disabled, enabled);
+
+        assert!(report.accel[0].passed());
+        assert!(!report.accel[1].passed());
+        assert_eq!(report.accel[2].change(), None);
+        assert!(!report.accel[2].passed());
+        assert!(report.gyro[0].passed());
+        assert!(report.gyro[1].passed());
+        assert!(!report.gyro[2].passed());
+        assert_relative_eq!(report.gyro[0].change().unwrap(), 0.0);
+        assert!(!report.passed());
+    }
+}
@@ -1,11 +1,23 @@
+pub mod frame;
+
+use super::{I2cAddr, RegAddr};
+use crate::error::*;
+use frame::*;
+
 use embedded_hal::blocking::i2c::{Write, WriteRead};
-use i2cdev::linux::LinuxI2CError;
-use pwm_pca9685::{Address, Channel, Error, Pca9685};
-use util::DivideList;
+use pwm_pca9685::{Address, Channel, Pca9685};
+
+pub(crate) const OSC: f64 = 25000000.0;
+pub(crate) const PULSE_BASE: f64 = 4096.0;
+
+pub(crate) const MODE1: RegAddr = RegAddr(0x00);
+pub(crate) const MODE2: RegAddr = RegAddr(0x01);
+pub(crate) const LED0_ON_L: RegAddr = RegAddr(0x06);
+pub(crate) const ALL_LED_ON_L: RegAddr = RegAddr(0xfa);
+pub(crate) const PRE_SCALE: RegAddr = RegAddr(0xfe);
 
-const OSC: f64 = 25000000.0;
-const PULSE_BASE: f64 = 4096.0;
-pub type PwmError = Error<LinuxI2CError>;
+/// The LED All Call address, which every PCA9685 also answers after power-on (MODE1 ALLCALL).
+pub const ALL_CALL_ADDRESS: I2cAddr = I2cAddr(0x70);
 
 pub trait HasPrescale {
     fn prescale(&self) -> u8;
@@ -17,63 +29,258 @@ pub trait HasChannel {
 
 pub struct PCA9685<D> {
     pub inner: Pca9685<D>,
+    address: I2cAddr,
     prescale: Option<u8>,
+    /// The counts last written to the channels.
+    counts: [Counts; CHANNELS],
 }
 
-impl<D> PCA9685<D>
+impl<D, E> PCA9685<D>
 where
-    D: Write<Error = LinuxI2CError> + WriteRead<Error = LinuxI2CError>,
+    D: Write<Error = E> + WriteRead<Error = E>,
+    E: BusError,
 {
-    pub fn new(dev: D, addr: u8) -> Result<PCA9685<D>, PwmError> {
-        let address = Address::from(addr);
-        Pca9685::new(dev, address).map(|inner| PCA9685 {
-            inner,
-            prescale: None,
-        })
+    pub fn new(dev: D, addr: u8) -> Result<PCA9685<D>, Error> {
+        let address = I2cAddr(addr);
+        Pca9685::new(dev, Address::from(addr))
+            .map(|inner| PCA9685 {
+                inner,
+                address,
+                prescale: None,
+                counts: [Counts::default(); CHANNELS],
+            })
+            .map_err(|e| pwm_error(address, None, e))
+    }
+
+    /// `new` after checking the device with `identify`.
+    /// Fails with `ErrorKind::UnexpectedDevice` if the device does not look like a PCA9685.
+    pub fn new_verified(mut dev: D, addr: u8) -> Result<PCA9685<D>, Error> {
+        let address = I2cAddr(addr);
+        if !identify(&mut dev, address)? {
+            return Err(
+                Error::new(ErrorKind::UnexpectedDevice, Device::Pca9685, address)
+                    .with_register(MODE2),
+            );
+        }
+        Self::new(dev, addr)
+    }
+
+    pub fn address(&self) -> I2cAddr {
+        self.address
     }
 
-    pub fn enable(&mut self) -> Result<(), PwmError> {
-        self.inner.enable()
+    pub fn enable(&mut self) -> Result<(), Error> {
+        let address = self.address;
+        self.inner
+            .enable()
+            .map_err(|e| pwm_error(address, Some(MODE1), e))
     }
 
-    pub fn set_prescale(&mut self, v: u8) -> Result<(), PwmError> {
-        match self.prescale {
-            Some(prev) if prev == v => Ok(()),
-            _ => {
-                self.prescale = Some(v);
-                self.inner.set_prescale(v)
+    /// The prescale is common to all of the channels of the board.
+    /// When it is changed, the counts of the channels already running are rewritten,
+    /// so that their pulse widths in microseconds are kept against the new period.
+    /// The counts are kept as they are if the prescale has not been set by this driver.
+    pub fn set_prescale(&mut self, v: u8) -> Result<(), Error> {
+        let prev = self.prescale;
+        if prev == Some(v) {
+            return Ok(());
+        }
+        let address = self.address;
+        self.inner
+            .set_prescale(v)
+            .map_err(|e| pwm_error(address, Some(PRE_SCALE), e))?;
+        self.prescale = Some(v);
+        match prev {
+            Some(prev) if self.counts.iter().any(|c| c.width() > 0) => {
+                let ratio = (prev as f64 + 1.0) / (v as f64 + 1.0);
+                self.write_counts(self.counts.map(|c| c.scale(ratio)))
             }
+            _ => Ok(()),
         }
     }
 
-    pub fn set_one_duty_cycle(&mut self, channel: Channel, rate: f64) -> Result<(), PwmError> {
+    /// `None` until it is set by this driver.
+    pub fn prescale(&self) -> Option<u8> {
+        self.prescale
+    }
+
+    /// Sets the prescale nearest to the frequency in Hz.
+    /// Returns the frequency actually generated.
+    pub fn set_frequency(&mut self, v: f64) -> Result<f64, Error> {
+        let (frequency, prescale) = collect_frequency(v);
+        self.set_prescale(prescale)?;
+        Ok(frequency)
+    }
+
+    /// The frequency in Hz actually generated with the prescale.
+    /// `None` until the prescale is set by this driver.
+    pub fn frequency(&self) -> Option<f64> {
+        self.prescale.map(prescale_frequency)
+    }
+
+    /// Sets the pulse width in microseconds, counted against the period of the current prescale.
+    /// Fails with `ErrorKind::InvalidInput` if the prescale has not been set.
+    pub fn set_pulse_width(&mut self, channel: Channel, width_us: f64) -> Result<(), Error> {
+        let frequency = self.frequency().ok_or_else(|| {
+            Error::new(ErrorKind::InvalidInput, Device::Pca9685, self.address)
+                .with_register(PRE_SCALE)
+        })?;
+        self.set_one_duty_cycle(channel, width_us * frequency / 1_000_000.0)
+    }
+
+    pub fn set_one_duty_cycle(&mut self, channel: Channel, rate: f64) -> Result<(), Error> {
+        let address = self.address;
         let v = calc_pulse(rate);
-        self.inner.set_channel_on_off(channel, 0, v)
+        self.inner
+            .set_channel_on_off(channel, 0, v)
+            .map_err(|e| pwm_error(address, Some(channel_register(channel)), e))?;
+        let counts = Counts { on: 0, off: v };
+        match channel {
+            Channel::All => self.counts = [counts; CHANNELS],
+            _ => self.counts[channel as usize] = counts,
+        }
+        Ok(())
     }
 
-    pub fn set_duty_cycle<T>(&mut self, rates: &[(&T, f64)]) -> Result<(), PwmError>
+    /// Writes the counts of all of the channels in one transfer, so that they change together.
+    /// The channels not set in the frame keep the counts last written by this driver,
+    /// or are turned off if none has been written.
+    /// If all of the channels get the same counts, only the ALL_LED registers are written.
+    pub fn write_frame(&mut self, frame: &PwmFrame) -> Result<(), Error> {
+        self.write_counts(frame.resolve(&self.counts))
+    }
+
+    fn write_counts(&mut self, counts: [Counts; CHANNELS]) -> Result<(), Error> {
+        let address = self.address;
+        let result = if counts.iter().all(|c| *c == counts[0]) {
+            self.inner
+                .set_channel_on_off(Channel::All, counts[0].on, counts[0].off)
+                .map_err(|e| pwm_error(address, Some(ALL_LED_ON_L), e))
+        } else {
+            let on = counts.map(|c| c.on);
+            let off = counts.map(|c| c.off);
+            self.inner
+                .set_all_on_off(&on, &off)
+                .map_err(|e| pwm_error(address, Some(LED0_ON_L), e))
+        };
+        result?;
+        self.counts = counts;
+        Ok(())
+    }
+
+    /// Sets the duty cycles with `write_frame`.
+    /// All of them must have the same prescale, which is common to the board;
+    /// fails with `ErrorKind::InvalidInput` otherwise.
+    pub fn set_duty_cycle_together<T>(
+        &mut self,
+        rates: &[(&T, f64)],
+        stagger: Stagger,
+    ) -> Result<(), Error>
     where
         T: HasChannel + HasPrescale,
     {
-        let by_prescale = rates.divide_by(|(e, _)| e.prescale());
-        by_prescale.iter().fold(Ok(()), |prev, (ps, values)| {
-            prev?;
-            let preset = self.set_prescale(*ps);
-            values.iter().fold(preset, |prev, (t, rate)| {
-                prev?;
-                self.set_one_duty_cycle(t.channel(), *rate)
-            })
-        })
+        let prescale = match self.common_prescale(rates)? {
+            Some(prescale) => prescale,
+            None => return Ok(()),
+        };
+        let mut frame = PwmFrame::new(stagger);
+        for (t, rate) in rates {
+            frame.set(t.channel(), *rate);
+        }
+        self.set_prescale(prescale)?;
+        self.write_frame(&frame)
+    }
+
+    /// Sets the duty cycles computed for the prescale of each of them.
+    /// The prescale is common to the board, so all of them must have the same prescale;
+    /// fails with `ErrorKind::InvalidInput` without writing anything otherwise.
+    /// Servos of different frequencies can share the board with `set_pulse_width`.
+    pub fn set_duty_cycle<T>(&mut self, rates: &[(&T, f64)]) -> Result<(), Error>
+    where
+        T: HasChannel + HasPrescale,
+    {
+        let prescale = match self.common_prescale(rates)? {
+            Some(prescale) => prescale,
+            None => return Ok(()),
+        };
+        self.set_prescale(prescale)?;
+        rates
+            .iter()
+            .try_for_each(|(t, rate)| self.set_one_duty_cycle(t.channel(), *rate))
+    }
+
+    fn common_prescale<T: HasPrescale>(&self, rates: &[(&T, f64)]) -> Result<Option<u8>, Error> {
+        let prescale = match rates.first() {
+            Some((t, _)) => t.prescale(),
+            None => return Ok(None),
+        };
+        if rates.iter().any(|(t, _)| t.prescale() != prescale) {
+            return Err(
+                Error::new(ErrorKind::InvalidInput, Device::Pca9685, self.address)
+                    .with_register(PRE_SCALE),
+            );
+        }
+        Ok(Some(prescale))
     }
 }
 
+/// Whether the device at the address looks like a PCA9685.
+/// The PCA9685 has no ID register, so MODE1 must be readable and
+/// the reserved bits 7:5 of MODE2, which always read 0, must be 0.
+pub fn identify<D, E>(dev: &mut D, address: I2cAddr) -> Result<bool, Error>
+where
+    D: WriteRead<Error = E>,
+    E: BusError,
+{
+    let mut read = |reg: RegAddr| {
+        let mut buf = [0; 1];
+        dev.write_read(address.into(), &[reg.into()], &mut buf)
+            .map(|_| buf[0])
+            .map_err(|e| Error::write_read(Device::Pca9685, address, reg, e))
+    };
+    read(MODE1)?;
+    let mode2 = read(MODE2)?;
+    Ok(mode2 & 0b_1110_0000 == 0)
+}
+
 pub fn collect_frequency(v: f64) -> (f64, u8) {
     let prescale = {
         let v = OSC / (PULSE_BASE * v) - 1.0;
-        v.max(3.0).min(255.0)
+        v.max(3.0).min(255.0) as u8
+    };
+    (prescale_frequency(prescale), prescale)
+}
+
+/// The frequency in Hz generated with the prescale.
+pub fn prescale_frequency(prescale: u8) -> f64 {
+    OSC / (PULSE_BASE * (prescale as f64 + 1.0))
+}
+
+/// The first register (LEDn_ON_L) of the channel.
+pub(crate) fn channel_register(channel: Channel) -> RegAddr {
+    match channel {
+        Channel::All => ALL_LED_ON_L,
+        _ => RegAddr(LED0_ON_L.0 + 4 * channel as u8),
+    }
+}
+
+fn pwm_error<E: BusError>(
+    address: I2cAddr,
+    reg: Option<RegAddr>,
+    err: pwm_pca9685::Error<E>,
+) -> Error {
+    let e = match err {
+        pwm_pca9685::Error::I2C(e) => {
+            Error::new(ErrorKind::Write, Device::Pca9685, address).with_source(e)
+        }
+        pwm_pca9685::Error::InvalidInputData => {
+            Error::new(ErrorKind::InvalidInput, Device::Pca9685, address)
+        }
     };
-    let frequency = OSC / (PULSE_BASE * (prescale + 1.0));
-    (frequency, prescale as u8)
+    match reg {
+        Some(reg) => e.with_register(reg),
+        None => e,
+    }
 }
 
 fn calc_pulse(rate: f64) -> u16 {
@@ -109,6 +316,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn channel_registers() {
+        assert_eq!(channel_register(Channel::C0), RegAddr(0x06));
+        assert_eq!(channel_register(Channel::C1), RegAddr(0x0a));
+        assert_eq!(channel_register(Channel::C15), RegAddr(0x42));
+        assert_eq!(channel_register(Channel::All), RegAddr(0xfa));
+    }
+
     #[test]
     fn calc_pulse_min() {
         let pulse = calc_pulse(0.0);
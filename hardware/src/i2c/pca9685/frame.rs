@@ -0,0 +1,172 @@
+use super::{calc_pulse, PULSE_BASE};
+
+use pwm_pca9685::Channel;
+
+pub const CHANNELS: usize = 16;
+
+/// The counts of the 12 bit counter where the output of a channel turns on and off.
+/// The output is off all the time if they are the same.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Counts {
+    pub on: u16,
+    pub off: u16,
+}
+
+impl Counts {
+    /// The length of the pulse in counts.
+    pub fn width(&self) -> u16 {
+        (self.off + PULSE_BASE as u16 - self.on) % PULSE_BASE as u16
+    }
+
+    /// The counts of the pulse starting at the same count with the width scaled by the ratio.
+    /// The pulse is at most 4095 counts long.
+    pub fn scale(&self, ratio: f64) -> Counts {
+        let width = (self.width() as f64 * ratio).round().min(PULSE_BASE - 1.0) as u16;
+        Counts {
+            on: self.on,
+            off: (self.on + width) % PULSE_BASE as u16,
+        }
+    }
+}
+
+/// Where the pulses of the channels start in the period.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Stagger {
+    /// All of the pulses start at the beginning of the period.
+    #[default]
+    None,
+    /// The pulse of channel n starts n/16 of the period later,
+    /// so that the current spikes of the servos are spread over the period.
+    /// The widths of the pulses are not changed.
+    Spread,
+}
+
+impl Stagger {
+    /// The count where the pulse of the channel starts.
+    pub fn on_count(&self, index: usize) -> u16 {
+        match self {
+            Stagger::None => 0,
+            Stagger::Spread => (index * PULSE_BASE as usize / CHANNELS) as u16,
+        }
+    }
+}
+
+/// The duty cycles of the channels of a board to be written together by `PCA9685::write_frame`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PwmFrame {
+    counts: [Option<Counts>; CHANNELS],
+    stagger: Stagger,
+}
+
+impl PwmFrame {
+    pub fn new(stagger: Stagger) -> Self {
+        Self {
+            counts: [None; CHANNELS],
+            stagger,
+        }
+    }
+
+    pub fn stagger(&self) -> Stagger {
+        self.stagger
+    }
+
+    /// Sets the duty cycle from 0 to 1 of the channel, or of every channel with `Channel::All`.
+    /// The pulse is at most 4095 counts long.
+    pub fn set(&mut self, channel: Channel, rate: f64) -> &mut Self {
+        match channel {
+            Channel::All => (0..CHANNELS).for_each(|i| self.set_index(i, rate)),
+            _ => self.set_index(channel as usize, rate),
+        }
+        self
+    }
+
+    fn set_index(&mut self, index: usize, rate: f64) {
+        let width = calc_pulse(rate.max(0.0)).min(PULSE_BASE as u16 - 1);
+        let on = self.stagger.on_count(index);
+        let off = (on + width) % PULSE_BASE as u16;
+        self.counts[index] = Some(Counts { on, off });
+    }
+
+    /// `None` unless the channel has been set.
+    pub fn get(&self, channel: Channel) -> Option<Counts> {
+        match channel {
+            Channel::All => None,
+            _ => self.counts[channel as usize],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.iter().all(Option::is_none)
+    }
+
+    /// The counts of all of the channels: the ones set in the frame and `current` for the others.
+    pub fn resolve(&self, current: &[Counts; CHANNELS]) -> [Counts; CHANNELS] {
+        let mut counts = *current;
+        for (c, v) in counts.iter_mut().zip(self.counts.iter()) {
+            if let Some(v) = v {
+                *c = *v;
+            }
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_without_stagger() {
+        let mut frame = PwmFrame::new(Stagger::None);
+        frame.set(Channel::C3, 0.075).set(Channel::C15, 1.0);
+        assert_eq!(frame.get(Channel::C3), Some(Counts { on: 0, off: 307 }));
+        assert_eq!(frame.get(Channel::C15), Some(Counts { on: 0, off: 4095 }));
+        assert_eq!(frame.get(Channel::C0), None);
+    }
+
+    #[test]
+    fn counts_with_stagger() {
+        let mut frame = PwmFrame::new(Stagger::Spread);
+        frame.set(Channel::All, 0.1);
+        assert_eq!(frame.get(Channel::C0), Some(Counts { on: 0, off: 409 }));
+        assert_eq!(frame.get(Channel::C1), Some(Counts { on: 256, off: 665 }));
+        // Wraps around the end of the period.
+        assert_eq!(frame.get(Channel::C15), Some(Counts { on: 3840, off: 153 }));
+    }
+
+    #[test]
+    fn scale_widths() {
+        let counts = Counts { on: 3840, off: 153 };
+        assert_eq!(counts.width(), 409);
+        assert_eq!(
+            counts.scale(0.5),
+            Counts {
+                on: 3840,
+                off: 4045
+            }
+        );
+        assert_eq!(
+            counts.scale(20.0),
+            Counts {
+                on: 3840,
+                off: 3839
+            }
+        );
+        assert_eq!(Counts::default().scale(2.0), Counts::default());
+    }
+
+    #[test]
+    fn resolve_keeps_the_others() {
+        let mut current = [Counts::default(); CHANNELS];
+        current[1] = Counts { on: 0, off: 300 };
+        current[2] = Counts { on: 0, off: 400 };
+
+        let mut frame = PwmFrame::default();
+        assert!(frame.is_empty());
+        frame.set(Channel::C2, 0.05);
+        let counts = frame.resolve(&current);
+        assert_eq!(counts[0], Counts::default());
+        assert_eq!(counts[1], Counts { on: 0, off: 300 });
+        assert_eq!(counts[2], Counts { on: 0, off: 204 });
+    }
+}
@@ -0,0 +1,501 @@
+//! Recording of the transactions on an I2C bus and their replay.
+//!
+//! `RecordingI2c` wraps a bus and writes every transaction to a file, one line each:
+//!
+//! ```text
+//! # elapsed_us address kind written read result
+//! 1520 68 wr 3b 0a0b0c0d0e0f ok
+//! 1604 40 w 0610000000 - ok
+//! ```
+//!
+//! `ReplayI2c` answers the same transactions from the recording,
+//! so the drivers and the controller can be run again without the hardware.
+//! Both implement `Write + WriteRead`, so they are wrapped by `ThreadSafeI2c` and
+//! `I2cWithAddr` like any other bus.
+//! The recording of a bus shared by several threads is in the order of the bus,
+//! which the replay reproduces only if the threads access it in the same order.
+//!
+//! ```ignore
+//! buses.register("main", || RecordingI2c::create(open_i2cdev(1)?, "walk.rec"));
+//! // On another machine.
+//! buses.register("main", || ReplayI2c::open("walk.rec"));
+//! ```
+
+use super::{I2cAddr, I2cBus, LockBus, LockedBus};
+
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+use embedded_hal::blocking::i2c::{SevenBitAddress, Write, WriteRead};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const HEADER: &str = "# elapsed_us address kind written read result";
+
+/// A transaction on the bus.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Transaction {
+    /// Since the recording started.
+    pub elapsed: Duration,
+    pub address: I2cAddr,
+    pub written: Vec<u8>,
+    /// The bytes read by `write_read`, or `None` for `write`.
+    pub read: Option<Vec<u8>>,
+    /// Whether the bus succeeded.
+    pub ok: bool,
+}
+
+impl Transaction {
+    /// Whether the other is the same request to the bus, regardless of the time and the response.
+    pub fn same_request(&self, other: &Transaction) -> bool {
+        self.address == other.address
+            && self.written == other.written
+            && self.read.as_ref().map(Vec::len) == other.read.as_ref().map(Vec::len)
+    }
+}
+
+impl Display for Transaction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let (kind, read) = match &self.read {
+            Some(read) => ("wr", to_hex(read)),
+            None => ("w", "-".to_string()),
+        };
+        write!(
+            f,
+            "{} {:02x} {} {} {} {}",
+            self.elapsed.as_micros(),
+            self.address.0,
+            kind,
+            to_hex(&self.written),
+            read,
+            if self.ok { "ok" } else { "err" }
+        )
+    }
+}
+
+impl FromStr for Transaction {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid transaction: '{}'", s),
+            )
+        };
+        let cols: Vec<_> = s.split_whitespace().collect();
+        if cols.len() != 6 {
+            return Err(invalid());
+        }
+        let elapsed = cols[0].parse().map_err(|_| invalid())?;
+        let address = u8::from_str_radix(cols[1], 16).map_err(|_| invalid())?;
+        let written = from_hex(cols[3]).ok_or_else(invalid)?;
+        let read = match cols[2] {
+            "wr" => Some(from_hex(cols[4]).ok_or_else(invalid)?),
+            "w" => None,
+            _ => return Err(invalid()),
+        };
+        let ok = match cols[5] {
+            "ok" => true,
+            "err" => false,
+            _ => return Err(invalid()),
+        };
+        Ok(Self {
+            elapsed: Duration::from_micros(elapsed),
+            address: I2cAddr(address),
+            written,
+            read,
+            ok,
+        })
+    }
+}
+
+/// Reads the transactions written by `RecordingI2c`.
+pub fn read_transactions<R: BufRead>(reader: R) -> io::Result<Vec<Transaction>> {
+    let mut transactions = vec![];
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        transactions.push(line.parse()?);
+    }
+    Ok(transactions)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        return "-".to_string();
+    }
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if s == "-" {
+        return Some(vec![]);
+    }
+    s.as_bytes()
+        .chunks(2)
+        .map(|c| match c {
+            [_, _] => u8::from_str_radix(std::str::from_utf8(c).ok()?, 16).ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+// ----------------------------------------------------------------
+
+/// Passes the transactions to the inner bus and writes them to `out`.
+///
+/// A failure to write the recording does not fail the bus;
+/// the recording stops for good and the error is returned by every `flush` after it,
+/// so that a recording with a gap is not taken for a complete one.
+///
+/// `lock_bus` locks the inner bus, and the transactions on the locked bus are recorded too.
+pub struct RecordingI2c<T, W: io::Write = BufWriter<File>> {
+    inner: T,
+    recorder: Recorder<W>,
+}
+
+struct Recorder<W> {
+    out: W,
+    start: Instant,
+    error: Option<io::Error>,
+}
+
+impl<T> RecordingI2c<T> {
+    /// Records to a new file, replacing the existing one.
+    pub fn create<P: AsRef<Path>>(inner: T, path: P) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Self::new(inner, BufWriter::new(file))
+    }
+}
+
+impl<T, W: io::Write> RecordingI2c<T, W> {
+    pub fn new(inner: T, mut out: W) -> io::Result<Self> {
+        writeln!(out, "{}", HEADER)?;
+        Ok(Self {
+            inner,
+            recorder: Recorder {
+                out,
+                start: Instant::now(),
+                error: None,
+            },
+        })
+    }
+
+    pub fn inner(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Flushes the recording, or returns the error which stopped it.
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &self.recorder.error {
+            Some(err) => Err(io::Error::new(err.kind(), err.to_string())),
+            None => self.recorder.out.flush(),
+        }
+    }
+}
+
+impl<W: io::Write> Recorder<W> {
+    fn record(&mut self, address: SevenBitAddress, written: &[u8], read: Option<&[u8]>, ok: bool) {
+        if self.error.is_some() {
+            return;
+        }
+        let transaction = Transaction {
+            elapsed: self.start.elapsed(),
+            address: I2cAddr(address),
+            written: written.to_vec(),
+            read: read.map(|r| r.to_vec()),
+            ok,
+        };
+        if let Err(err) = writeln!(self.out, "{}", transaction) {
+            self.error = Some(err);
+        }
+    }
+}
+
+impl<T: Write, W: io::Write> Write for RecordingI2c<T, W> {
+    type Error = <T as Write>::Error;
+
+    fn write(&mut self, address: SevenBitAddress, bytes: &[u8]) -> Result<(), Self::Error> {
+        let res = self.inner.write(address, bytes);
+        self.recorder.record(address, bytes, None, res.is_ok());
+        res
+    }
+}
+
+impl<T: WriteRead, W: io::Write> WriteRead for RecordingI2c<T, W> {
+    type Error = <T as WriteRead>::Error;
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddress,
+        bytes: &[u8],
+        buf: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let res = self.inner.write_read(address, bytes, buf);
+        self.recorder.record(address, bytes, Some(buf), res.is_ok());
+        res
+    }
+}
+
+impl<T: LockBus, W: io::Write> LockBus for RecordingI2c<T, W> {
+    fn lock_bus<R>(&mut self, f: impl FnOnce(&mut LockedBus<'_, Self>) -> R) -> R {
+        let recorder = &mut self.recorder;
+        self.inner
+            .lock_bus(|bus| f(&mut LockedRecordingI2c { bus, recorder }))
+    }
+}
+
+/// The inner bus locked by `RecordingI2c::lock_bus`, recording the transactions.
+pub struct LockedRecordingI2c<'a, WE, RE, W> {
+    bus: &'a mut dyn I2cBus<WE, RE>,
+    recorder: &'a mut Recorder<W>,
+}
+
+impl<WE, RE, W: io::Write> Write for LockedRecordingI2c<'_, WE, RE, W> {
+    type Error = WE;
+
+    fn write(&mut self, address: SevenBitAddress, bytes: &[u8]) -> Result<(), Self::Error> {
+        let res = self.bus.write(address, bytes);
+        self.recorder.record(address, bytes, None, res.is_ok());
+        res
+    }
+}
+
+impl<WE, RE, W: io::Write> WriteRead for LockedRecordingI2c<'_, WE, RE, W> {
+    type Error = RE;
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddress,
+        bytes: &[u8],
+        buf: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let res = self.bus.write_read(address, bytes, buf);
+        self.recorder.record(address, bytes, Some(buf), res.is_ok());
+        res
+    }
+}
+
+impl<T, W: io::Write> Drop for RecordingI2c<T, W> {
+    fn drop(&mut self) {
+        let _ = self.recorder.out.flush();
+    }
+}
+
+// ----------------------------------------------------------------
+
+/// The error of `ReplayI2c`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ReplayError {
+    /// The request differs from the recording.
+    /// `expected` is `None` if the recording has ended.
+    Diverged {
+        index: usize,
+        expected: Option<Box<Transaction>>,
+        actual: Box<Transaction>,
+    },
+    /// The transaction failed when it was recorded.
+    Recorded { index: usize },
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ReplayError::Diverged {
+                index,
+                expected: Some(expected),
+                actual,
+            } => write!(
+                f,
+                "Transaction {} diverged from the recording: expected '{}', got '{}'",
+                index, expected, actual
+            ),
+            ReplayError::Diverged {
+                index,
+                expected: None,
+                actual,
+            } => write!(
+                f,
+                "Transaction {} is beyond the recording: got '{}'",
+                index, actual
+            ),
+            ReplayError::Recorded { index } => {
+                write!(f, "Transaction {} failed in the recording", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// Answers the transactions from a recording, in order.
+///
+/// A request which differs from the recording fails with `ReplayError::Diverged`,
+/// and so does every request after it.
+/// The time of the recording is not reproduced.
+pub struct ReplayI2c {
+    transactions: VecDeque<Transaction>,
+    index: usize,
+    divergence: Option<ReplayError>,
+}
+
+impl ReplayI2c {
+    pub fn new(transactions: Vec<Transaction>) -> Self {
+        Self {
+            transactions: transactions.into(),
+            index: 0,
+            divergence: None,
+        }
+    }
+
+    /// Loads the file written by `RecordingI2c`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        Ok(Self::new(read_transactions(BufReader::new(file))?))
+    }
+
+    /// The number of transactions replayed.
+    pub fn position(&self) -> usize {
+        self.index
+    }
+
+    /// The number of transactions not replayed yet.
+    pub fn remaining(&self) -> usize {
+        self.transactions.len()
+    }
+
+    /// The first divergence from the recording, if any.
+    pub fn divergence(&self) -> Option<&ReplayError> {
+        self.divergence.as_ref()
+    }
+
+    /// Whether all of the recording has been replayed without divergence.
+    pub fn is_finished(&self) -> bool {
+        self.transactions.is_empty() && self.divergence.is_none()
+    }
+
+    fn next(&mut self, actual: Transaction) -> Result<Transaction, ReplayError> {
+        if let Some(err) = &self.divergence {
+            return Err(err.clone());
+        }
+        let index = self.index;
+        match self.transactions.front() {
+            Some(expected) if expected.same_request(&actual) => {
+                self.index += 1;
+                let expected = self.transactions.pop_front().expect("The front exists.");
+                if expected.ok {
+                    Ok(expected)
+                } else {
+                    Err(ReplayError::Recorded { index })
+                }
+            }
+            expected => {
+                let err = ReplayError::Diverged {
+                    index,
+                    expected: expected.cloned().map(Box::new),
+                    actual: Box::new(actual),
+                };
+                self.divergence = Some(err.clone());
+                Err(err)
+            }
+        }
+    }
+}
+
+fn request(address: SevenBitAddress, bytes: &[u8], read: Option<&[u8]>) -> Transaction {
+    Transaction {
+        elapsed: Duration::ZERO,
+        address: I2cAddr(address),
+        written: bytes.to_vec(),
+        read: read.map(|r| r.to_vec()),
+        ok: true,
+    }
+}
+
+impl Write for ReplayI2c {
+    type Error = ReplayError;
+
+    fn write(&mut self, address: SevenBitAddress, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.next(request(address, bytes, None))?;
+        Ok(())
+    }
+}
+
+impl WriteRead for ReplayI2c {
+    type Error = ReplayError;
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddress,
+        bytes: &[u8],
+        buf: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let recorded = self.next(request(address, bytes, Some(buf)))?;
+        buf.copy_from_slice(&recorded.read.expect("The request is write_read."));
+        Ok(())
+    }
+}
+
+impl LockBus for ReplayI2c {
+    fn lock_bus<R>(&mut self, f: impl FnOnce(&mut LockedBus<'_, Self>) -> R) -> R {
+        f(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transaction_line() {
+        let t = Transaction {
+            elapsed: Duration::from_micros(1520),
+            address: I2cAddr(0x68),
+            written: vec![0x3b],
+            read: Some(vec![0x0a, 0xff]),
+            ok: true,
+        };
+        assert_eq!(t.to_string(), "1520 68 wr 3b 0aff ok");
+        assert_eq!("1520 68 wr 3b 0aff ok".parse::<Transaction>().unwrap(), t);
+
+        let t = Transaction {
+            elapsed: Duration::from_micros(7),
+            address: I2cAddr(0x40),
+            written: vec![],
+            read: None,
+            ok: false,
+        };
+        assert_eq!(t.to_string(), "7 40 w - - err");
+        assert_eq!("7 40 w - - err".parse::<Transaction>().unwrap(), t);
+    }
+
+    #[test]
+    fn invalid_lines() {
+        for line in [
+            "",
+            "1520 68 wr 3b 0aff",
+            "1520 68 rw 3b 0aff ok",
+            "1520 68 wr 3 0aff ok",
+            "1520 68 wr 3b 0aff maybe",
+            "x 68 w 3b - ok",
+            "1520 1ff w 3b - ok",
+        ] {
+            let err = line.parse::<Transaction>().expect_err(line);
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn read_with_comments() {
+        let text = format!("{}\n\n1 68 w 6b00 - ok\n# note\n2 68 wr 75 68 ok\n", HEADER);
+        let transactions = read_transactions(text.as_bytes()).unwrap();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[1].read, Some(vec![0x68]));
+    }
+}
@@ -0,0 +1,71 @@
+use super::RegAddr;
+
+use std::collections::HashMap;
+
+/// Write-through shadow of the registers of a device.
+///
+/// It remembers the last value written to or read from each register,
+/// so that reading a configuration register or writing the same value again
+/// does not need a transfer.
+/// Registers marked `VOLATILE` are never cached.
+/// The cache must be invalidated when the device changes the registers by itself, e.g. by a reset.
+#[derive(Debug, Default, Clone)]
+pub struct RegisterCache {
+    values: HashMap<RegAddr, u8>,
+}
+
+impl RegisterCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, reg: RegAddr) -> Option<u8> {
+        self.values.get(&reg).copied()
+    }
+
+    pub fn store(&mut self, reg: RegAddr, v: u8) {
+        self.values.insert(reg, v);
+    }
+
+    /// Forgets the consecutive registers from `reg`.
+    pub fn invalidate(&mut self, reg: RegAddr, len: usize) {
+        for i in 0..len {
+            self.values.remove(&RegAddr(reg.0.wrapping_add(i as u8)));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_and_invalidate() {
+        let mut cache = RegisterCache::new();
+        cache.store(RegAddr(0x06), 0x12);
+        cache.store(RegAddr(0x07), 0x34);
+        cache.store(RegAddr(0x1b), 0x18);
+        assert_eq!(cache.get(RegAddr(0x07)), Some(0x34));
+
+        cache.invalidate(RegAddr(0x05), 3);
+        assert_eq!(cache.get(RegAddr(0x06)), None);
+        assert_eq!(cache.get(RegAddr(0x07)), None);
+        assert_eq!(cache.get(RegAddr(0x1b)), Some(0x18));
+        assert_eq!(cache.len(), 1);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
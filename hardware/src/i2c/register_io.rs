@@ -1,7 +1,7 @@
-use super::I2cAddr;
+use super::{I2cAddr, RegisterCache};
+use crate::error::*;
 
 use core::fmt::Debug;
-use core::fmt::Formatter;
 use derive_more::{From, Into};
 use embedded_hal::blocking::i2c::{Write, WriteRead};
 
@@ -10,44 +10,164 @@ pub struct RegAddr(pub u8);
 
 pub trait Register: From<u8> + Into<u8> + Debug + Copy + Eq {
     const ADDR: RegAddr;
+    /// The device changes the value by itself, so it must not be cached.
+    const VOLATILE: bool = false;
 }
 
+/// A bus which can be held exclusively across several transfers.
+///
+/// A bus shared between threads (`ThreadSafeI2c`) locks for every transfer,
+/// so another thread can access the device between a read and the following write.
+/// `lock_bus` holds the lock until the closure returns.
+///
+/// A layer over a bus (`RetryingI2c`, `RecordingI2c`) locks the bus under it,
+/// and passes the locked bus wrapped in its own layer.
+pub trait LockBus: Write + WriteRead {
+    fn lock_bus<R>(&mut self, f: impl FnOnce(&mut LockedBus<'_, Self>) -> R) -> R;
+}
+
+impl LockBus for linux_embedded_hal::I2cdev {
+    fn lock_bus<R>(&mut self, f: impl FnOnce(&mut LockedBus<'_, Self>) -> R) -> R {
+        f(self)
+    }
+}
+
+/// A bus of both of the transfers, for a locked bus as a trait object.
+pub trait I2cBus<WE, RE>: Write<Error = WE> + WriteRead<Error = RE> {}
+
+impl<T, WE, RE> I2cBus<WE, RE> for T where T: Write<Error = WE> + WriteRead<Error = RE> {}
+
+/// The bus passed to the closure of `LockBus::lock_bus`, with the errors of the bus `T`.
+pub type LockedBus<'a, T> = dyn I2cBus<<T as Write>::Error, <T as WriteRead>::Error> + 'a;
+
 pub trait I2cRegister<T>
 where
     T: Write + WriteRead,
-    <T as Write>::Error: Debug,
-    <T as WriteRead>::Error: Debug,
+    <T as Write>::Error: BusError,
+    <T as WriteRead>::Error: BusError,
 {
     fn i2c_device(&mut self) -> &mut T;
     fn address(&self) -> I2cAddr;
+    fn device(&self) -> Device;
+
+    /// The shadow of the registers, if the implementor keeps one.
+    /// Only the `Register` methods use it; the byte methods always access the bus.
+    fn cache(&mut self) -> Option<&mut RegisterCache> {
+        None
+    }
+
+    /// Forgets the cached values. Call it after a reset of the device.
+    fn invalidate_cache(&mut self) {
+        if let Some(cache) = self.cache() {
+            cache.clear();
+        }
+    }
 
-    fn read_bytes(&mut self, reg: RegAddr, res: &mut [u8]) -> Result<(), Error<T>> {
+    fn read_bytes(&mut self, reg: RegAddr, res: &mut [u8]) -> Result<(), Error> {
         let addr = self.address();
+        let device = self.device();
         self.i2c_device()
             .write_read(addr.into(), &[reg.into()], res)
-            .map_err(Error::WriteReadError)
+            .map_err(|e| Error::write_read(device, addr, reg, e))
     }
 
-    fn read_byte(&mut self, reg: RegAddr) -> Result<u8, Error<T>> {
+    fn read_byte(&mut self, reg: RegAddr) -> Result<u8, Error> {
         let mut buf = [0; 1];
         self.read_bytes(reg, &mut buf)?;
         Ok(buf[0])
     }
 
-    fn write_byte(&mut self, reg: RegAddr, v: u8) -> Result<(), Error<T>> {
+    fn write_byte(&mut self, reg: RegAddr, v: u8) -> Result<(), Error> {
         let addr = self.address();
+        let device = self.device();
+        if let Some(cache) = self.cache() {
+            cache.invalidate(reg, 1);
+        }
         self.i2c_device()
             .write(addr.into(), &[reg.into(), v])
-            .map_err(Error::WriteError)
+            .map_err(|e| Error::write(device, addr, reg, e))
     }
 
-    fn read_register<R: Register>(&mut self) -> Result<R, Error<T>> {
+    /// Served from the cache if the register is cached.
+    fn read_register<R: Register>(&mut self) -> Result<R, Error> {
+        if let Some(byte) = cached::<R>(self.cache()) {
+            return Ok(R::from(byte));
+        }
         let byte = self.read_byte(R::ADDR)?;
+        store::<R>(self.cache(), byte);
         Ok(R::from(byte))
     }
 
-    fn write_register<R: Register>(&mut self, reg_value: R) -> Result<(), Error<T>> {
-        self.write_byte(R::ADDR, reg_value.into())
+    /// Skipped if the cache has the same value.
+    fn write_register<R: Register>(&mut self, reg_value: R) -> Result<(), Error> {
+        let byte = reg_value.into();
+        if cached::<R>(self.cache()) == Some(byte) {
+            return Ok(());
+        }
+        self.write_byte(R::ADDR, byte)?;
+        store::<R>(self.cache(), byte);
+        Ok(())
+    }
+
+    /// Writes the bytes to the consecutive registers from `reg` in one transfer.
+    /// The device must increment the register address automatically.
+    fn write_bytes(&mut self, reg: RegAddr, bytes: &[u8]) -> Result<(), Error> {
+        let addr = self.address();
+        let device = self.device();
+        let mut buf = Vec::with_capacity(bytes.len() + 1);
+        buf.push(reg.into());
+        buf.extend_from_slice(bytes);
+        if let Some(cache) = self.cache() {
+            cache.invalidate(reg, bytes.len());
+        }
+        self.i2c_device()
+            .write(addr.into(), &buf)
+            .map_err(|e| Error::write(device, addr, reg, e))
+    }
+
+    /// Reads the register, lets `f` modify it and writes it back,
+    /// holding the bus so that no other access comes in between.
+    /// Returns the written value.
+    /// A cached register is not read, and is not written if the value does not change.
+    fn modify_register<R, F>(&mut self, f: F) -> Result<R, Error>
+    where
+        R: Register,
+        F: FnOnce(&mut R),
+        T: LockBus,
+    {
+        if let Some(byte) = cached::<R>(self.cache()) {
+            let mut value = R::from(byte);
+            f(&mut value);
+            self.write_register(value)?;
+            return Ok(value);
+        }
+        let addr = self.address();
+        let device = self.device();
+        let value = self.i2c_device().lock_bus(|bus| {
+            let mut buf = [0; 1];
+            bus.write_read(addr.into(), &[R::ADDR.into()], &mut buf)
+                .map_err(|e| Error::write_read(device, addr, R::ADDR, e))?;
+            let mut value = R::from(buf[0]);
+            f(&mut value);
+            bus.write(addr.into(), &[R::ADDR.into(), value.into()])
+                .map_err(|e| Error::write(device, addr, R::ADDR, e))?;
+            Ok::<_, Error>(value)
+        })?;
+        store::<R>(self.cache(), value.into());
+        Ok(value)
+    }
+}
+
+fn cached<R: Register>(cache: Option<&mut RegisterCache>) -> Option<u8> {
+    if R::VOLATILE {
+        return None;
+    }
+    cache.and_then(|c| c.get(R::ADDR))
+}
+
+fn store<R: Register>(cache: Option<&mut RegisterCache>, v: u8) {
+    if let (false, Some(cache)) = (R::VOLATILE, cache) {
+        cache.store(R::ADDR, v);
     }
 }
 
@@ -55,19 +175,42 @@ where
 pub struct I2cWithAddr<T> {
     dev: T,
     address: I2cAddr,
+    device: Device,
+    cache: Option<RegisterCache>,
 }
 
 impl<T> I2cWithAddr<T> {
     pub fn new(dev: T, address: I2cAddr) -> Self {
-        Self { dev, address }
+        Self {
+            dev,
+            address,
+            device: Device::Unknown,
+            cache: None,
+        }
+    }
+
+    /// Sets which device is at the address. It is reported in errors.
+    pub fn set_device(&mut self, device: Device) {
+        self.device = device;
+    }
+
+    /// Keeps the shadow of the registers from now on.
+    /// The registers must not be written other than through this value,
+    /// and a clone has its own cache.
+    pub fn enable_cache(&mut self) {
+        self.cache.get_or_insert_with(RegisterCache::new);
+    }
+
+    pub fn disable_cache(&mut self) {
+        self.cache = None;
     }
 }
 
 impl<T> I2cRegister<T> for I2cWithAddr<T>
 where
     T: Write + WriteRead,
-    <T as Write>::Error: Debug,
-    <T as WriteRead>::Error: Debug,
+    <T as Write>::Error: BusError,
+    <T as WriteRead>::Error: BusError,
 {
     fn i2c_device(&mut self) -> &mut T {
         &mut self.dev
@@ -76,30 +219,12 @@ where
     fn address(&self) -> I2cAddr {
         self.address
     }
-}
 
-// ----------------------------------------------------------------
-
-pub enum Error<T>
-where
-    T: Write + WriteRead,
-    <T as Write>::Error: Debug,
-    <T as WriteRead>::Error: Debug,
-{
-    WriteError(<T as Write>::Error),
-    WriteReadError(<T as WriteRead>::Error),
-}
+    fn device(&self) -> Device {
+        self.device
+    }
 
-impl<I2c> Debug for Error<I2c>
-where
-    I2c: WriteRead + Write,
-    <I2c as WriteRead>::Error: Debug,
-    <I2c as Write>::Error: Debug,
-{
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), core::fmt::Error> {
-        match self {
-            Error::WriteReadError(e) => f.debug_tuple("WriteReadError").field(e).finish(),
-            Error::WriteError(e) => f.debug_tuple("WriteError").field(e).finish(),
-        }
+    fn cache(&mut self) -> Option<&mut RegisterCache> {
+        self.cache.as_mut()
     }
 }
@@ -0,0 +1,296 @@
+use super::{I2cAddr, I2cBus, LockBus, LockedBus, RegAddr};
+
+use embedded_hal::blocking::i2c::{SevenBitAddress, Write, WriteRead};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// How `RetryingI2c` repeats a failed transaction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// The number of retries after the first attempt.
+    pub retries: u32,
+    /// The wait before the first retry.
+    pub backoff: Duration,
+    /// The wait is multiplied by this at every retry.
+    pub backoff_factor: f64,
+    /// No retry is made which would start later than this after the first attempt.
+    pub deadline: Option<Duration>,
+    /// The recovery runs after this number of consecutive failed attempts.
+    pub recover_after: Option<u32>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            backoff: Duration::from_millis(1),
+            backoff_factor: 2.0,
+            deadline: Some(Duration::from_millis(50)),
+            recover_after: Some(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retries nor recovers.
+    pub fn none() -> Self {
+        Self {
+            retries: 0,
+            backoff: Duration::ZERO,
+            backoff_factor: 1.0,
+            deadline: None,
+            recover_after: None,
+        }
+    }
+
+    /// The wait before the retry, counted from 1.
+    pub fn backoff_of(&self, retry: u32) -> Duration {
+        let factor = self
+            .backoff_factor
+            .max(1.0)
+            .powi(retry.saturating_sub(1) as i32);
+        self.backoff.mul_f64(factor)
+    }
+}
+
+/// Counters of `RetryingI2c`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RetryStats {
+    pub transactions: u64,
+    pub retries: u64,
+    /// Transactions which failed after all of the retries.
+    pub failures: u64,
+    pub recoveries: u64,
+    /// Recoveries which returned an error.
+    pub recovery_failures: u64,
+}
+
+type Recovery<T> = Box<dyn FnMut(&mut T) -> std::io::Result<()> + Send>;
+
+/// Retries the transactions failed on the inner bus.
+///
+/// Every error of the inner bus is retried, so an address without a device
+/// takes all of the retries to fail; scan the bus without this layer.
+/// After `RetryPolicy::recover_after` consecutive failed attempts,
+/// the recovery, e.g. opening the device again or writing the setup of the drivers,
+/// is run with the inner bus.
+/// The error of the last attempt is returned when the retries run out.
+///
+/// A failed read may have changed the device already, e.g. popped bytes from a FIFO
+/// or cleared a status. Mark such registers with `set_no_retry`,
+/// e.g. with `mpu6050::NO_RETRY_REGISTERS`, so that the reads from them are tried only once.
+///
+/// `lock_bus` locks the inner bus, and the transactions on the locked bus are retried too.
+/// The recovery is not run while the bus is locked, but at the next failure after it.
+pub struct RetryingI2c<T> {
+    inner: T,
+    state: RetryState,
+    recovery: Option<Recovery<T>>,
+}
+
+struct RetryState {
+    policy: RetryPolicy,
+    no_retry: HashSet<(I2cAddr, RegAddr)>,
+    consecutive_failures: u32,
+    stats: RetryStats,
+}
+
+impl<T> RetryingI2c<T> {
+    pub fn new(inner: T, policy: RetryPolicy) -> Self {
+        Self {
+            inner,
+            state: RetryState {
+                policy,
+                no_retry: HashSet::new(),
+                consecutive_failures: 0,
+                stats: RetryStats::default(),
+            },
+            recovery: None,
+        }
+    }
+
+    pub fn set_recovery<F>(&mut self, f: F)
+    where
+        F: FnMut(&mut T) -> std::io::Result<()> + Send + 'static,
+    {
+        self.recovery = Some(Box::new(f));
+    }
+
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.state.policy
+    }
+
+    pub fn set_policy(&mut self, policy: RetryPolicy) {
+        self.state.policy = policy;
+    }
+
+    /// The reads from the registers of the device are not retried.
+    /// A read from the first register of a burst is not retried either.
+    pub fn set_no_retry(&mut self, address: I2cAddr, regs: &[RegAddr]) {
+        self.state
+            .no_retry
+            .extend(regs.iter().map(|reg| (address, *reg)));
+    }
+
+    pub fn stats(&self) -> RetryStats {
+        self.state.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.state.stats = RetryStats::default();
+    }
+
+    pub fn inner(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    fn run<R, E>(
+        &mut self,
+        retryable: bool,
+        f: impl FnMut(&mut T) -> Result<R, E>,
+    ) -> Result<R, E> {
+        let recovery = &mut self.recovery;
+        self.state.run(&mut self.inner, retryable, f, |bus| {
+            recovery.as_mut().map(|recovery| recovery(bus).is_ok())
+        })
+    }
+}
+
+impl RetryState {
+    fn retryable(&self, address: SevenBitAddress, bytes: &[u8]) -> bool {
+        match bytes.first() {
+            Some(reg) => !self.no_retry.contains(&(I2cAddr(address), RegAddr(*reg))),
+            None => true,
+        }
+    }
+
+    /// `recover` returns whether the recovery succeeded, or `None` if it is not run.
+    fn run<B: ?Sized, R, E>(
+        &mut self,
+        bus: &mut B,
+        retryable: bool,
+        mut f: impl FnMut(&mut B) -> Result<R, E>,
+        mut recover: impl FnMut(&mut B) -> Option<bool>,
+    ) -> Result<R, E> {
+        self.stats.transactions += 1;
+        let start = Instant::now();
+        let mut retry = 0;
+        loop {
+            let err = match f(bus) {
+                Ok(v) => {
+                    self.consecutive_failures = 0;
+                    return Ok(v);
+                }
+                Err(err) => err,
+            };
+            self.consecutive_failures += 1;
+            if matches!(self.policy.recover_after, Some(n) if self.consecutive_failures >= n) {
+                if let Some(ok) = recover(bus) {
+                    self.consecutive_failures = 0;
+                    self.stats.recoveries += 1;
+                    if !ok {
+                        self.stats.recovery_failures += 1;
+                    }
+                }
+            }
+
+            retry += 1;
+            let wait = self.policy.backoff_of(retry);
+            let past_deadline = self
+                .policy
+                .deadline
+                .map(|deadline| start.elapsed() + wait > deadline)
+                .unwrap_or(false);
+            if !retryable || retry > self.policy.retries || past_deadline {
+                self.stats.failures += 1;
+                return Err(err);
+            }
+            self.stats.retries += 1;
+            if !wait.is_zero() {
+                std::thread::sleep(wait);
+            }
+        }
+    }
+}
+
+impl<T: Write> Write for RetryingI2c<T> {
+    type Error = <T as Write>::Error;
+
+    fn write(&mut self, address: SevenBitAddress, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.run(true, |bus| bus.write(address, bytes))
+    }
+}
+
+impl<T: WriteRead> WriteRead for RetryingI2c<T> {
+    type Error = <T as WriteRead>::Error;
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddress,
+        bytes: &[u8],
+        buf: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let retryable = self.state.retryable(address, bytes);
+        self.run(retryable, |bus| bus.write_read(address, bytes, buf))
+    }
+}
+
+impl<T: LockBus> LockBus for RetryingI2c<T> {
+    fn lock_bus<R>(&mut self, f: impl FnOnce(&mut LockedBus<'_, Self>) -> R) -> R {
+        let state = &mut self.state;
+        self.inner
+            .lock_bus(|bus| f(&mut LockedRetryingI2c { bus, state }))
+    }
+}
+
+/// The inner bus locked by `RetryingI2c::lock_bus`, with the retries of `RetryingI2c`.
+pub struct LockedRetryingI2c<'a, WE, RE> {
+    bus: &'a mut dyn I2cBus<WE, RE>,
+    state: &'a mut RetryState,
+}
+
+impl<WE, RE> Write for LockedRetryingI2c<'_, WE, RE> {
+    type Error = WE;
+
+    fn write(&mut self, address: SevenBitAddress, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.state
+            .run(self.bus, true, |bus| bus.write(address, bytes), |_| None)
+    }
+}
+
+impl<WE, RE> WriteRead for LockedRetryingI2c<'_, WE, RE> {
+    type Error = RE;
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddress,
+        bytes: &[u8],
+        buf: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let retryable = self.state.retryable(address, bytes);
+        self.state.run(
+            self.bus,
+            retryable,
+            |bus| bus.write_read(address, bytes, buf),
+            |_| None,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff() {
+        let policy = RetryPolicy {
+            backoff: Duration::from_millis(2),
+            backoff_factor: 3.0,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.backoff_of(1), Duration::from_millis(2));
+        assert_eq!(policy.backoff_of(2), Duration::from_millis(6));
+        assert_eq!(policy.backoff_of(3), Duration::from_millis(18));
+        assert_eq!(RetryPolicy::none().backoff_of(5), Duration::ZERO);
+    }
+}
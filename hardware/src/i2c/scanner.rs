@@ -0,0 +1,61 @@
+use super::{mpu6050, pca9685, I2cAddr};
+use crate::error::{BusError, Device};
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+/// The first address which is not reserved.
+pub const FIRST_ADDRESS: I2cAddr = I2cAddr(0x03);
+/// The last address which is not reserved.
+pub const LAST_ADDRESS: I2cAddr = I2cAddr(0x77);
+
+/// A device which responded to the scan.
+/// `device` is `Device::Unknown` unless it is identified as one of the drivers in this crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Found {
+    pub address: I2cAddr,
+    pub device: Device,
+}
+
+/// Probes every address from `FIRST_ADDRESS` to `LAST_ADDRESS`.
+///
+/// A device is present if it acknowledges a write of no bytes (a quick write).
+/// Then it is identified by the addresses the known devices can have:
+/// WHO_AM_I at 0x68 and 0x69, and the MODE registers at 0x40 and above.
+/// If a PCA9685 is found, `pca9685::ALL_CALL_ADDRESS` is left out,
+/// since the PCA9685 answers it too.
+pub fn scan<T, E>(bus: &mut T) -> Vec<Found>
+where
+    T: Write<Error = E> + WriteRead<Error = E>,
+    E: BusError,
+{
+    let mut found = vec![];
+    for address in (FIRST_ADDRESS.0..=LAST_ADDRESS.0).map(I2cAddr) {
+        if bus.write(address.0, &[]).is_ok() {
+            let device = identify(bus, address);
+            found.push(Found { address, device });
+        }
+    }
+    let has_pca9685 = found
+        .iter()
+        .any(|f| f.device == Device::Pca9685 && f.address != pca9685::ALL_CALL_ADDRESS);
+    if has_pca9685 {
+        found.retain(|f| f.address != pca9685::ALL_CALL_ADDRESS);
+    }
+    found
+}
+
+fn identify<T, E>(bus: &mut T, address: I2cAddr) -> Device
+where
+    T: Write<Error = E> + WriteRead<Error = E>,
+    E: BusError,
+{
+    let is_mpu6050 = [mpu6050::ADDRESS_LOW, mpu6050::ADDRESS_HIGH].contains(&address)
+        && mpu6050::identify(bus, address).unwrap_or(false);
+    if is_mpu6050 {
+        Device::Mpu6050
+    } else if address.0 >= 0x40 && pca9685::identify(bus, address).unwrap_or(false) {
+        Device::Pca9685
+    } else {
+        Device::Unknown
+    }
+}
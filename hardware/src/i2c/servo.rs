@@ -1,7 +1,13 @@
-use super::pca9685::{collect_frequency, HasChannel, HasPrescale, PwmError, PCA9685};
+pub mod bank;
+pub mod calibration;
+pub mod spec;
+
+use super::pca9685::{collect_frequency, HasChannel, HasPrescale, PCA9685};
+use super::I2cAddr;
+use crate::error::{BusError, Device, Error, ErrorKind};
 use embedded_hal::blocking::i2c::{Write, WriteRead};
-use i2cdev::linux::LinuxI2CError;
 use pwm_pca9685::Channel;
+use spec::ServoSpec;
 
 pub struct ServoMotor {
     channel: Channel,
@@ -9,6 +15,7 @@ pub struct ServoMotor {
     prescale: u8,
     min_width: f64,
     max_width: f64,
+    spec: Option<ServoSpec>,
 }
 
 impl ServoMotor {
@@ -20,23 +27,106 @@ impl ServoMotor {
             prescale,
             min_width,
             max_width,
+            spec: None,
+        }
+    }
+
+    /// The servo of the model, driven at the frequency and the pulse widths of the spec.
+    pub fn from_spec(channel: Channel, spec: ServoSpec) -> ServoMotor {
+        let servo = ServoMotor::new(channel, spec.frequency, spec.min_width, spec.max_width);
+        ServoMotor {
+            spec: Some(spec),
+            ..servo
         }
     }
 
+    /// `None` if made by `new`.
+    pub fn spec(&self) -> Option<&ServoSpec> {
+        self.spec.as_ref()
+    }
+
+    /// The frequency actually generated with the prescale.
+    pub fn frequency(&self) -> f64 {
+        self.frequency
+    }
+
+    /// The pulse width in milliseconds at one end of the travel.
+    pub fn min_width(&self) -> f64 {
+        self.min_width
+    }
+
+    /// The pulse width in milliseconds at the other end of the travel.
+    pub fn max_width(&self) -> f64 {
+        self.max_width
+    }
+
     pub fn calc_pulse(&self, v: f64) -> f64 {
-        let unit = 1000.0 / self.frequency;
         let pulse = self.min_width + (self.max_width - self.min_width) * v;
-        pulse / unit
+        self.calc_pulse_by_width(pulse)
+    }
+
+    /// The duty cycle of the pulse width in milliseconds.
+    pub fn calc_pulse_by_width(&self, width: f64) -> f64 {
+        let unit = 1000.0 / self.frequency;
+        width / unit
     }
 
-    pub fn set_by_rate<D>(&self, pwm: &mut PCA9685<D>, v: f64) -> Result<(), PwmError>
+    /// Sets the pulse width at the rate from 0 to 1 between `min_width` and `max_width`.
+    /// See `set_by_width` for the frequency.
+    pub fn set_by_rate<D, E>(&self, pwm: &mut PCA9685<D>, v: f64) -> Result<(), Error>
     where
-        D: Write<Error = LinuxI2CError> + WriteRead<Error = LinuxI2CError>,
+        D: Write<Error = E> + WriteRead<Error = E>,
+        E: BusError,
     {
-        let rate = self.calc_pulse(v);
-        pwm.set_prescale(self.prescale)?;
-        pwm.set_one_duty_cycle(self.channel, rate)
+        let width = self.min_width + (self.max_width - self.min_width) * v;
+        self.set_by_width(pwm, width)
     }
+
+    /// Sets the pulse width in milliseconds as it is, without limiting it to the travel.
+    ///
+    /// The prescale is common to the board, so it is set to the one of the servo
+    /// only if it has not been set yet. Otherwise the board keeps its frequency,
+    /// and the pulse is counted against its actual period.
+    pub fn set_by_width<D, E>(&self, pwm: &mut PCA9685<D>, width: f64) -> Result<(), Error>
+    where
+        D: Write<Error = E> + WriteRead<Error = E>,
+        E: BusError,
+    {
+        if pwm.prescale().is_none() {
+            pwm.set_prescale(self.prescale)?;
+        }
+        pwm.set_pulse_width(self.channel, width * 1000.0)
+    }
+
+    /// Turns to the angle in degrees from the end of `min_width`, limited to the travel.
+    /// Fails with `ErrorKind::InvalidInput` unless the spec is of a positional servo.
+    pub fn set_angle<D, E>(&self, pwm: &mut PCA9685<D>, angle: f64) -> Result<(), Error>
+    where
+        D: Write<Error = E> + WriteRead<Error = E>,
+        E: BusError,
+    {
+        match self.spec.and_then(|s| s.width_by_angle(angle)) {
+            Some(width) => self.set_by_width(pwm, width),
+            None => Err(invalid_input(pwm.address())),
+        }
+    }
+
+    /// Rotates at the signed speed from -1 to 1. 0 stops the servo.
+    /// Fails with `ErrorKind::InvalidInput` unless the spec is of a continuous rotation servo.
+    pub fn set_speed<D, E>(&self, pwm: &mut PCA9685<D>, speed: f64) -> Result<(), Error>
+    where
+        D: Write<Error = E> + WriteRead<Error = E>,
+        E: BusError,
+    {
+        match self.spec.and_then(|s| s.width_by_speed(speed)) {
+            Some(width) => self.set_by_width(pwm, width),
+            None => Err(invalid_input(pwm.address())),
+        }
+    }
+}
+
+fn invalid_input(address: I2cAddr) -> Error {
+    Error::new(ErrorKind::InvalidInput, Device::Pca9685, address)
 }
 
 impl HasPrescale for ServoMotor {
@@ -70,9 +160,10 @@ impl SG90_180 {
         self.servo.calc_pulse(v)
     }
 
-    pub fn set_angle<D>(&self, pwm: &mut PCA9685<D>, angle: f64) -> Result<(), PwmError>
+    pub fn set_angle<D, E>(&self, pwm: &mut PCA9685<D>, angle: f64) -> Result<(), Error>
     where
-        D: Write<Error = LinuxI2CError> + WriteRead<Error = LinuxI2CError>,
+        D: Write<Error = E> + WriteRead<Error = E>,
+        E: BusError,
     {
         let v = SG90_180::calc_angle_rate(angle);
         self.servo.set_by_rate(pwm, v)
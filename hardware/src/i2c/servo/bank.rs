@@ -0,0 +1,191 @@
+use super::calibration::{CalibratedServo, ServoProfile, ServoProfiles};
+use crate::error::*;
+use crate::i2c::pca9685::frame::{PwmFrame, Stagger, CHANNELS};
+use crate::i2c::pca9685::*;
+use crate::i2c::I2cAddr;
+
+use derive_more::{Display, From, Into};
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use pwm_pca9685::Channel;
+use std::collections::BTreeMap;
+use std::io;
+
+/// The logical ID of a servo in `ServoBank`, independent of where it is connected.
+#[derive(Debug, Display, From, Into, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ServoId(pub u16);
+
+/// Where a servo is connected: the index of the board in `ServoBank` and the channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ServoPort {
+    pub board: usize,
+    pub channel: Channel,
+}
+
+struct Board<D> {
+    pwm: PCA9685<D>,
+    /// Common to the servos of the board.
+    prescale: Option<u8>,
+    servos: [Option<ServoId>; CHANNELS],
+}
+
+/// The servos over several PCA9685 boards, which may be on different buses.
+///
+/// The servos are addressed by `ServoId`, and the duty cycles of a frame
+/// are written in one transfer for each board, board by board in the order they were added.
+/// The servos of a board must share the prescale, which is set for each board.
+pub struct ServoBank<D> {
+    boards: Vec<Board<D>>,
+    ports: BTreeMap<ServoId, ServoPort>,
+    stagger: Stagger,
+}
+
+impl<D, E> ServoBank<D>
+where
+    D: Write<Error = E> + WriteRead<Error = E>,
+    E: BusError,
+{
+    pub fn new(stagger: Stagger) -> Self {
+        Self {
+            boards: vec![],
+            ports: BTreeMap::new(),
+            stagger,
+        }
+    }
+
+    /// Returns the index of the board.
+    pub fn add_board(&mut self, pwm: PCA9685<D>) -> usize {
+        self.boards.push(Board {
+            pwm,
+            prescale: None,
+            servos: [None; CHANNELS],
+        });
+        self.boards.len() - 1
+    }
+
+    /// # Panics
+    /// If there is no board of the index.
+    pub fn board(&mut self, index: usize) -> &mut PCA9685<D> {
+        &mut self.boards[index].pwm
+    }
+
+    pub fn board_count(&self) -> usize {
+        self.boards.len()
+    }
+
+    /// Enables all of the boards.
+    pub fn enable(&mut self) -> Result<(), Error> {
+        self.boards.iter_mut().try_for_each(|b| b.pwm.enable())
+    }
+
+    /// Maps the ID to the channel of the servo on the board.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if the ID is already mapped,
+    /// the channel is `All` or taken, or the prescale differs from the other servos of the board.
+    ///
+    /// # Panics
+    /// If there is no board of the index.
+    pub fn attach<S>(&mut self, id: ServoId, board: usize, servo: &S) -> Result<(), Error>
+    where
+        S: HasChannel + HasPrescale,
+    {
+        let channel = servo.channel();
+        let b = &mut self.boards[board];
+        let invalid = |b: &Board<D>, reg| {
+            Error::new(ErrorKind::InvalidInput, Device::Pca9685, b.pwm.address()).with_register(reg)
+        };
+        if self.ports.contains_key(&id) || channel == Channel::All {
+            return Err(invalid(b, channel_register(channel)));
+        }
+        if b.servos[channel as usize].is_some() {
+            return Err(invalid(b, channel_register(channel)));
+        }
+        if matches!(b.prescale, Some(p) if p != servo.prescale()) {
+            return Err(invalid(b, PRE_SCALE));
+        }
+        b.prescale = Some(servo.prescale());
+        b.servos[channel as usize] = Some(id);
+        self.ports.insert(id, ServoPort { board, channel });
+        Ok(())
+    }
+
+    /// Removes the mapping. Returns where the servo was connected.
+    pub fn detach(&mut self, id: ServoId) -> Option<ServoPort> {
+        let port = self.ports.remove(&id)?;
+        let b = &mut self.boards[port.board];
+        b.servos[port.channel as usize] = None;
+        if b.servos.iter().all(Option::is_none) {
+            b.prescale = None;
+        }
+        Some(port)
+    }
+
+    /// Builds the servos of the profiles and attaches them to the boards of the profiles.
+    /// The ID of a servo is its index in the profiles.
+    /// Fails with `InvalidData` if a profile is not valid or cannot be attached;
+    /// the servos of the profiles attached before it are detached again.
+    pub fn attach_profiles(
+        &mut self,
+        profiles: &ServoProfiles,
+    ) -> io::Result<Vec<(ServoId, CalibratedServo)>> {
+        let mut servos = vec![];
+        for (i, p) in profiles.servos.iter().enumerate() {
+            let id = ServoId(i as u16);
+            match self.attach_profile(id, p) {
+                Ok(servo) => servos.push((id, servo)),
+                Err(e) => {
+                    for (id, _) in servos {
+                        self.detach(id);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(servos)
+    }
+
+    fn attach_profile(&mut self, id: ServoId, p: &ServoProfile) -> io::Result<CalibratedServo> {
+        if p.board >= self.boards.len() {
+            let msg = format!("{}: No board {}", p.name, p.board);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+        }
+        let servo = p.build()?;
+        self.attach(id, p.board, &servo)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(servo)
+    }
+
+    pub fn port(&self, id: ServoId) -> Option<ServoPort> {
+        self.ports.get(&id).copied()
+    }
+
+    /// The mapped IDs in ascending order.
+    pub fn ids(&self) -> impl Iterator<Item = ServoId> + '_ {
+        self.ports.keys().copied()
+    }
+
+    /// Sets the duty cycles from 0 to 1 of the servos as one frame.
+    /// The servos not in the frame keep their duty cycles.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` without writing anything if an ID is not mapped.
+    /// The error has the general call address 0x00, as the ID is not on any board.
+    pub fn set_duty_cycle(&mut self, rates: &[(ServoId, f64)]) -> Result<(), Error> {
+        let mut frames: BTreeMap<usize, PwmFrame> = BTreeMap::new();
+        for (id, rate) in rates {
+            let port = self
+                .port(*id)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidInput, Device::Pca9685, I2cAddr(0)))?;
+            frames
+                .entry(port.board)
+                .or_insert_with(|| PwmFrame::new(self.stagger))
+                .set(port.channel, *rate);
+        }
+        for (index, frame) in frames {
+            let board = &mut self.boards[index];
+            if let Some(prescale) = board.prescale {
+                board.pwm.set_prescale(prescale)?;
+            }
+            board.pwm.write_frame(&frame)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,471 @@
+use super::ServoMotor;
+use crate::i2c::pca9685::{HasChannel, HasPrescale, PCA9685};
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use pwm_pca9685::Channel;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+/// A pulse width measured at an angle of the horn.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PulsePoint {
+    /// In degrees from the end of the travel reached with `min_width`.
+    pub angle: f64,
+    /// In milliseconds.
+    pub width: f64,
+}
+
+/// How a servo, as mounted, differs from the ideal one.
+///
+/// An angle is converted to the pulse width in this order:
+/// limited to `min_angle` and `max_angle`, mirrored if `inverted`, shifted by `trim`,
+/// then converted by `table`, or linearly with the widths of the servo if the table is empty.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServoCalibration {
+    /// Degrees added to the angle, for the horn not mounted at the center.
+    pub trim: f64,
+    /// The angle goes in the opposite direction, for the servo mounted mirrored.
+    pub inverted: bool,
+    /// Soft limits of the angle in degrees, before `inverted` and `trim`.
+    pub min_angle: Option<f64>,
+    pub max_angle: Option<f64>,
+    /// Pulse widths measured at some angles, in any order.
+    /// Linearly interpolated, and the nearest one is used out of their range.
+    pub table: Vec<PulsePoint>,
+}
+
+impl ServoCalibration {
+    /// Fails with `InvalidData` if the limits are reversed,
+    /// or the table has a single point or the same angle twice.
+    pub fn validate(&self) -> Result<()> {
+        if let (Some(min), Some(max)) = (self.min_angle, self.max_angle) {
+            if min > max {
+                return Err(invalid(format!("min_angle {} > max_angle {}", min, max)));
+            }
+        }
+        if self.table.len() == 1 {
+            return Err(invalid("The table needs 2 points at least"));
+        }
+        let mut angles: Vec<f64> = self.table.iter().map(|p| p.angle).collect();
+        angles.sort_by(f64::total_cmp);
+        if let Some(w) = angles.windows(2).find(|w| w[0] == w[1]) {
+            return Err(invalid(format!("The table has {} degrees twice", w[0])));
+        }
+        Ok(())
+    }
+
+    /// The angle limited to the soft limits.
+    pub fn limit(&self, angle: f64) -> f64 {
+        let angle = self.min_angle.map_or(angle, |min| angle.max(min));
+        self.max_angle.map_or(angle, |max| angle.min(max))
+    }
+
+    /// The angle of the servo itself for the angle of the joint.
+    pub fn servo_angle(&self, angle: f64, travel: f64) -> f64 {
+        let angle = self.limit(angle);
+        let angle = if self.inverted { travel - angle } else { angle };
+        angle + self.trim
+    }
+
+    /// The pulse width in milliseconds for the angle of the joint.
+    /// The width stays in the range of the table or of the servo.
+    pub fn width(&self, angle: f64, servo: &ServoMotor, travel: f64) -> f64 {
+        let angle = self.servo_angle(angle, travel);
+        if self.table.is_empty() {
+            let rate = (angle / travel).clamp(0.0, 1.0);
+            return servo.min_width() + (servo.max_width() - servo.min_width()) * rate;
+        }
+        let mut points = self.table.clone();
+        points.sort_by(|a, b| a.angle.total_cmp(&b.angle));
+        interpolate(&points, angle)
+    }
+}
+
+fn interpolate(points: &[PulsePoint], angle: f64) -> f64 {
+    let first = points[0];
+    let last = points[points.len() - 1];
+    if angle <= first.angle {
+        return first.width;
+    }
+    if angle >= last.angle {
+        return last.width;
+    }
+    points
+        .windows(2)
+        .find(|w| angle < w[1].angle)
+        .map(|w| {
+            let r = (angle - w[0].angle) / (w[1].angle - w[0].angle);
+            w[0].width + (w[1].width - w[0].width) * r
+        })
+        .unwrap_or(last.width)
+}
+
+/// A servo with its calibration, driven by the angle of the joint.
+pub struct CalibratedServo {
+    servo: ServoMotor,
+    travel: f64,
+    calibration: ServoCalibration,
+}
+
+impl CalibratedServo {
+    /// `travel` is the angle in degrees between the ends reached with the min and max widths.
+    pub fn new(servo: ServoMotor, travel: f64, calibration: ServoCalibration) -> CalibratedServo {
+        CalibratedServo {
+            servo,
+            travel,
+            calibration,
+        }
+    }
+
+    pub fn servo(&self) -> &ServoMotor {
+        &self.servo
+    }
+
+    pub fn travel(&self) -> f64 {
+        self.travel
+    }
+
+    pub fn calibration(&self) -> &ServoCalibration {
+        &self.calibration
+    }
+
+    /// For adjusting the trim while the servo is running.
+    pub fn calibration_mut(&mut self) -> &mut ServoCalibration {
+        &mut self.calibration
+    }
+
+    /// The pulse width in milliseconds.
+    pub fn calc_width_by_angle(&self, angle: f64) -> f64 {
+        self.calibration.width(angle, &self.servo, self.travel)
+    }
+
+    /// The duty cycle.
+    pub fn calc_pulse_by_angle(&self, angle: f64) -> f64 {
+        self.servo
+            .calc_pulse_by_width(self.calc_width_by_angle(angle))
+    }
+
+    pub fn set_angle<D, E>(
+        &self,
+        pwm: &mut PCA9685<D>,
+        angle: f64,
+    ) -> std::result::Result<(), crate::Error>
+    where
+        D: Write<Error = E> + WriteRead<Error = E>,
+        E: crate::BusError,
+    {
+        self.servo
+            .set_by_width(pwm, self.calc_width_by_angle(angle))
+    }
+}
+
+impl HasPrescale for CalibratedServo {
+    fn prescale(&self) -> u8 {
+        self.servo.prescale()
+    }
+}
+
+impl HasChannel for CalibratedServo {
+    fn channel(&self) -> Channel {
+        self.servo.channel()
+    }
+}
+
+/// A servo in `ServoProfiles`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServoProfile {
+    pub name: String,
+    /// The channel of the PCA9685, 0 to 15.
+    pub channel: u8,
+    /// The index of the PCA9685 in `ServoBank`.
+    #[serde(default)]
+    pub board: usize,
+    #[serde(default = "default_frequency")]
+    pub frequency: f64,
+    /// The pulse widths in milliseconds at the ends of the travel.
+    pub min_width: f64,
+    pub max_width: f64,
+    /// The angle between the ends in degrees.
+    #[serde(default = "default_travel")]
+    pub travel: f64,
+    #[serde(default)]
+    pub calibration: ServoCalibration,
+}
+
+fn default_frequency() -> f64 {
+    50.0
+}
+
+fn default_travel() -> f64 {
+    180.0
+}
+
+impl ServoProfile {
+    /// Fails with `InvalidData` if the profile is not valid.
+    pub fn build(&self) -> Result<CalibratedServo> {
+        let channel = Channel::try_from(self.channel)
+            .map_err(|_| invalid(format!("{}: No channel {}", self.name, self.channel)))?;
+        if self.travel <= 0.0 {
+            return Err(invalid(format!("{}: travel must be positive", self.name)));
+        }
+        self.calibration
+            .validate()
+            .map_err(|e| invalid(format!("{}: {}", self.name, e)))?;
+        let servo = ServoMotor::new(channel, self.frequency, self.min_width, self.max_width);
+        Ok(CalibratedServo::new(
+            servo,
+            self.travel,
+            self.calibration.clone(),
+        ))
+    }
+}
+
+/// The profiles of all of the servos, kept in a file.
+///
+/// ```toml
+/// [[servo]]
+/// name = "front_left_coxa"
+/// channel = 0
+/// board = 1
+/// min_width = 0.5
+/// max_width = 2.4
+///
+/// [servo.calibration]
+/// trim = -3.5
+/// inverted = true
+/// min_angle = 30.0
+/// max_angle = 150.0
+///
+/// [[servo.calibration.table]]
+/// angle = 0.0
+/// width = 0.55
+///
+/// [[servo.calibration.table]]
+/// angle = 180.0
+/// width = 2.35
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServoProfiles {
+    #[serde(rename = "servo", default)]
+    pub servos: Vec<ServoProfile>,
+}
+
+impl ServoProfiles {
+    pub fn get(&self, name: &str) -> Option<&ServoProfile> {
+        self.servos.iter().find(|p| p.name == name)
+    }
+
+    /// Builds all of the servos in the order of the profiles.
+    pub fn build(&self) -> Result<Vec<CalibratedServo>> {
+        self.servos.iter().map(ServoProfile::build).collect()
+    }
+
+    /// Fails with `InvalidData` if a name or a channel of a board appears twice
+    /// or a profile is not valid.
+    pub fn validate(&self) -> Result<()> {
+        let mut names = HashSet::new();
+        let mut channels = HashSet::new();
+        for p in &self.servos {
+            if !names.insert(&p.name) {
+                return Err(invalid(format!("{}: The name appears twice", p.name)));
+            }
+            if !channels.insert((p.board, p.channel)) {
+                return Err(invalid(format!(
+                    "{}: Channel {} of board {} is taken",
+                    p.name, p.channel, p.board
+                )));
+            }
+            p.build()?;
+        }
+        Ok(())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let text = toml::to_string(self).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+
+    /// Fails with `InvalidData` if the profiles are not valid.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let profiles: Self =
+            toml::from_str(&text).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        profiles.validate()?;
+        Ok(profiles)
+    }
+}
+
+fn invalid<S: Into<String>>(msg: S) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn servo() -> ServoMotor {
+        ServoMotor::new(Channel::C0, 50.0, 0.5, 2.5)
+    }
+
+    fn point(angle: f64, width: f64) -> PulsePoint {
+        PulsePoint { angle, width }
+    }
+
+    #[test]
+    fn linear_without_calibration() {
+        let cal = ServoCalibration::default();
+        assert_eq!(cal.width(0.0, &servo(), 180.0), 0.5);
+        assert_eq!(cal.width(90.0, &servo(), 180.0), 1.5);
+        assert_eq!(cal.width(180.0, &servo(), 180.0), 2.5);
+        assert_eq!(cal.width(200.0, &servo(), 180.0), 2.5);
+        assert_eq!(cal.width(-20.0, &servo(), 180.0), 0.5);
+    }
+
+    #[test]
+    fn trim_and_inverted() {
+        let cal = ServoCalibration {
+            trim: 9.0,
+            inverted: true,
+            ..ServoCalibration::default()
+        };
+        assert_eq!(cal.servo_angle(90.0, 180.0), 99.0);
+        assert_eq!(cal.servo_angle(30.0, 180.0), 159.0);
+        assert_eq!(cal.width(30.0, &servo(), 180.0), 0.5 + 2.0 * 159.0 / 180.0);
+    }
+
+    #[test]
+    fn soft_limits_before_trim() {
+        let cal = ServoCalibration {
+            trim: -5.0,
+            min_angle: Some(45.0),
+            max_angle: Some(135.0),
+            ..ServoCalibration::default()
+        };
+        assert_eq!(cal.servo_angle(0.0, 180.0), 40.0);
+        assert_eq!(cal.servo_angle(100.0, 180.0), 95.0);
+        assert_eq!(cal.servo_angle(180.0, 180.0), 130.0);
+    }
+
+    #[test]
+    fn table_interpolation() {
+        let cal = ServoCalibration {
+            table: vec![point(180.0, 2.3), point(0.0, 0.6), point(90.0, 1.4)],
+            ..ServoCalibration::default()
+        };
+        assert_eq!(cal.width(0.0, &servo(), 180.0), 0.6);
+        assert_eq!(cal.width(90.0, &servo(), 180.0), 1.4);
+        assert!((cal.width(45.0, &servo(), 180.0) - 1.0).abs() < 1e-9);
+        assert!((cal.width(135.0, &servo(), 180.0) - 1.85).abs() < 1e-9);
+        assert_eq!(cal.width(-10.0, &servo(), 180.0), 0.6);
+        assert_eq!(cal.width(190.0, &servo(), 180.0), 2.3);
+    }
+
+    #[test]
+    fn invalid_calibrations() {
+        let reversed = ServoCalibration {
+            min_angle: Some(100.0),
+            max_angle: Some(80.0),
+            ..ServoCalibration::default()
+        };
+        let single = ServoCalibration {
+            table: vec![point(0.0, 0.5)],
+            ..ServoCalibration::default()
+        };
+        let twice = ServoCalibration {
+            table: vec![point(0.0, 0.5), point(90.0, 1.5), point(0.0, 0.6)],
+            ..ServoCalibration::default()
+        };
+        for cal in [reversed, single, twice] {
+            assert_eq!(cal.validate().unwrap_err().kind(), ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn profile_defaults() {
+        let text = r#"
+            [[servo]]
+            name = "coxa"
+            channel = 3
+            min_width = 0.5
+            max_width = 2.4
+        "#;
+        let profiles: ServoProfiles = toml::from_str(text).unwrap();
+        let profile = profiles.get("coxa").unwrap();
+        assert_eq!(profile.frequency, 50.0);
+        assert_eq!(profile.travel, 180.0);
+        assert_eq!(profile.calibration, ServoCalibration::default());
+
+        let servo = profile.build().unwrap();
+        assert_eq!(servo.channel(), Channel::C3);
+        assert_eq!(servo.prescale(), 121);
+    }
+
+    #[test]
+    fn invalid_profiles() {
+        let profile = |name: &str, channel| ServoProfile {
+            name: name.to_string(),
+            channel,
+            board: 0,
+            frequency: 50.0,
+            min_width: 0.5,
+            max_width: 2.4,
+            travel: 180.0,
+            calibration: ServoCalibration::default(),
+        };
+        let no_channel = ServoProfiles {
+            servos: vec![profile("a", 16)],
+        };
+        let same_name = ServoProfiles {
+            servos: vec![profile("a", 0), profile("a", 1)],
+        };
+        let same_channel = ServoProfiles {
+            servos: vec![profile("a", 0), profile("b", 0)],
+        };
+        for profiles in [no_channel, same_name, same_channel] {
+            assert_eq!(
+                profiles.validate().unwrap_err().kind(),
+                ErrorKind::InvalidData
+            );
+        }
+    }
+
+    #[test]
+    fn save_and_load() {
+        let femur = ServoProfile {
+            name: "front_left_femur".to_string(),
+            channel: 1,
+            board: 2,
+            frequency: 50.0,
+            min_width: 0.5,
+            max_width: 2.4,
+            travel: 180.0,
+            calibration: ServoCalibration {
+                trim: -3.5,
+                inverted: true,
+                min_angle: Some(30.0),
+                max_angle: None,
+                table: vec![point(0.0, 0.55), point(180.0, 2.35)],
+            },
+        };
+        // The same channel of another board.
+        let coxa = ServoProfile {
+            name: "front_left_coxa".to_string(),
+            board: 0,
+            calibration: ServoCalibration::default(),
+            ..femur.clone()
+        };
+        let profiles = ServoProfiles {
+            servos: vec![femur, coxa],
+        };
+        let path = std::env::temp_dir().join(format!("servo-profiles-{}.toml", std::process::id()));
+        profiles.save(&path).unwrap();
+        let loaded = ServoProfiles::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, profiles);
+    }
+}
@@ -0,0 +1,175 @@
+use std::time::Duration;
+
+/// How the horn moves with the pulse width.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rotation {
+    /// The pulse width sets the angle in the travel, in degrees.
+    Positional { travel: f64 },
+    /// The pulse width sets the speed and the direction.
+    /// The servo stops at the middle of the pulse widths.
+    Continuous,
+}
+
+/// The values of a servo model in its datasheet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ServoSpec {
+    pub name: &'static str,
+    /// The frequency of the pulses in Hz.
+    pub frequency: f64,
+    /// The pulse widths in milliseconds at the ends of the travel,
+    /// or at the full speeds of the continuous rotation.
+    pub min_width: f64,
+    pub max_width: f64,
+    pub rotation: Rotation,
+    /// Seconds to turn 60 degrees without load.
+    pub speed: f64,
+    /// In kg·cm.
+    pub stall_torque: f64,
+}
+
+pub const SG90: ServoSpec = ServoSpec {
+    name: "SG90",
+    frequency: 50.0,
+    min_width: 0.5,
+    max_width: 2.4,
+    rotation: Rotation::Positional { travel: 180.0 },
+    speed: 0.1,
+    stall_torque: 1.8,
+};
+
+pub const MG90S: ServoSpec = ServoSpec {
+    name: "MG90S",
+    frequency: 50.0,
+    min_width: 0.5,
+    max_width: 2.5,
+    rotation: Rotation::Positional { travel: 180.0 },
+    speed: 0.1,
+    stall_torque: 1.8,
+};
+
+pub const MG996R: ServoSpec = ServoSpec {
+    name: "MG996R",
+    frequency: 50.0,
+    min_width: 0.5,
+    max_width: 2.5,
+    rotation: Rotation::Positional { travel: 180.0 },
+    speed: 0.17,
+    stall_torque: 9.4,
+};
+
+pub const DS3218_270: ServoSpec = ServoSpec {
+    name: "DS3218_270",
+    frequency: 50.0,
+    min_width: 0.5,
+    max_width: 2.5,
+    rotation: Rotation::Positional { travel: 270.0 },
+    speed: 0.16,
+    stall_torque: 19.0,
+};
+
+/// A continuous rotation servo.
+pub const FS90R: ServoSpec = ServoSpec {
+    name: "FS90R",
+    frequency: 50.0,
+    min_width: 0.7,
+    max_width: 2.3,
+    rotation: Rotation::Continuous,
+    speed: 0.09,
+    stall_torque: 1.3,
+};
+
+/// The values at 4.8V, or at 5V for DS3218.
+pub const PRESETS: [ServoSpec; 5] = [SG90, MG90S, MG996R, DS3218_270, FS90R];
+
+impl ServoSpec {
+    /// One of `PRESETS`, ignoring the case.
+    pub fn preset(name: &str) -> Option<ServoSpec> {
+        PRESETS
+            .iter()
+            .find(|s| s.name.eq_ignore_ascii_case(name))
+            .copied()
+    }
+
+    /// The angle between the ends in degrees. `None` for the continuous rotation.
+    pub fn travel(&self) -> Option<f64> {
+        match self.rotation {
+            Rotation::Positional { travel } => Some(travel),
+            Rotation::Continuous => None,
+        }
+    }
+
+    pub fn is_continuous(&self) -> bool {
+        self.rotation == Rotation::Continuous
+    }
+
+    /// The pulse width in milliseconds at the middle.
+    pub fn neutral_width(&self) -> f64 {
+        (self.min_width + self.max_width) / 2.0
+    }
+
+    /// The angular velocity without load in deg/s.
+    pub fn degrees_per_second(&self) -> f64 {
+        60.0 / self.speed
+    }
+
+    /// The time to turn the angle in degrees without load.
+    pub fn time_to_turn(&self, angle: f64) -> Duration {
+        Duration::from_secs_f64(angle.abs() / self.degrees_per_second())
+    }
+
+    /// The pulse width in milliseconds for the angle from the end of `min_width`.
+    /// The angle is limited to the travel. `None` for the continuous rotation.
+    pub fn width_by_angle(&self, angle: f64) -> Option<f64> {
+        let travel = self.travel()?;
+        let rate = (angle / travel).clamp(0.0, 1.0);
+        Some(self.min_width + (self.max_width - self.min_width) * rate)
+    }
+
+    /// The pulse width in milliseconds for the signed speed from -1 to 1,
+    /// which is positive toward `max_width`. 0 stops the servo.
+    /// `None` unless the rotation is continuous.
+    pub fn width_by_speed(&self, speed: f64) -> Option<f64> {
+        if !self.is_continuous() {
+            return None;
+        }
+        let half = (self.max_width - self.min_width) / 2.0;
+        Some(self.neutral_width() + half * speed.clamp(-1.0, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_by_name() {
+        assert_eq!(ServoSpec::preset("mg996r"), Some(MG996R));
+        assert_eq!(ServoSpec::preset("DS3218_270"), Some(DS3218_270));
+        assert_eq!(ServoSpec::preset("unknown"), None);
+    }
+
+    #[test]
+    fn widths_of_270_degrees() {
+        assert_eq!(DS3218_270.width_by_angle(0.0), Some(0.5));
+        assert_eq!(DS3218_270.width_by_angle(135.0), Some(1.5));
+        assert_eq!(DS3218_270.width_by_angle(270.0), Some(2.5));
+        assert_eq!(DS3218_270.width_by_angle(300.0), Some(2.5));
+        assert_eq!(DS3218_270.width_by_speed(0.5), None);
+    }
+
+    #[test]
+    fn widths_of_continuous() {
+        let width = |speed| (FS90R.width_by_speed(speed).unwrap() * 1000.0).round();
+        assert_eq!(FS90R.travel(), None);
+        assert_eq!(FS90R.width_by_angle(90.0), None);
+        assert_eq!(width(0.0), 1500.0);
+        assert_eq!(width(1.0), 2300.0);
+        assert_eq!(width(-2.0), 700.0);
+    }
+
+    #[test]
+    fn time_to_turn() {
+        assert_eq!(MG996R.degrees_per_second().round(), 353.0);
+        assert_eq!(SG90.time_to_turn(-90.0), Duration::from_millis(150));
+    }
+}
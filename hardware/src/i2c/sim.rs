@@ -0,0 +1,125 @@
+//! Simulated devices on an in-memory I2C bus.
+//!
+//! The devices keep their registers and behave like the hardware at the register level,
+//! so the drivers can be tested without byte lists prepared for every transaction.
+//! The time of the simulation advances only by `SimBus::advance`.
+
+pub mod mpu6050;
+pub mod pca9685;
+
+use super::{I2cAddr, LockBus, LockedBus};
+
+use embedded_hal::blocking::i2c::{SevenBitAddress, Write, WriteRead};
+use std::any::Any;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+use std::time::Duration;
+
+/// A device connected to `SimBus`.
+pub trait SimDevice: Send {
+    /// The bytes of a write transfer. The first one is the register address.
+    /// An empty write only addresses the device.
+    fn write(&mut self, bytes: &[u8]) -> Result<()>;
+
+    /// The bytes of a read transfer following the register address set by `write`.
+    fn read(&mut self, buf: &mut [u8]) -> Result<()>;
+
+    /// Lets the time of the simulation pass.
+    fn advance(&mut self, _dt: Duration) {}
+
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// An I2C bus with simulated devices.
+/// An address without a device does not acknowledge, and the transfer fails with `NotFound`.
+#[derive(Default)]
+pub struct SimBus {
+    devices: HashMap<I2cAddr, Box<dyn SimDevice>>,
+    /// The number of the next transfers to fail for each address.
+    failures: HashMap<I2cAddr, usize>,
+    elapsed: Duration,
+}
+
+impl SimBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connects the device at the address, replacing the one already there.
+    pub fn attach<D: SimDevice + 'static>(&mut self, address: I2cAddr, device: D) {
+        self.devices.insert(address, Box::new(device));
+    }
+
+    /// Disconnects the device. Returns false if there is no device at the address.
+    pub fn detach(&mut self, address: I2cAddr) -> bool {
+        self.devices.remove(&address).is_some()
+    }
+
+    /// The device at the address, if it is of the type.
+    pub fn device<D: SimDevice + 'static>(&self, address: I2cAddr) -> Option<&D> {
+        self.devices.get(&address)?.as_any().downcast_ref()
+    }
+
+    pub fn device_mut<D: SimDevice + 'static>(&mut self, address: I2cAddr) -> Option<&mut D> {
+        self.devices.get_mut(&address)?.as_any_mut().downcast_mut()
+    }
+
+    /// Lets the time pass for all of the devices.
+    pub fn advance(&mut self, dt: Duration) {
+        self.elapsed += dt;
+        for device in self.devices.values_mut() {
+            device.advance(dt);
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Makes the next transfers to the address fail as not acknowledged,
+    /// without reaching the device.
+    pub fn inject_failures(&mut self, address: I2cAddr, count: usize) {
+        self.failures.insert(address, count);
+    }
+
+    fn select(&mut self, address: SevenBitAddress) -> Result<&mut Box<dyn SimDevice>> {
+        if let Some(count) = self.failures.get_mut(&I2cAddr(address)).filter(|n| **n > 0) {
+            *count -= 1;
+            return Err(Error::other(format!(
+                "Injected failure at 0x{:02x}",
+                address
+            )));
+        }
+        self.devices.get_mut(&I2cAddr(address)).ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("No device at 0x{:02x}", address),
+            )
+        })
+    }
+}
+
+impl Write for SimBus {
+    type Error = Error;
+
+    fn write(&mut self, address: SevenBitAddress, bytes: &[u8]) -> Result<()> {
+        self.select(address)?.write(bytes)
+    }
+}
+
+impl WriteRead for SimBus {
+    type Error = Error;
+
+    fn write_read(&mut self, address: SevenBitAddress, bytes: &[u8], buf: &mut [u8]) -> Result<()> {
+        let device = self.select(address)?;
+        device.write(bytes)?;
+        device.read(buf)
+    }
+}
+
+impl LockBus for SimBus {
+    fn lock_bus<R>(&mut self, f: impl FnOnce(&mut LockedBus<'_, Self>) -> R) -> R {
+        f(self)
+    }
+}
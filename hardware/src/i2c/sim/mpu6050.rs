@@ -0,0 +1,412 @@
+use super::SimDevice;
+use crate::i2c::mpu6050::fifo::FIFO_SIZE;
+use crate::i2c::mpu6050::power::WakeFrequency;
+use crate::i2c::mpu6050::raw_data::*;
+use crate::i2c::mpu6050::register::*;
+use crate::i2c::mpu6050::WHO_AM_I;
+use crate::i2c::{RegAddr, Register};
+
+use std::any::Any;
+use std::collections::VecDeque;
+use std::io::Result;
+use std::time::Duration;
+
+const REGISTER_SIZE: usize = 0x80;
+/// ACCEL_XOUT_H to GYRO_ZOUT_L.
+const SENSOR_DATA: std::ops::Range<usize> = 0x3b..0x49;
+/// Registers which the host cannot write: the sensor data, EXT_SENS_DATA and the status.
+const READ_ONLY: [std::ops::Range<usize>; 4] = [0x36..0x37, 0x3a..0x61, 0x72..0x74, 0x75..0x76];
+/// I2C_SLV0_CTRL to I2C_SLV3_CTRL.
+const SLAVE_CTRL: [usize; 4] = [0x27, 0x2a, 0x2d, 0x30];
+const EXT_SENS_DATA: usize = 0x49;
+
+/// The motion applied to the sensor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Motion {
+    /// Acceleration in m/s², including the gravity.
+    pub accel: [f64; 3],
+    /// Angular velocity in deg/s.
+    pub gyro: [f64; 3],
+    /// Temperature in degrees C.
+    pub temp: f64,
+}
+
+impl Motion {
+    /// At rest with the Z axis up, at 25 degrees C.
+    pub fn still() -> Self {
+        Self {
+            accel: [0.0, 0.0, GRAVITY],
+            gyro: [0.0; 3],
+            temp: 25.0,
+        }
+    }
+}
+
+/// The motion at the time since the simulation started.
+pub trait MotionProfile: Send {
+    fn motion(&mut self, t: Duration) -> Motion;
+}
+
+impl<F> MotionProfile for F
+where
+    F: FnMut(Duration) -> Motion + Send,
+{
+    fn motion(&mut self, t: Duration) -> Motion {
+        self(t)
+    }
+}
+
+/// An MPU6050 at the register level.
+///
+/// The registers are reset to the power-on values by DEVICE_RESET,
+/// and the FIFO buffer and the signal paths by the reset bits of USER_CTRL.
+/// While the device is awake, it takes a sample of the profile at every sample period
+/// (or at LP_WAKE_CTRL in cycle mode), converts it with the full scales,
+/// loads the FIFO buffer and sets the enabled interrupts in INT_STATUS.
+/// INT_STATUS and I2C_MST_STATUS are cleared by reading them, and reading FIFO_R_W pops the buffer.
+/// The offset and self-test registers are kept but do not affect the data.
+pub struct SimMpu6050 {
+    regs: [u8; REGISTER_SIZE],
+    pointer: u8,
+    fifo: VecDeque<u8>,
+    profile: Box<dyn MotionProfile>,
+    elapsed: Duration,
+    next_sample: Duration,
+    last_accel: Option<[f64; 3]>,
+}
+
+impl SimMpu6050 {
+    pub fn new<P: MotionProfile + 'static>(profile: P) -> Self {
+        let mut o = Self {
+            regs: [0; REGISTER_SIZE],
+            pointer: 0,
+            fifo: VecDeque::new(),
+            profile: Box::new(profile),
+            elapsed: Duration::ZERO,
+            next_sample: Duration::ZERO,
+            last_accel: None,
+        };
+        o.reset();
+        o
+    }
+
+    /// At rest with the Z axis up.
+    pub fn still() -> Self {
+        Self::new(|_| Motion::still())
+    }
+
+    pub fn set_profile<P: MotionProfile + 'static>(&mut self, profile: P) {
+        self.profile = Box::new(profile);
+    }
+
+    /// The value of the register, without the side effects of reading it.
+    pub fn register(&self, reg: RegAddr) -> u8 {
+        self.regs[reg.0 as usize]
+    }
+
+    /// Overwrites the register, e.g. to inject EXT_SENS_DATA or a fault.
+    pub fn set_register(&mut self, reg: RegAddr, v: u8) {
+        self.regs[reg.0 as usize] = v;
+    }
+
+    /// The number of bytes in the FIFO buffer.
+    pub fn fifo_len(&self) -> usize {
+        self.fifo.len()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// The interval of samples with the current configuration.
+    pub fn sample_period(&self) -> Duration {
+        let power = PwrMgmt1::from(self.regs[PwrMgmt1::ADDR.0 as usize]);
+        if power.get_cycle() {
+            let wake = PwrMgmt2::from(self.regs[PwrMgmt2::ADDR.0 as usize]).get_lp_wake_ctrl();
+            let millis = match wake {
+                WakeFrequency::Hz1_25 => 800,
+                WakeFrequency::Hz5 => 200,
+                WakeFrequency::Hz20 => 50,
+                WakeFrequency::Hz40 => 25,
+            };
+            return Duration::from_millis(millis);
+        }
+        let config = Configure::from(self.regs[Configure::ADDR.0 as usize]);
+        let output_rate = match config.get_dlpf() {
+            DigitalLowPassFilterCfg::V0 | DigitalLowPassFilterCfg::V7 => 8000,
+            _ => 1000,
+        };
+        let div = self.regs[SampleRateDivider::ADDR.0 as usize] as u64;
+        Duration::from_nanos(1_000_000_000 * (1 + div) / output_rate)
+    }
+
+    fn is_sampling(&self) -> bool {
+        let power = PwrMgmt1::from(self.regs[PwrMgmt1::ADDR.0 as usize]);
+        !power.get_sleep()
+    }
+
+    fn reset(&mut self) {
+        self.regs = [0; REGISTER_SIZE];
+        self.regs[PwrMgmt1::ADDR.0 as usize] = 0x40;
+        self.regs[WhoAmI::ADDR.0 as usize] = WHO_AM_I;
+        self.fifo.clear();
+        self.last_accel = None;
+    }
+
+    fn write_register(&mut self, reg: u8, v: u8) {
+        let i = reg as usize;
+        if i >= REGISTER_SIZE || READ_ONLY.iter().any(|r| r.contains(&i)) {
+            return;
+        }
+        match RegAddr(reg) {
+            PwrMgmt1::ADDR if PwrMgmt1::from(v).get_device_reset() => self.reset(),
+            PwrMgmt1::ADDR => {
+                let asleep = !self.is_sampling();
+                self.regs[i] = v;
+                if asleep && self.is_sampling() {
+                    self.next_sample = self.elapsed + self.sample_period();
+                }
+            }
+            UserCtrl::ADDR => {
+                let mut ctrl = UserCtrl::from(v);
+                if ctrl.get_fifo_reset() {
+                    self.fifo.clear();
+                }
+                if ctrl.get_sigcond_reset() {
+                    self.regs[SENSOR_DATA].fill(0);
+                }
+                ctrl.set_fifo_reset(false);
+                ctrl.set_i2cmst_reset(false);
+                ctrl.set_sigcond_reset(false);
+                self.regs[i] = ctrl.into();
+            }
+            SignalPathReset::ADDR => self.regs[i] = 0,
+            FifoData::ADDR => {}
+            _ => self.regs[i] = v,
+        }
+    }
+
+    fn read_register(&mut self, reg: u8) -> u8 {
+        let i = reg as usize;
+        if i >= REGISTER_SIZE {
+            return 0;
+        }
+        match RegAddr(reg) {
+            IntStatus::ADDR | I2cMstStatus::ADDR => std::mem::take(&mut self.regs[i]),
+            FifoData::ADDR => self.fifo.pop_front().unwrap_or(0),
+            RegAddr(0x72) => (self.fifo.len() >> 8) as u8,
+            RegAddr(0x73) => self.fifo.len() as u8,
+            _ => self.regs[i],
+        }
+    }
+
+    fn sample(&mut self) {
+        let motion = self.profile.motion(self.elapsed);
+        let accel_fs = AccelConfig::from(self.regs[AccelConfig::ADDR.0 as usize]).get_scale();
+        let gyro_fs = GyroConfig::from(self.regs[GyroConfig::ADDR.0 as usize]).get_scale();
+        let power = PwrMgmt1::from(self.regs[PwrMgmt1::ADDR.0 as usize]);
+        let standby = PwrMgmt2::from(self.regs[PwrMgmt2::ADDR.0 as usize]);
+        let stby_accel = standby.get_stby_accel();
+        let stby_gyro = standby.get_stby_gyro();
+        let stby_accel = [stby_accel.x(), stby_accel.y(), stby_accel.z()];
+        let stby_gyro = [stby_gyro.x(), stby_gyro.y(), stby_gyro.z()];
+
+        for axis in 0..3 {
+            if !stby_accel[axis] {
+                let g = motion.accel[axis] / GRAVITY;
+                self.set_i16(0x3b + 2 * axis, to_raw(g, accel_fs.max()));
+            }
+            if !stby_gyro[axis] && !power.get_cycle() {
+                self.set_i16(0x43 + 2 * axis, to_raw(motion.gyro[axis], gyro_fs.max()));
+            }
+        }
+        if !power.get_tempdis() {
+            let raw = ((motion.temp - 36.53) * 340.0).round();
+            self.set_i16(0x41, raw.clamp(i16::MIN as f64, i16::MAX as f64) as i16);
+        }
+
+        let mut status = IntStatus::from(self.regs[IntStatus::ADDR.0 as usize]);
+        let enabled = IntEnable::from(self.regs[IntEnable::ADDR.0 as usize]);
+        if self.load_fifo() && enabled.get_fifo_oflow_en() {
+            status.set_fifo_oflow_int(true);
+        }
+        if enabled.get_datardy_en() {
+            status.set_data_rdy_int(true);
+        }
+        if enabled.get_mot_en() && self.detect_motion(motion.accel) {
+            status.set_mot_int(true);
+        }
+        self.regs[IntStatus::ADDR.0 as usize] = status.into();
+    }
+
+    /// Returns true if the FIFO buffer overflowed. The oldest bytes are discarded.
+    fn load_fifo(&mut self) -> bool {
+        let ctrl = UserCtrl::from(self.regs[UserCtrl::ADDR.0 as usize]);
+        if !ctrl.get_fifo_en() {
+            return false;
+        }
+        let enable = FifoEnable::from(self.regs[FifoEnable::ADDR.0 as usize]);
+        let slv3 = I2cMstCtrl::from(self.regs[I2cMstCtrl::ADDR.0 as usize]).get_slv3_fifo_en();
+        let mut frame = vec![];
+        if enable.get_accel() {
+            frame.extend_from_slice(&self.regs[0x3b..0x41]);
+        }
+        if enable.get_temp() {
+            frame.extend_from_slice(&self.regs[0x41..0x43]);
+        }
+        for (on, start) in [
+            (enable.get_xg(), 0x43),
+            (enable.get_yg(), 0x45),
+            (enable.get_zg(), 0x47),
+        ] {
+            if on {
+                frame.extend_from_slice(&self.regs[start..start + 2]);
+            }
+        }
+        let slaves = [
+            enable.get_slv0(),
+            enable.get_slv1(),
+            enable.get_slv2(),
+            slv3,
+        ];
+        let mut ext = EXT_SENS_DATA;
+        for (on, ctrl) in slaves.iter().zip(SLAVE_CTRL) {
+            let ctrl = I2cSlvCtrl::from(self.regs[ctrl]);
+            let len = if ctrl.get_en() {
+                ctrl.get_len() as usize
+            } else {
+                0
+            };
+            if *on {
+                frame.extend_from_slice(&self.regs[ext..ext + len]);
+            }
+            ext += len;
+        }
+
+        self.fifo.extend(frame);
+        let over = self.fifo.len().saturating_sub(FIFO_SIZE as usize);
+        self.fifo.drain(..over);
+        over > 0
+    }
+
+    /// Whether an axis changed more than MOT_THR (2mg per LSB) since the last sample.
+    fn detect_motion(&mut self, accel: [f64; 3]) -> bool {
+        let threshold = self.regs[MotThr::ADDR.0 as usize] as f64 * 0.002 * GRAVITY;
+        let moved = match self.last_accel {
+            Some(last) => (0..3).any(|i| (accel[i] - last[i]).abs() > threshold),
+            None => false,
+        };
+        self.last_accel = Some(accel);
+        moved
+    }
+
+    fn set_i16(&mut self, i: usize, v: i16) {
+        self.regs[i..i + 2].copy_from_slice(&v.to_be_bytes());
+    }
+}
+
+/// The inverse of `FullScale::scaled`.
+fn to_raw(v: f64, max: i32) -> i16 {
+    let raw = (v * AccelFullScale::RESOLUTION as f64 / (2 * max) as f64).round();
+    raw.clamp(i16::MIN as f64, i16::MAX as f64) as i16
+}
+
+impl SimDevice for SimMpu6050 {
+    fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        if let Some((reg, data)) = bytes.split_first() {
+            self.pointer = *reg;
+            for v in data {
+                self.write_register(self.pointer, *v);
+                self.pointer = self.pointer.wrapping_add(1);
+            }
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<()> {
+        for b in buf.iter_mut() {
+            *b = self.read_register(self.pointer);
+            if RegAddr(self.pointer) != FifoData::ADDR {
+                self.pointer = self.pointer.wrapping_add(1);
+            }
+        }
+        Ok(())
+    }
+
+    fn advance(&mut self, dt: Duration) {
+        let end = self.elapsed + dt;
+        if !self.is_sampling() {
+            self.elapsed = end;
+            return;
+        }
+        while self.next_sample <= end {
+            self.elapsed = self.next_sample;
+            self.sample();
+            self.next_sample += self.sample_period();
+        }
+        self.elapsed = end;
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(sim: &mut SimMpu6050, reg: u8, n: usize) -> Vec<u8> {
+        let mut buf = vec![0; n];
+        sim.write(&[reg]).unwrap();
+        sim.read(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn raw_values() {
+        assert_eq!(to_raw(1.0, AccelFullScale::G2.max()), 16375);
+        assert_eq!(to_raw(-1.0, AccelFullScale::G16.max()), -2047);
+        assert_eq!(to_raw(5000.0, GyroFullScale::Deg250.max()), i16::MAX);
+    }
+
+    #[test]
+    fn power_on_values() {
+        let mut sim = SimMpu6050::still();
+        assert_eq!(read(&mut sim, 0x6b, 1), vec![0x40]);
+        assert_eq!(read(&mut sim, 0x75, 1), vec![0x68]);
+
+        // Asleep.
+        sim.advance(Duration::from_millis(10));
+        assert_eq!(read(&mut sim, 0x3b, 6), vec![0; 6]);
+    }
+
+    #[test]
+    fn burst_write_and_reset() {
+        let mut sim = SimMpu6050::still();
+        sim.write(&[0x19, 9, 0x03]).unwrap();
+        assert_eq!(read(&mut sim, 0x19, 2), vec![9, 0x03]);
+        assert_eq!(sim.sample_period(), Duration::from_millis(10));
+
+        sim.write(&[0x6b, 0x80]).unwrap();
+        assert_eq!(read(&mut sim, 0x19, 2), vec![0, 0]);
+        assert_eq!(read(&mut sim, 0x6b, 1), vec![0x40]);
+    }
+
+    #[test]
+    fn samples_and_status() {
+        let mut sim = SimMpu6050::still();
+        sim.write(&[0x38, 0x01]).unwrap();
+        sim.write(&[0x6b, 0x00]).unwrap();
+        sim.advance(Duration::from_millis(1));
+
+        let data = read(&mut sim, 0x3b, 6);
+        assert_eq!(data, vec![0, 0, 0, 0, 0x3f, 0xf7]);
+        assert_eq!(read(&mut sim, 0x3a, 1), vec![0x01]);
+        assert_eq!(read(&mut sim, 0x3a, 1), vec![0x00]);
+    }
+}
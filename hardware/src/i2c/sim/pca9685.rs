@@ -0,0 +1,235 @@
+use super::SimDevice;
+use crate::i2c::pca9685::*;
+use crate::i2c::RegAddr;
+
+use pwm_pca9685::Channel;
+use std::any::Any;
+use std::io::Result;
+
+const REGISTER_SIZE: usize = 0x100;
+const RESTART: u8 = 0b_1000_0000;
+const AUTO_INC: u8 = 0b_0010_0000;
+const SLEEP: u8 = 0b_0001_0000;
+/// The bit 4 of LEDn_ON_H and LEDn_OFF_H.
+const FULL: u8 = 0b_0001_0000;
+/// LED15_OFF_H, the last register of the channels.
+const LAST_CHANNEL_REGISTER: usize = 0x45;
+
+/// The output of a channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PwmOutput {
+    /// In Hz, which is common to all of the channels.
+    pub frequency: f64,
+    /// The width of the high pulse in microseconds.
+    /// 0 while the oscillator is stopped.
+    pub pulse_width_us: f64,
+    /// The counts of LEDn_ON and LEDn_OFF, without the full on and off bits.
+    pub on: u16,
+    pub off: u16,
+}
+
+/// A PCA9685 at the register level.
+///
+/// The registers start with the power-on values: asleep, 200Hz and all channels fully off.
+/// PRE_SCALE can be written only while asleep, as on the hardware,
+/// and the register address increments only if MODE1 enables it.
+/// A write to ALL_LED_ON/OFF is copied to every channel.
+pub struct SimPca9685 {
+    regs: [u8; REGISTER_SIZE],
+    pointer: u8,
+}
+
+impl Default for SimPca9685 {
+    fn default() -> Self {
+        let mut regs = [0; REGISTER_SIZE];
+        regs[MODE1.0 as usize] = SLEEP | 0b_0000_0001;
+        regs[MODE2.0 as usize] = 0b_0000_0100;
+        regs[0x02] = 0xe2;
+        regs[0x03] = 0xe4;
+        regs[0x04] = 0xe8;
+        regs[0x05] = 0xe0;
+        for ch in 0..16 {
+            regs[LED0_ON_L.0 as usize + 4 * ch + 3] = FULL;
+        }
+        regs[PRE_SCALE.0 as usize] = 0x1e;
+        Self { regs, pointer: 0 }
+    }
+}
+
+impl SimPca9685 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The value of the register.
+    pub fn register(&self, reg: RegAddr) -> u8 {
+        self.regs[reg.0 as usize]
+    }
+
+    pub fn is_sleeping(&self) -> bool {
+        self.regs[MODE1.0 as usize] & SLEEP != 0
+    }
+
+    pub fn prescale(&self) -> u8 {
+        self.regs[PRE_SCALE.0 as usize]
+    }
+
+    /// The frequency of the PWM in Hz.
+    pub fn frequency(&self) -> f64 {
+        OSC / (PULSE_BASE * (self.prescale() as f64 + 1.0))
+    }
+
+    /// The output of the channel, which must not be `Channel::All`.
+    pub fn output(&self, channel: Channel) -> PwmOutput {
+        assert!(channel != Channel::All, "A single channel must be given.");
+        let i = channel_register(channel).0 as usize;
+        let count = |i: usize| u16::from_le_bytes([self.regs[i], self.regs[i + 1] & 0x0f]);
+        let on = count(i);
+        let off = count(i + 2);
+        let full_on = self.regs[i + 1] & FULL != 0;
+        let full_off = self.regs[i + 3] & FULL != 0;
+
+        let frequency = self.frequency();
+        let period_us = 1_000_000.0 / frequency;
+        let high = if self.is_sleeping() || full_off {
+            0.0
+        } else if full_on {
+            PULSE_BASE
+        } else {
+            ((off as i32 - on as i32).rem_euclid(PULSE_BASE as i32)) as f64
+        };
+        PwmOutput {
+            frequency,
+            pulse_width_us: period_us * high / PULSE_BASE,
+            on,
+            off,
+        }
+    }
+
+    fn write_register(&mut self, reg: u8, v: u8) {
+        let i = reg as usize;
+        match RegAddr(reg) {
+            MODE1 => {
+                // Writing 1 to RESTART clears it.
+                self.regs[i] = v & !RESTART;
+            }
+            PRE_SCALE if !self.is_sleeping() => {}
+            PRE_SCALE => self.regs[i] = v,
+            RegAddr(r) if r >= ALL_LED_ON_L.0 => {
+                let offset = i - ALL_LED_ON_L.0 as usize;
+                for ch in 0..16 {
+                    self.regs[LED0_ON_L.0 as usize + 4 * ch + offset] = v;
+                }
+            }
+            _ if i <= LAST_CHANNEL_REGISTER => self.regs[i] = v,
+            _ => {}
+        }
+    }
+
+    fn next_pointer(&self) -> u8 {
+        if self.regs[MODE1.0 as usize] & AUTO_INC == 0 {
+            return self.pointer;
+        }
+        match self.pointer as usize {
+            LAST_CHANNEL_REGISTER => ALL_LED_ON_L.0,
+            0xfe => 0x00,
+            _ => self.pointer.wrapping_add(1),
+        }
+    }
+}
+
+impl SimDevice for SimPca9685 {
+    fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        if let Some((reg, data)) = bytes.split_first() {
+            self.pointer = *reg;
+            for v in data {
+                self.write_register(self.pointer, *v);
+                self.pointer = self.next_pointer();
+            }
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<()> {
+        for b in buf.iter_mut() {
+            // ALL_LED registers read 0.
+            *b = match self.pointer {
+                0xfa..=0xfd => 0,
+                p => self.regs[p as usize],
+            };
+            self.pointer = self.next_pointer();
+        }
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn power_on_values() {
+        let sim = SimPca9685::new();
+        assert!(sim.is_sleeping());
+        assert_eq!(sim.prescale(), 0x1e);
+        assert_eq!((sim.frequency() * 10.0).round(), 1969.0);
+        assert_eq!(sim.output(Channel::C3).pulse_width_us, 0.0);
+    }
+
+    #[test]
+    fn prescale_only_while_sleeping() {
+        let mut sim = SimPca9685::new();
+        sim.write(&[0xfe, 121]).unwrap();
+        assert_eq!(sim.prescale(), 121);
+
+        sim.write(&[0x00, 0x01]).unwrap();
+        sim.write(&[0xfe, 100]).unwrap();
+        assert_eq!(sim.prescale(), 121);
+    }
+
+    #[test]
+    fn pulse_width() {
+        let mut sim = SimPca9685::new();
+        sim.write(&[0xfe, 121]).unwrap();
+        sim.write(&[0x00, AUTO_INC]).unwrap();
+        // LED1 from 0 to 307 of 4096 at 50Hz.
+        sim.write(&[0x0a, 0x00, 0x00, 0x33, 0x01]).unwrap();
+
+        let out = sim.output(Channel::C1);
+        assert_eq!((out.on, out.off), (0, 307));
+        assert_eq!((out.frequency * 10.0).round(), 500.0);
+        assert_eq!(out.pulse_width_us.round(), 1498.0);
+
+        // The pulse may wrap around the end of the period.
+        sim.write(&[0x0a, 0x00, 0x0f, 0x33, 0x00]).unwrap();
+        assert_eq!(sim.output(Channel::C1).pulse_width_us.round(), 1498.0);
+    }
+
+    #[test]
+    fn without_auto_increment() {
+        let mut sim = SimPca9685::new();
+        sim.write(&[0x00, 0x00]).unwrap();
+        sim.write(&[0x06, 0x01, 0x02]).unwrap();
+        assert_eq!(sim.register(RegAddr(0x06)), 0x02);
+        assert_eq!(sim.register(RegAddr(0x07)), 0x00);
+    }
+
+    #[test]
+    fn all_led() {
+        let mut sim = SimPca9685::new();
+        sim.write(&[0x00, AUTO_INC]).unwrap();
+        sim.write(&[0xfa, 0x00, 0x10, 0x00, 0x00]).unwrap();
+        for ch in [Channel::C0, Channel::C15] {
+            let out = sim.output(ch);
+            assert_eq!(out.pulse_width_us, 1_000_000.0 / out.frequency);
+        }
+    }
+}
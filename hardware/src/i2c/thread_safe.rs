@@ -1,3 +1,5 @@
+use super::{LockBus, LockedBus};
+
 use embedded_hal::blocking::i2c::{AddressMode, Write, WriteRead};
 use parking_lot::{Mutex, MutexGuard};
 use std::sync::Arc;
@@ -43,3 +45,12 @@ where
         self.0.lock().write_read(address, bytes, buf)
     }
 }
+
+impl<T> LockBus for ThreadSafeI2c<T>
+where
+    T: Write + WriteRead,
+{
+    fn lock_bus<R>(&mut self, f: impl FnOnce(&mut LockedBus<'_, Self>) -> R) -> R {
+        f(&mut *self.0.lock())
+    }
+}
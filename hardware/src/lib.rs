@@ -1,2 +1,7 @@
+#![allow(stable_features)]
+
+pub mod error;
 pub mod i2c;
 pub mod model;
+
+pub use error::*;
@@ -4,14 +4,33 @@ use super::*;
 
 use derive_more::Constructor;
 use getset::CopyGetters;
-use num_traits::FloatConst;
+use num_traits::{Float, FloatConst, FromPrimitive};
 
+/// Angular velocity around each axis in `A` per second.
 #[derive(Debug, PartialEq, Eq, Constructor, CopyGetters)]
 #[get_copy = "pub"]
-pub struct Gyro3D<V: Copy + FloatConst> {
-    x: AngleVelocity<V>,
-    y: AngleVelocity<V>,
-    z: AngleVelocity<V>,
+pub struct Gyro3D<V: Copy + FloatConst, A: Copy = Degrees<V>> {
+    x: UnitsDiv<V, A, Seconds<V>>,
+    y: UnitsDiv<V, A, Seconds<V>>,
+    z: UnitsDiv<V, A, Seconds<V>>,
+}
+
+impl<V> Gyro3D<V>
+where
+    V: Copy + Float + FloatConst + FromPrimitive,
+    V: From<AngleVelocity<V>>,
+    V: From<Degrees<V>>,
+    V: From<Radians<V>>,
+{
+    pub fn to_radians(&self) -> Gyro3D<V, Radians<V>> {
+        let conv = |a: AngleVelocity<V>| {
+            let deg: V = a.into();
+            let deg = Degrees::from(deg);
+            let rad: V = Radians::from(deg).into();
+            UnitsDiv::from(rad)
+        };
+        Gyro3D::new(conv(self.x), conv(self.y), conv(self.z))
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Constructor, CopyGetters)]
@@ -100,6 +119,15 @@ mod tests {
         assert_eq!(b.z(), 3.0);
     }
 
+    #[test]
+    fn gyro_to_radians() {
+        let a: Gyro3D<f64> = Gyro3D::new(180_f64.into(), 90_f64.into(), (-45_f64).into());
+        let b = a.to_radians();
+        assert_eq!(f64::from(b.x()), std::f64::consts::PI);
+        assert_eq!(f64::from(b.y()), std::f64::consts::FRAC_PI_2);
+        assert_eq!(f64::from(b.z()), -std::f64::consts::FRAC_PI_4);
+    }
+
     #[test]
     fn vector_add() {
         let a = Vector3D::new(1_f64, 2_f64, 3_f64);
@@ -1,5 +1,8 @@
+use super::{Accel3D, Celsius, Gyro3D};
+
 use derive_more::Constructor;
 use getset::CopyGetters;
+use num_traits::FloatConst;
 
 #[derive(Debug, Constructor, CopyGetters, PartialEq)]
 #[get_copy = "pub"]
@@ -16,3 +19,11 @@ pub struct AccelInfo<V: Copy> {
     y: V,
     z: V,
 }
+
+/// Measurements of the accelerometer, gyroscope and temperature sensor in physical units.
+#[derive(Debug, PartialEq)]
+pub struct Measurement<V: Copy + FloatConst> {
+    pub accel: Accel3D<V>,
+    pub gyro: Gyro3D<V>,
+    pub temp: Celsius<V>,
+}
@@ -0,0 +1,37 @@
+use measure_units::*;
+
+/// Not `Convertible`, which only scales the value;
+/// the other temperature units (K, °F) need an offset.
+#[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, CalcMix)]
+#[calcmix(unit_name = "°C".to_string())]
+pub struct Celsius<V>(V);
+
+pub trait MkTemperature<V> {
+    fn celsius(self) -> Celsius<V>;
+}
+
+impl MkTemperature<f32> for f32 {
+    fn celsius(self) -> Celsius<f32> {
+        self.into()
+    }
+}
+
+impl MkTemperature<f64> for f64 {
+    fn celsius(self) -> Celsius<f64> {
+        self.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn additions() {
+        let a = 20_f64.celsius() + 1.5_f64.celsius();
+        assert_eq!(a.to_string(), "21.5°C");
+
+        let a = 20_f32.celsius() - 25_f32.celsius();
+        assert_eq!(a.to_string(), "-5°C");
+    }
+}
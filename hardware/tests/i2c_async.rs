@@ -0,0 +1,250 @@
+#![cfg(feature = "async")]
+
+use hardware::i2c::asynchronous::mpu6050::AsyncMPU6050;
+use hardware::i2c::asynchronous::pca9685::AsyncPCA9685;
+use hardware::i2c::asynchronous::*;
+use hardware::i2c::mpu6050::aux_i2c::Slave;
+use hardware::i2c::mpu6050::fifo::*;
+use hardware::i2c::mpu6050::raw_data::*;
+use hardware::i2c::mpu6050::ADDRESS_LOW;
+use hardware::i2c::servo::ServoMotor;
+use hardware::i2c::sim::mpu6050::*;
+use hardware::i2c::sim::pca9685::*;
+use hardware::i2c::sim::SimBus;
+use hardware::i2c::*;
+use hardware::ErrorKind;
+use pwm_pca9685::Channel;
+use std::time::Duration;
+
+const PWM_ADDR: I2cAddr = I2cAddr(0x40);
+
+fn sim_bus() -> AsyncBus<SimBus> {
+    let mut bus = SimBus::new();
+    bus.attach(ADDRESS_LOW, SimMpu6050::still());
+    bus.attach(PWM_ADDR, SimPca9685::new());
+    AsyncBus::new(bus)
+}
+
+async fn setup_mpu(bus: &AsyncBus<SimBus>) -> AsyncMPU6050<SimBus> {
+    let dev = AsyncI2cWithAddr::new(bus.clone(), ADDRESS_LOW);
+    let mut mpu = AsyncMPU6050::new_verified(dev).await.unwrap();
+    mpu.normal_setup().await.unwrap();
+    mpu
+}
+
+async fn advance(bus: &AsyncBus<SimBus>, millis: u64) {
+    bus.lock().await.advance(Duration::from_millis(millis));
+}
+
+#[tokio::test(start_paused = true)]
+async fn mpu6050_measures() {
+    let bus = sim_bus();
+    let mut mpu = setup_mpu(&bus).await;
+    mpu.set_accel_full_scale(AccelFullScale::G8).await.unwrap();
+    advance(&bus, 10).await;
+
+    assert_eq!(mpu.get_infos().await.unwrap().accel.z, 4094);
+    let m = mpu.get_measurement::<f64>().await.unwrap();
+    let z: f64 = m.accel.z().into();
+    assert!((z - GRAVITY).abs() < 0.01);
+}
+
+#[tokio::test(start_paused = true)]
+async fn mpu6050_identity() {
+    let bus = sim_bus();
+    let dev = AsyncI2cWithAddr::new(bus.clone(), PWM_ADDR);
+    let err = AsyncMPU6050::new_verified(dev).await.err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedDevice);
+}
+
+#[tokio::test(start_paused = true)]
+async fn mpu6050_fifo() {
+    let bus = sim_bus();
+    let mut mpu = setup_mpu(&bus).await;
+    let err = mpu
+        .enable_fifo(FifoSensors::all().with_slave(Slave::Slv0, 2))
+        .await
+        .expect_err("the auxiliary I2C master is not supported");
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    mpu.enable_fifo(FifoSensors::new(true, false, true))
+        .await
+        .unwrap();
+    // 1.6kHz: 8kHz without the low pass filter, divided by 5 in `normal_setup`.
+    advance(&bus, 50).await;
+    let frames = mpu.read_fifo().await.unwrap();
+    assert_eq!(frames.len(), 80);
+    assert_eq!(frames[0].accel.unwrap().z, 16375);
+
+    mpu.disable_fifo().await.unwrap();
+    assert_eq!(
+        mpu.read_fifo().await.unwrap_err().kind(),
+        ErrorKind::InvalidInput
+    );
+}
+
+#[tokio::test]
+async fn pca9685_servo() {
+    let bus = sim_bus();
+    let mut pwm = AsyncPCA9685::new_verified(bus.clone(), PWM_ADDR.0)
+        .await
+        .unwrap();
+    let servo = ServoMotor::new(Channel::C2, 50.0, 0.5, 2.4);
+    pwm.set_duty_cycle(&[(&servo, servo.calc_pulse(1.0))])
+        .await
+        .unwrap();
+    pwm.enable().await.unwrap();
+
+    let out = {
+        let bus = bus.lock().await;
+        let sim: &SimPca9685 = bus.device(PWM_ADDR).unwrap();
+        assert!(!sim.is_sleeping());
+        sim.output(Channel::C2)
+    };
+    assert!((out.frequency - 50.0).abs() < 0.5);
+    assert!((out.pulse_width_us - 2400.0).abs() < 20.0);
+
+    // The oscillator is stopped and restarted for another prescale.
+    let fast = ServoMotor::new(Channel::C3, 100.0, 0.5, 2.4);
+    let err = pwm
+        .set_duty_cycle(&[
+            (&servo, servo.calc_pulse(0.0)),
+            (&fast, fast.calc_pulse(0.0)),
+        ])
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    pwm.set_duty_cycle(&[(&fast, fast.calc_pulse(0.0))])
+        .await
+        .unwrap();
+    let bus = bus.lock().await;
+    let sim: &SimPca9685 = bus.device(PWM_ADDR).unwrap();
+    assert!(!sim.is_sleeping());
+    assert!((sim.frequency() - 100.0).abs() < 1.0);
+}
+
+#[tokio::test]
+async fn pca9685_keeps_widths_for_another_prescale() {
+    let bus = sim_bus();
+    let mut pwm = AsyncPCA9685::new(bus.clone(), PWM_ADDR.0);
+    pwm.enable().await.unwrap();
+    let slow = ServoMotor::new(Channel::C2, 50.0, 0.5, 2.4);
+    let fast = ServoMotor::new(Channel::C3, 100.0, 0.5, 2.4);
+    pwm.set_duty_cycle(&[(&slow, slow.calc_pulse(1.0))])
+        .await
+        .unwrap();
+    pwm.set_duty_cycle(&[(&fast, fast.calc_pulse(0.0))])
+        .await
+        .unwrap();
+
+    // The running channel is rewritten for the new period.
+    let bus = bus.lock().await;
+    let sim: &SimPca9685 = bus.device(PWM_ADDR).unwrap();
+    let slow = sim.output(Channel::C2);
+    assert!((slow.frequency - 100.0).abs() < 1.0);
+    assert!((slow.pulse_width_us - 2400.0).abs() < 20.0);
+    assert!((sim.output(Channel::C3).pulse_width_us - 500.0).abs() < 20.0);
+}
+
+#[tokio::test]
+async fn pca9685_out_of_range() {
+    let bus = sim_bus();
+    let mut pwm = AsyncPCA9685::new(bus, PWM_ADDR.0);
+    let err = pwm.set_one_duty_cycle(Channel::C0, 1.0).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = pwm.set_prescale(2).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[tokio::test(start_paused = true)]
+async fn waits_for_the_bus_without_blocking() {
+    let bus = sim_bus();
+    let mut dev = AsyncI2cWithAddr::new(bus.clone(), PWM_ADDR);
+
+    let guard = bus.lock().await;
+    let modify = dev.modify_register(|r: &mut Mode1| r.0 |= 0x80);
+    // The runtime keeps running other work, here the timer, while the bus is held.
+    assert!(tokio::time::timeout(Duration::from_millis(10), modify)
+        .await
+        .is_err());
+    drop(guard);
+
+    let value = dev
+        .modify_register(|r: &mut Mode1| r.0 |= 0x08)
+        .await
+        .unwrap();
+    assert_eq!(value, Mode1(0x19));
+}
+
+#[tokio::test]
+async fn tasks_share_the_bus() {
+    let bus = sim_bus();
+    let mut mpu = AsyncMPU6050::new(AsyncI2cWithAddr::new(bus.clone(), ADDRESS_LOW));
+    let mut pwm = AsyncPCA9685::new(bus.clone(), PWM_ADDR.0);
+
+    let imu = tokio::spawn(async move {
+        for _ in 0..20 {
+            mpu.set_sleep_enabled(false).await.unwrap();
+            mpu.get_infos().await.unwrap();
+        }
+    });
+    let servo = tokio::spawn(async move {
+        pwm.enable().await.unwrap();
+        for i in 0..20 {
+            pwm.set_one_duty_cycle(Channel::C0, i as f64 / 40.0)
+                .await
+                .unwrap();
+        }
+    });
+    imu.await.unwrap();
+    servo.await.unwrap();
+
+    let bus = bus.lock().await;
+    let sim: &SimPca9685 = bus.device(PWM_ADDR).unwrap();
+    assert_eq!(sim.output(Channel::C0).off, (19.0 / 40.0 * 4096.0) as u16);
+}
+
+/// Blocks every write until it is released.
+struct SlowBus(std::sync::mpsc::Receiver<()>);
+
+impl embedded_hal::blocking::i2c::Write for SlowBus {
+    type Error = std::io::Error;
+
+    fn write(&mut self, _: u8, _: &[u8]) -> Result<(), Self::Error> {
+        self.0
+            .recv_timeout(Duration::from_secs(1))
+            .map_err(std::io::Error::other)
+    }
+}
+
+#[tokio::test]
+async fn slow_transfer_does_not_block_the_runtime() {
+    let (release, rx) = std::sync::mpsc::channel();
+    let bus = AsyncBus::new(SlowBus(rx));
+    let write = tokio::spawn(async move { bus.write(PWM_ADDR.0, &[0]).await });
+
+    // The timer of this task fires while the write is blocked.
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert!(!write.is_finished());
+    release.send(()).unwrap();
+    write.await.unwrap().unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Mode1(u8);
+
+impl From<u8> for Mode1 {
+    fn from(v: u8) -> Self {
+        Mode1(v)
+    }
+}
+
+impl From<Mode1> for u8 {
+    fn from(v: Mode1) -> Self {
+        v.0
+    }
+}
+
+impl Register for Mode1 {
+    const ADDR: RegAddr = RegAddr(0x00);
+}
@@ -1,9 +1,13 @@
+#![allow(dead_code)]
+
 use hardware::i2c::register_io::*;
 
 use core::fmt::Debug;
 use derive_more::{From, Into};
 use embedded_hal::blocking::i2c::{SevenBitAddress, Write, WriteRead};
-use std::collections::{HashMap, VecDeque};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::rc::Rc;
 
 type I2cAddr = SevenBitAddress;
 
@@ -11,6 +15,8 @@ type I2cAddr = SevenBitAddress;
 pub struct MockI2c {
     pub reading: HashMap<I2cAddr, VecDeque<u8>>,
     pub written: HashMap<I2cAddr, VecDeque<u8>>,
+    /// Addresses which do not acknowledge.
+    pub absent: HashSet<I2cAddr>,
     current_addr: I2cAddr,
 }
 
@@ -22,6 +28,17 @@ impl MockI2c {
         }
     }
 
+    fn select(&mut self, address: I2cAddr) -> Result<(), std::io::Error> {
+        if self.absent.contains(&address) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No device at 0x{:02x}", address),
+            ));
+        }
+        self.current_addr = address;
+        Ok(())
+    }
+
     fn write_current(&mut self, bytes: &[u8]) -> Result<(), std::io::Error> {
         let vec = self
             .written
@@ -51,7 +68,7 @@ impl Write for MockI2c {
     type Error = std::io::Error;
 
     fn write(&mut self, address: I2cAddr, bytes: &[u8]) -> Result<(), Self::Error> {
-        self.current_addr = address;
+        self.select(address)?;
         self.write_current(bytes)
     }
 }
@@ -65,12 +82,61 @@ impl WriteRead for MockI2c {
         bytes: &[u8],
         buf: &mut [u8],
     ) -> Result<(), Self::Error> {
-        self.current_addr = address;
+        self.select(address)?;
         self.write_current(bytes)?;
         self.read_current(buf)
     }
 }
 
+impl LockBus for MockI2c {
+    fn lock_bus<R>(&mut self, f: impl FnOnce(&mut LockedBus<'_, Self>) -> R) -> R {
+        f(self)
+    }
+}
+
+// ----------------------------------------------------------------
+
+pub struct ClonableI2c<T>(pub Rc<RefCell<T>>);
+
+impl<T> ClonableI2c<T> {
+    pub fn new(t: T) -> Self {
+        Self(Rc::new(RefCell::new(t)))
+    }
+}
+
+impl<T> Clone for ClonableI2c<T> {
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0))
+    }
+}
+
+impl<T: Write> Write for ClonableI2c<T> {
+    type Error = <T as Write>::Error;
+
+    fn write(&mut self, address: I2cAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.0.borrow_mut().write(address, bytes)
+    }
+}
+
+impl<T: WriteRead> WriteRead for ClonableI2c<T> {
+    type Error = <T as WriteRead>::Error;
+
+    fn write_read(
+        &mut self,
+        address: I2cAddr,
+        bytes: &[u8],
+        buf: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.0.borrow_mut().write_read(address, bytes, buf)
+    }
+}
+
+impl<T: Write + WriteRead> LockBus for ClonableI2c<T> {
+    fn lock_bus<R>(&mut self, f: impl FnOnce(&mut LockedBus<'_, Self>) -> R) -> R {
+        f(&mut *self.0.borrow_mut())
+    }
+}
+
 // ----------------------------------------------------------------
 
 #[derive(Debug, From, Into, Clone, Copy, PartialEq, Eq)]
@@ -92,3 +158,11 @@ pub struct MockRegisterC(pub u8);
 impl Register for MockRegisterC {
     const ADDR: RegAddr = RegAddr(0x23);
 }
+
+#[derive(Debug, From, Into, Clone, Copy, PartialEq, Eq)]
+pub struct MockStatus(pub u8);
+
+impl Register for MockStatus {
+    const ADDR: RegAddr = RegAddr(0x3a);
+    const VOLATILE: bool = true;
+}
@@ -0,0 +1,535 @@
+use hardware::i2c::mpu6050::aux_i2c::*;
+use hardware::i2c::mpu6050::calibration::*;
+use hardware::i2c::mpu6050::fifo::*;
+use hardware::i2c::mpu6050::interrupt::*;
+use hardware::i2c::mpu6050::power::*;
+use hardware::i2c::mpu6050::raw_data::*;
+use hardware::i2c::mpu6050::self_test::*;
+use hardware::i2c::mpu6050::{ADDRESS_LOW, MPU6050};
+use hardware::i2c::*;
+use hardware::ErrorKind;
+use std::collections::VecDeque;
+use std::io;
+use std::time::Duration;
+
+mod i2c_mock;
+use i2c_mock::*;
+
+const ADDR: u8 = 0x68;
+
+fn setup(data: &[u8]) -> (ClonableI2c<MockI2c>, MPU6050<ClonableI2c<MockI2c>>) {
+    let mut mock = MockI2c::default();
+    mock.prepare_data(ADDR, data);
+    let i2c = ClonableI2c::new(mock);
+    let mpu = MPU6050::new(I2cWithAddr::new(i2c.clone(), ADDRESS_LOW)).unwrap();
+    (i2c, mpu)
+}
+
+fn written(i2c: &ClonableI2c<MockI2c>) -> Vec<u8> {
+    i2c.0.borrow().written[&ADDR].iter().copied().collect()
+}
+
+#[test]
+fn fifo_enable() {
+    let (i2c, mut mpu) = setup(&[0]);
+    mpu.enable_fifo(FifoSensors::all()).unwrap();
+
+    assert_eq!(
+        written(&i2c),
+        vec![
+            0x23,
+            0b_1111_1000,
+            0x6a,
+            0x6a,
+            0b_0000_0100,
+            0x6a,
+            0b_0100_0000
+        ]
+    );
+}
+
+#[test]
+fn fifo_read_complete_frames() {
+    let frame_a = [0, 1, 0, 2, 0, 3, 1, 0, 1, 1, 1, 2, 1, 3];
+    let frame_b = [0, 4, 0, 5, 0, 6, 2, 0, 2, 1, 2, 2, 2, 3];
+    let mut data = vec![0, 0, 30];
+    data.extend_from_slice(&frame_a);
+    data.extend_from_slice(&frame_b);
+
+    let (i2c, mut mpu) = setup(&data);
+    mpu.enable_fifo(FifoSensors::all()).unwrap();
+    i2c.0.borrow_mut().written.clear();
+
+    let frames = mpu.read_fifo().unwrap();
+    assert_eq!(written(&i2c), vec![0x72, 0x74]);
+    assert_eq!(frames.len(), 2);
+
+    let raw = frames[1].raw_data().unwrap();
+    assert_eq!(raw.accel, AccelData { x: 4, y: 5, z: 6 });
+    assert_eq!(raw.temp, Temperature::from(0x200));
+    assert_eq!(
+        raw.gyro,
+        GyroData {
+            x: 0x201,
+            y: 0x202,
+            z: 0x203
+        }
+    );
+}
+
+#[test]
+fn fifo_read_nothing() {
+    let (i2c, mut mpu) = setup(&[0, 0, 5]);
+    mpu.enable_fifo(FifoSensors::new(true, false, false))
+        .unwrap();
+    i2c.0.borrow_mut().written.clear();
+
+    let frames = mpu.read_fifo().unwrap();
+    assert!(frames.is_empty());
+    assert_eq!(written(&i2c), vec![0x72]);
+}
+
+#[test]
+fn fifo_overflow() {
+    let (i2c, mut mpu) = setup(&[0, 0x04, 0x00, 0b_0100_0000]);
+    mpu.enable_fifo(FifoSensors::all()).unwrap();
+    i2c.0.borrow_mut().written.clear();
+
+    let err = mpu.read_fifo().expect_err("Must be overflowed");
+    assert_eq!(err.kind(), ErrorKind::FifoOverflow);
+    assert_eq!(
+        written(&i2c),
+        vec![0x72, 0x6a, 0x6a, 0b_0000_0100, 0x6a, 0b_0100_0000]
+    );
+}
+
+#[test]
+fn fifo_not_enabled() {
+    let (_, mut mpu) = setup(&[]);
+    let err = mpu.read_fifo().expect_err("FIFO is not enabled");
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+/// Reports the level of the INT pin in the prepared order, or times out.
+struct FakeLine {
+    levels: VecDeque<bool>,
+    waited_for: Vec<bool>,
+}
+
+impl FakeLine {
+    fn new(levels: &[bool]) -> Self {
+        Self {
+            levels: levels.iter().copied().collect(),
+            waited_for: vec![],
+        }
+    }
+}
+
+impl InterruptLine for FakeLine {
+    type Error = io::Error;
+
+    fn wait_for_level(&mut self, high: bool, _: Duration) -> Result<bool, Self::Error> {
+        self.waited_for.push(high);
+        Ok(self.levels.pop_front() == Some(high))
+    }
+}
+
+const TIMEOUT: Duration = Duration::from_millis(10);
+
+#[test]
+fn interrupt_pin_keeps_bypass() {
+    let (i2c, mut mpu) = setup(&[0b_0000_0010]);
+    let cfg = IntPinConfig::new(IntLevel::ActiveLow, IntDrive::OpenDrain, true, false);
+    mpu.set_interrupt_pin(cfg).unwrap();
+
+    assert_eq!(written(&i2c), vec![0x37, 0x37, 0b_1110_0010]);
+    assert_eq!(mpu.get_interrupt_pin(), cfg);
+}
+
+#[test]
+fn enable_interrupts() {
+    let (i2c, mut mpu) = setup(&[]);
+    let interrupts = Interrupts {
+        fifo_overflow: true,
+        motion: true,
+        ..Interrupts::data_ready()
+    };
+    mpu.enable_interrupts(interrupts).unwrap();
+    assert_eq!(written(&i2c), vec![0x38, 0b_0101_0001]);
+}
+
+#[test]
+fn zero_motion_interrupt() {
+    let (i2c, mut mpu) = setup(&[]);
+    mpu.set_zero_motion_detection(ZeroMotionDetection::new(40, 640))
+        .unwrap();
+    mpu.enable_interrupts(Interrupts {
+        zero_motion: true,
+        ..Interrupts::default()
+    })
+    .unwrap();
+    assert_eq!(written(&i2c), vec![0x21, 20, 0x22, 10, 0x38, 0b_0010_0000]);
+}
+
+#[test]
+fn wait_for_data_ready() {
+    // 1 回目は motion の割り込みなので読み捨てる
+    let (i2c, mut mpu) = setup(&[0, 0b_0100_0000, 0b_0000_0001]);
+    let cfg = IntPinConfig::new(IntLevel::ActiveLow, IntDrive::PushPull, true, false);
+    mpu.set_interrupt_pin(cfg).unwrap();
+    i2c.0.borrow_mut().written.clear();
+
+    let mut line = FakeLine::new(&[false, false]);
+    let status = mpu.wait_for_data_ready(&mut line, TIMEOUT).unwrap();
+    assert_eq!(status, Interrupts::data_ready());
+    assert_eq!(line.waited_for, vec![false, false]);
+    assert_eq!(written(&i2c), vec![0x3a, 0x3a]);
+}
+
+#[test]
+fn wait_for_interrupt_timeout() {
+    let (i2c, mut mpu) = setup(&[]);
+    let mut line = FakeLine::new(&[]);
+    let err = mpu
+        .wait_for_interrupt(&mut line, TIMEOUT)
+        .expect_err("No interrupt is generated");
+    assert_eq!(err.kind(), ErrorKind::Timeout);
+    assert_eq!(line.waited_for, vec![true]);
+    assert!(i2c.0.borrow().written.is_empty());
+}
+
+struct NoDelay;
+
+impl embedded_hal::blocking::delay::DelayMs<u8> for NoDelay {
+    fn delay_ms(&mut self, _: u8) {}
+}
+
+#[test]
+fn calibrate_averages_samples() {
+    // ±2g, ±250dps
+    let mut data = vec![0, 0];
+    for gz in [131_i16, 393] {
+        data.extend_from_slice(&[0, 0, 0, 0, 0x3f, 0xf7, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&gz.to_be_bytes());
+    }
+    let (_, mut mpu) = setup(&data);
+
+    let cal = mpu.calibrate(Gravity::ZUp, 2, 1, &mut NoDelay).unwrap();
+    assert!(cal.accel_offset.iter().all(|v| v.abs() < 1e-9));
+    assert!((cal.gyro_bias[2] - 2.0).abs() < 1e-3);
+}
+
+#[test]
+fn write_offsets_to_registers() {
+    let accel = [0xfc, 0x17, 0x01, 0xf4, 0x04, 0xd2];
+    let gyro = [0, 0, 0, 10, 0, 0];
+    let mut data = accel.to_vec();
+    data.extend_from_slice(&gyro);
+    let (i2c, mut mpu) = setup(&data);
+
+    let cal = Calibration {
+        gyro_bias: [1.0, -1.0, 0.0],
+        accel_offset: [10.0 / 2046.875, 0.0, -20.0 / 2046.875],
+        temp: None,
+    };
+    mpu.write_offsets(&cal).unwrap();
+
+    let [ax0, ax1] = (-1011_i16).to_be_bytes();
+    let [az0, az1] = 1254_i16.to_be_bytes();
+    let [gx0, gx1] = (-33_i16).to_be_bytes();
+    #[rustfmt::skip]
+    let expected = vec![
+        0x06, 0x13,
+        0x06, ax0, ax1, 0x01, 0xf4, az0, az1,
+        0x13, gx0, gx1, 0, 43, 0, 0,
+    ];
+    assert_eq!(written(&i2c), expected);
+}
+
+#[test]
+fn self_test_restores_config() {
+    let accel_config = 0b_0000_1000;
+    let gyro_config = 0b_0001_1000;
+    let mut data = vec![accel_config, gyro_config];
+    // 無効時と有効時の出力
+    data.extend_from_slice(&[0; 14]);
+    let mut enabled = vec![0x05, 0x71, 0, 0, 0, 0, 0, 0];
+    enabled.extend_from_slice(&3275_i16.to_be_bytes());
+    enabled.extend_from_slice(&(-3275_i16).to_be_bytes());
+    enabled.extend_from_slice(&3275_i16.to_be_bytes());
+    data.extend_from_slice(&enabled);
+    data.extend_from_slice(&[0b_0000_0001, 0b_0000_0001, 0b_0000_0001, 0b_0001_0000]);
+    let (i2c, mut mpu) = setup(&data);
+
+    let report = mpu.self_test(&mut NoDelay).unwrap();
+    assert!(report.accel[0].passed());
+    assert_eq!(report.accel[1].change(), None);
+    assert!(report.gyro.iter().all(AxisResult::passed));
+    assert!(!report.passed());
+
+    #[rustfmt::skip]
+    let expected = vec![
+        0x1c, 0x1b,
+        0x1c, 0b_0001_0000, 0x1b, 0b_0000_0000, 0x3b,
+        0x1c, 0b_1111_0000, 0x1b, 0b_1110_0000, 0x3b,
+        0x0d,
+        0x1c, accel_config, 0x1b, gyro_config,
+    ];
+    assert_eq!(written(&i2c), expected);
+}
+
+#[test]
+fn measurement_with_full_scale() {
+    let mut data = vec![0, 0];
+    data.extend_from_slice(&[0x1f, 0xfc, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x06]);
+    let (i2c, mut mpu) = setup(&data);
+    mpu.set_accel_full_scale(AccelFullScale::G4).unwrap();
+    mpu.set_gyro_full_scale(GyroFullScale::Deg500).unwrap();
+
+    let m = mpu.get_measurement::<f64>().unwrap();
+    assert!((f64::from(m.accel.x()) - GRAVITY).abs() < 1e-3);
+    assert!((f64::from(m.gyro.z()) - 4.0).abs() < 1e-3);
+    assert!((f64::from(m.temp) - 36.53).abs() < 1e-9);
+    assert!(written(&i2c).ends_with(&[0x3b]));
+}
+
+#[test]
+fn measurement_reads_full_scale_once() {
+    let mut data = vec![0b_0001_0000, 0b_0000_1000];
+    data.extend_from_slice(&[0; 28]);
+    let (i2c, mut mpu) = setup(&data);
+
+    mpu.get_measurement::<f64>().unwrap();
+    mpu.get_measurement::<f64>().unwrap();
+    assert_eq!(written(&i2c), vec![0x1c, 0x1b, 0x3b, 0x3b]);
+    assert_eq!(mpu.get_accel_full_scale().unwrap(), AccelFullScale::G8);
+    assert_eq!(mpu.get_gyro_full_scale().unwrap(), GyroFullScale::Deg500);
+}
+
+#[test]
+fn wake_on_motion() {
+    let (i2c, mut mpu) = setup(&[0b_0000_1000, 0b_0000_0001]);
+    mpu.enable_wake_on_motion(WakeFrequency::Hz5, MotionDetection::new(40, 5))
+        .unwrap();
+
+    #[rustfmt::skip]
+    let expected = vec![
+        0x1c, 0x1c, 0b_0000_1001,
+        0x1f, 20, 0x20, 5,
+        0x38, 0b_0100_0000,
+        0x6c, 0b_0100_0111,
+        0x6b, 0x6b, 0b_0010_1001,
+    ];
+    assert_eq!(written(&i2c), expected);
+}
+
+#[test]
+fn exit_cycle_mode_keeps_wake_frequency() {
+    let (i2c, mut mpu) = setup(&[0b_0010_1001, 0b_1100_0111]);
+    mpu.exit_cycle_mode().unwrap();
+
+    #[rustfmt::skip]
+    let expected = vec![
+        0x6b, 0x6b, 0b_0000_0001,
+        0x6c, 0x6c, 0b_1100_0000,
+    ];
+    assert_eq!(written(&i2c), expected);
+}
+
+#[test]
+fn bypass_enable() {
+    let (i2c, mut mpu) = setup(&[0b_0010_0000, 0b_0001_0000]);
+    mpu.set_bypass_enabled(true).unwrap();
+
+    #[rustfmt::skip]
+    let expected = vec![
+        0x6a, 0x6a, 0b_0000_0000,
+        0x37, 0x37, 0b_0001_0010,
+    ];
+    assert_eq!(written(&i2c), expected);
+}
+
+#[test]
+fn i2c_master_reads_slaves() {
+    let (i2c, mut mpu) = setup(&[0, 0, 0, 1, 2, 3, 4, 5, 6, 7]);
+    mpu.enable_i2c_master(MasterClock::Khz400).unwrap();
+    mpu.set_slave_read(Slave::Slv0, SlaveRead::new(I2cAddr(0x1e), 0x03, 6))
+        .unwrap();
+    mpu.set_slave_read(Slave::Slv1, SlaveRead::new(I2cAddr(0x0d), 0x06, 1))
+        .unwrap();
+
+    assert_eq!(
+        mpu.read_slave_data(Slave::Slv0).unwrap(),
+        vec![1, 2, 3, 4, 5, 6]
+    );
+    assert_eq!(mpu.read_slave_data(Slave::Slv1).unwrap(), vec![7]);
+    assert!(mpu.read_slave_data(Slave::Slv2).unwrap().is_empty());
+
+    #[rustfmt::skip]
+    let expected = vec![
+        0x37, 0x37, 0b_0000_0000,
+        0x24, 0x24, 0b_0100_1101,
+        0x6a, 0x6a, 0b_0010_0000,
+        0x25, 0x9e, 0x03, 0b_1000_0110,
+        0x28, 0x8d, 0x06, 0b_1000_0001,
+        0x49, 0x4f,
+    ];
+    assert_eq!(written(&i2c), expected);
+}
+
+#[test]
+fn slave_read_invalid() {
+    let (_, mut mpu) = setup(&[]);
+    let err = mpu
+        .set_slave_read(Slave::Slv0, SlaveRead::new(I2cAddr(0x1e), 0x03, 16))
+        .expect_err("Too long");
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    for slave in [Slave::Slv0, Slave::Slv1] {
+        mpu.set_slave_read(slave, SlaveRead::new(I2cAddr(0x1e), 0, 10))
+            .unwrap();
+    }
+    let err = mpu
+        .set_slave_read(Slave::Slv2, SlaveRead::new(I2cAddr(0x1e), 0, 5))
+        .expect_err("EXT_SENS_DATA overflows");
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn slave_register_write() {
+    let (i2c, mut mpu) = setup(&[0, 0b_0100_0000]);
+    mpu.write_slave_register(I2cAddr(0x1e), 0x02, 0x00, Duration::from_secs(1))
+        .unwrap();
+
+    #[rustfmt::skip]
+    let expected = vec![
+        0x31, 0x1e, 0x02, 0x00,
+        0x34, 0b_1000_0000,
+        0x36, 0x36,
+    ];
+    assert_eq!(written(&i2c), expected);
+}
+
+#[test]
+fn slave_register_waits_between_polls() {
+    let (i2c, mut mpu) = setup(&[0, 0, 0, 0b_0100_0000]);
+    let start = std::time::Instant::now();
+    mpu.write_slave_register(I2cAddr(0x1e), 0x02, 0x00, Duration::from_secs(1))
+        .unwrap();
+    assert!(start.elapsed() >= Duration::from_micros(300));
+    let polls = written(&i2c).iter().filter(|b| **b == 0x36).count();
+    assert_eq!(polls, 4);
+}
+
+#[test]
+fn slave_register_read() {
+    let (i2c, mut mpu) = setup(&[0b_0100_0000, 0x48]);
+    let v = mpu
+        .read_slave_register(I2cAddr(0x1e), 0x0a, Duration::from_secs(1))
+        .unwrap();
+    assert_eq!(v, 0x48);
+    assert_eq!(written(&i2c)[1], 0x9e);
+}
+
+#[test]
+fn slave_register_nack() {
+    let (_, mut mpu) = setup(&[0b_0001_0000]);
+    let err = mpu
+        .write_slave_register(I2cAddr(0x1e), 0x02, 0x00, Duration::from_secs(1))
+        .expect_err("NACK");
+    assert_eq!(err.kind(), ErrorKind::AuxNack);
+}
+
+#[test]
+fn fifo_with_slave() {
+    let (i2c, mut mpu) = setup(&[0, 0]);
+    let sensors = FifoSensors::new(false, false, false).with_slave(Slave::Slv3, 2);
+    let err = mpu.enable_fifo(sensors).expect_err("Slave 3 is not read");
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    mpu.set_slave_read(Slave::Slv3, SlaveRead::new(I2cAddr(0x1e), 0, 2))
+        .unwrap();
+    i2c.0.borrow_mut().written.clear();
+    mpu.enable_fifo(sensors).unwrap();
+
+    #[rustfmt::skip]
+    let expected = vec![
+        0x23, 0,
+        0x24, 0x24, 0b_0010_0000,
+        0x6a, 0x6a, 0b_0000_0100,
+        0x6a, 0b_0100_0000,
+    ];
+    assert_eq!(written(&i2c), expected);
+}
+
+#[test]
+fn verified_new() {
+    let mut mock = MockI2c::default();
+    mock.prepare_data(ADDR, &[0x68, 0x98]);
+    let i2c = ClonableI2c::new(mock);
+    let dev = || I2cWithAddr::new(i2c.clone(), ADDRESS_LOW);
+
+    MPU6050::new_verified(dev()).unwrap();
+    let err = MPU6050::new_verified(dev())
+        .err()
+        .expect("MPU6500 is not MPU6050");
+    assert_eq!(err.kind(), ErrorKind::UnexpectedDevice);
+    assert_eq!(written(&i2c), vec![0x75, 0x75]);
+}
+
+#[test]
+fn register_cache() {
+    let mut mock = MockI2c::default();
+    mock.prepare_data(ADDR, &[0b_0000_0000, 0b_0100_0000]);
+    let i2c = ClonableI2c::new(mock);
+    let mut dev = I2cWithAddr::new(i2c.clone(), ADDRESS_LOW);
+    dev.enable_cache();
+    let mut mpu = MPU6050::new(dev).unwrap();
+
+    mpu.set_accel_full_scale(AccelFullScale::G8).unwrap();
+    mpu.set_accel_full_scale(AccelFullScale::G8).unwrap();
+    mpu.set_accel_full_scale(AccelFullScale::G2).unwrap();
+    mpu.reset(&mut NoDelay).unwrap();
+    mpu.set_sleep_enabled(false).unwrap();
+
+    #[rustfmt::skip]
+    let expected = vec![
+        0x1c, 0x1c, 0b_0001_0000,
+        0x1c, 0b_0000_0000,
+        0x6b, 0x6b, 0b_1100_0000,
+        0x6b, 0x6b, 0b_0000_0000,
+    ];
+    assert_eq!(written(&i2c), expected);
+}
+
+/// A bus which cannot be held across transfers.
+struct UnlockedI2c(MockI2c);
+
+impl embedded_hal::blocking::i2c::Write for UnlockedI2c {
+    type Error = io::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.0.write(address, bytes)
+    }
+}
+
+impl embedded_hal::blocking::i2c::WriteRead for UnlockedI2c {
+    type Error = io::Error;
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.0.write_read(address, bytes, buf)
+    }
+}
+
+#[test]
+fn measurement_without_lock_bus() {
+    let mut mock = MockI2c::default();
+    let mut data = vec![0x08, 0x08];
+    data.extend_from_slice(&[0x1f, 0xfc, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x06]);
+    mock.prepare_data(ADDR, &data);
+    let mut mpu = MPU6050::new(I2cWithAddr::new(UnlockedI2c(mock), ADDRESS_LOW)).unwrap();
+
+    let m = mpu.get_measurement::<f64>().unwrap();
+    assert!((f64::from(m.accel.x()) - GRAVITY).abs() < 1e-3);
+    assert!((f64::from(m.gyro.z()) - 4.0).abs() < 1e-3);
+}
@@ -0,0 +1,157 @@
+use hardware::i2c::mpu6050::raw_data::RawData;
+use hardware::i2c::mpu6050::{ADDRESS_LOW, MPU6050};
+use hardware::i2c::recording::*;
+use hardware::i2c::*;
+use hardware::ErrorKind;
+
+mod i2c_mock;
+use i2c_mock::*;
+
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("i2c-{}-{}.rec", name, std::process::id()))
+}
+
+fn record(path: &PathBuf) -> Vec<RawData> {
+    let mut mock = MockI2c::default();
+    let samples: Vec<u8> = (0..28).collect();
+    mock.prepare_data(0x68, &[0x68, 0b_0100_0000]);
+    mock.prepare_data(0x68, &samples);
+    let bus = ThreadSafeI2c::new(RecordingI2c::create(mock, path).unwrap());
+    let mut mpu = MPU6050::new_verified(I2cWithAddr::new(bus.clone(), ADDRESS_LOW)).unwrap();
+    mpu.set_sleep_enabled(false).unwrap();
+    let samples = (0..2).map(|_| mpu.get_infos().unwrap()).collect();
+    bus.lock().flush().unwrap();
+    samples
+}
+
+#[test]
+fn replay_reproduces_recording() {
+    let path = temp_path("replay");
+    let recorded = record(&path);
+
+    let replay = ThreadSafeI2c::new(ReplayI2c::open(&path).unwrap());
+    let mut mpu = MPU6050::new_verified(I2cWithAddr::new(replay.clone(), ADDRESS_LOW)).unwrap();
+    mpu.set_sleep_enabled(false).unwrap();
+    let replayed: Vec<_> = (0..2).map(|_| mpu.get_infos().unwrap()).collect();
+
+    assert_eq!(replayed, recorded);
+    assert!(replay.lock().is_finished());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn replay_detects_divergence() {
+    let path = temp_path("diverge");
+    record(&path);
+
+    let replay = ThreadSafeI2c::new(ReplayI2c::open(&path).unwrap());
+    let mut mpu = MPU6050::new_verified(I2cWithAddr::new(replay.clone(), ADDRESS_LOW)).unwrap();
+    let err = mpu.set_sleep_enabled(true).expect_err("Must diverge");
+    assert_eq!(err.kind(), ErrorKind::Write);
+    assert!(err.bus_error().unwrap().to_string().contains("diverged"));
+
+    let replay = replay.lock();
+    match replay.divergence() {
+        Some(ReplayError::Diverged {
+            index, expected, ..
+        }) => {
+            assert_eq!(*index, 2);
+            assert_eq!(expected.as_ref().unwrap().written, vec![0x6b, 0x00]);
+        }
+        other => panic!("Unexpected {:?}", other),
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn recorded_failure_is_replayed() {
+    let path = temp_path("failure");
+    let mut mock = MockI2c::default();
+    mock.absent.insert(0x40);
+    let mut bus = RecordingI2c::create(mock, &path).unwrap();
+    assert!(!pwm_identify(&mut bus));
+    bus.flush().unwrap();
+
+    let mut replay = ReplayI2c::open(&path).unwrap();
+    assert!(!pwm_identify(&mut replay));
+    assert_eq!(replay.divergence(), None);
+    assert!(replay.is_finished());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn transfers_under_lock_are_recorded() {
+    let path = temp_path("lock");
+    let mut mock = MockI2c::default();
+    mock.prepare_data(0x68, &[0x05]);
+    let bus = RecordingI2c::create(ThreadSafeI2c::new(mock), &path).unwrap();
+    let mut dev = I2cWithAddr::new(bus, ADDRESS_LOW);
+    dev.modify_register(|r: &mut MockRegisterA| r.0 |= 0x80)
+        .unwrap();
+    dev.i2c_device().flush().unwrap();
+
+    let mut replay = I2cWithAddr::new(ReplayI2c::open(&path).unwrap(), ADDRESS_LOW);
+    let value = replay
+        .modify_register(|r: &mut MockRegisterA| r.0 |= 0x80)
+        .unwrap();
+    assert_eq!(value, MockRegisterA(0x85));
+    assert_eq!(replay.i2c_device().divergence(), None);
+    assert!(replay.i2c_device().is_finished());
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// Fails while `failing` is set, and keeps what it accepted.
+#[derive(Clone, Default)]
+struct SharedWriter {
+    failing: Rc<Cell<bool>>,
+    written: Rc<RefCell<Vec<u8>>>,
+}
+
+impl io::Write for SharedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.failing.get() {
+            return Err(io::Error::other("Disk full"));
+        }
+        self.written.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn recording_failure_is_sticky() {
+    let out = SharedWriter::default();
+    let mut mock = MockI2c::default();
+    mock.absent.insert(0x40);
+    let mut bus = RecordingI2c::new(mock, out.clone()).unwrap();
+
+    out.failing.set(true);
+    pwm_identify(&mut bus);
+    out.failing.set(false);
+    let recorded = out.written.borrow().len();
+
+    // The recording stays stopped after the error is returned.
+    for _ in 0..2 {
+        let err = bus.flush().expect_err("Must keep the error");
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+        assert!(err.to_string().contains("Disk full"));
+        pwm_identify(&mut bus);
+    }
+    assert_eq!(out.written.borrow().len(), recorded);
+}
+
+fn pwm_identify<T>(bus: &mut T) -> bool
+where
+    T: embedded_hal::blocking::i2c::WriteRead,
+    <T as embedded_hal::blocking::i2c::WriteRead>::Error: hardware::BusError,
+{
+    hardware::i2c::pca9685::identify(bus, I2cAddr(0x40)).is_ok()
+}
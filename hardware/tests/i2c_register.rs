@@ -57,43 +57,87 @@ fn read_bytes() {
     assert_eq!(written[&7], vec![0xAC]);
 }
 
-// ----------------------------------------------------------------
-use embedded_hal::blocking::i2c::{SevenBitAddress, Write, WriteRead};
-use std::cell::RefCell;
-use std::rc::Rc;
-type I2cAddr = SevenBitAddress;
-
-pub struct ClonableI2c<T>(pub Rc<RefCell<T>>);
-
-impl<T> ClonableI2c<T> {
-    pub fn new(t: T) -> Self {
-        Self(Rc::new(RefCell::new(t)))
-    }
+#[test]
+fn write_bytes() {
+    let mock = MockI2c::default();
+    let safe_i2c = ClonableI2c::new(mock);
+
+    let mut i2c = I2cWithAddr::new(safe_i2c.clone(), 7.into());
+
+    i2c.write_bytes(RegAddr(0x06), &[0x41, 0x32, 0x23]).unwrap();
+
+    let written = safe_i2c.0.borrow().written.clone();
+    assert_eq!(written.len(), 1);
+    assert_eq!(written[&7], vec![0x06, 0x41, 0x32, 0x23]);
 }
 
-impl<T> Clone for ClonableI2c<T> {
-    fn clone(&self) -> Self {
-        Self(Rc::clone(&self.0))
-    }
+#[test]
+fn modify_register() {
+    let mut mock = MockI2c::default();
+    mock.prepare_data(8, &[0b_0101_0000]);
+    let safe_i2c = ClonableI2c::new(mock);
+
+    let mut i2c = I2cWithAddr::new(safe_i2c.clone(), 8.into());
+
+    let value = i2c
+        .modify_register(|r: &mut MockRegisterB| r.0 |= 0b_0000_0011)
+        .unwrap();
+    assert_eq!(value, MockRegisterB(0b_0101_0011));
+
+    let written = safe_i2c.0.borrow().written.clone();
+    assert_eq!(written[&8], vec![0x12, 0x12, 0b_0101_0011]);
 }
 
-impl<T: Write> Write for ClonableI2c<T> {
-    type Error = <T as Write>::Error;
+#[test]
+fn cache_serves_reads_and_skips_writes() {
+    let mut mock = MockI2c::default();
+    mock.prepare_data(8, &[0x17, 0x01, 0x02]);
+    let safe_i2c = ClonableI2c::new(mock);
 
-    fn write(&mut self, address: I2cAddr, bytes: &[u8]) -> Result<(), Self::Error> {
-        self.0.borrow_mut().write(address, bytes)
-    }
+    let mut i2c = I2cWithAddr::new(safe_i2c.clone(), 8.into());
+    i2c.enable_cache();
+
+    let reg_a: MockRegisterA = i2c.read_register().unwrap();
+    let again: MockRegisterA = i2c.read_register().unwrap();
+    assert_eq!(reg_a, again);
+    i2c.write_register(MockRegisterA(0x17)).unwrap();
+    i2c.write_register(MockRegisterB(0x78)).unwrap();
+    i2c.write_register(MockRegisterB(0x78)).unwrap();
+    let reg_b = i2c
+        .modify_register(|r: &mut MockRegisterB| r.0 |= 0x01)
+        .unwrap();
+    assert_eq!(reg_b, MockRegisterB(0x79));
+    i2c.modify_register(|r: &mut MockRegisterB| r.0 |= 0x01)
+        .unwrap();
+
+    // Volatile registers always access the bus.
+    let s0: MockStatus = i2c.read_register().unwrap();
+    let s1: MockStatus = i2c.read_register().unwrap();
+    assert_eq!((s0, s1), (MockStatus(0x01), MockStatus(0x02)));
+
+    let written = safe_i2c.0.borrow().written.clone();
+    assert_eq!(written[&8], vec![0x01, 0x12, 0x78, 0x12, 0x79, 0x3a, 0x3a]);
 }
 
-impl<T: WriteRead> WriteRead for ClonableI2c<T> {
-    type Error = <T as WriteRead>::Error;
-
-    fn write_read(
-        &mut self,
-        address: I2cAddr,
-        bytes: &[u8],
-        buf: &mut [u8],
-    ) -> Result<(), Self::Error> {
-        self.0.borrow_mut().write_read(address, bytes, buf)
-    }
+#[test]
+fn cache_invalidation() {
+    let mut mock = MockI2c::default();
+    mock.prepare_data(8, &[0x17, 0x18, 0x19]);
+    let safe_i2c = ClonableI2c::new(mock);
+
+    let mut i2c = I2cWithAddr::new(safe_i2c.clone(), 8.into());
+    i2c.enable_cache();
+
+    let _: MockRegisterA = i2c.read_register().unwrap();
+    i2c.invalidate_cache();
+    let reg_a: MockRegisterA = i2c.read_register().unwrap();
+    assert_eq!(reg_a, MockRegisterA(0x18));
+
+    // Raw writes are not cached.
+    i2c.write_bytes(RegAddr(0x00), &[0x20, 0x21]).unwrap();
+    let reg_a: MockRegisterA = i2c.read_register().unwrap();
+    assert_eq!(reg_a, MockRegisterA(0x19));
+
+    let written = safe_i2c.0.borrow().written.clone();
+    assert_eq!(written[&8], vec![0x01, 0x01, 0x00, 0x20, 0x21, 0x01]);
 }
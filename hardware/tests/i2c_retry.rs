@@ -0,0 +1,176 @@
+use hardware::i2c::mpu6050::{ADDRESS_LOW, MPU6050, NO_RETRY_REGISTERS};
+use hardware::i2c::pca9685::PCA9685;
+use hardware::i2c::retry::*;
+use hardware::i2c::servo::SG90_180;
+use hardware::i2c::sim::mpu6050::SimMpu6050;
+use hardware::i2c::sim::pca9685::SimPca9685;
+use hardware::i2c::sim::SimBus;
+use hardware::i2c::*;
+use hardware::ErrorKind;
+use pwm_pca9685::Channel;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const ADDR: I2cAddr = I2cAddr(0x40);
+
+fn policy(retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        retries,
+        backoff: Duration::ZERO,
+        deadline: None,
+        recover_after: None,
+        ..RetryPolicy::default()
+    }
+}
+
+fn setup(policy: RetryPolicy) -> ThreadSafeI2c<RetryingI2c<SimBus>> {
+    let mut bus = SimBus::new();
+    bus.attach(ADDR, SimPca9685::new());
+    ThreadSafeI2c::new(RetryingI2c::new(bus, policy))
+}
+
+#[test]
+fn transient_failures_are_retried() {
+    let bus = setup(policy(3));
+    let mut pwm = PCA9685::new(bus.clone(), ADDR.0).unwrap();
+    pwm.enable().unwrap();
+
+    bus.lock().inner().inject_failures(ADDR, 3);
+    let servo = SG90_180::new(Channel::C0, 50.0, 0.5, 2.4);
+    servo.set_angle(&mut pwm, 90.0).unwrap();
+
+    let mut bus = bus.lock();
+    let stats = bus.stats();
+    assert_eq!(stats.retries, 3);
+    assert_eq!(stats.failures, 0);
+    let sim: &SimPca9685 = bus.inner().device(ADDR).unwrap();
+    assert!(sim.output(Channel::C0).pulse_width_us > 0.0);
+}
+
+#[test]
+fn retries_run_out() {
+    let bus = setup(policy(2));
+    let mut pwm = PCA9685::new(bus.clone(), ADDR.0).unwrap();
+
+    bus.lock().inner().inject_failures(ADDR, 3);
+    let err = pwm.enable().expect_err("Must fail");
+    assert_eq!(err.kind(), ErrorKind::Write);
+    assert!(err.bus_error().unwrap().to_string().contains("Injected"));
+
+    // The failure after the retries is not retried again.
+    pwm.enable().unwrap();
+    let stats = bus.lock().stats();
+    assert_eq!(stats.transactions, 2);
+    assert_eq!(stats.retries, 2);
+    assert_eq!(stats.failures, 1);
+}
+
+#[test]
+fn recovery_after_consecutive_failures() {
+    let bus = setup(RetryPolicy {
+        recover_after: Some(4),
+        ..policy(10)
+    });
+    let recoveries = Arc::new(AtomicUsize::new(0));
+    let count = recoveries.clone();
+    bus.lock().set_recovery(move |sim: &mut SimBus| {
+        count.fetch_add(1, Ordering::SeqCst);
+        sim.inject_failures(ADDR, 0);
+        Ok(())
+    });
+    bus.lock().inner().inject_failures(ADDR, 100);
+
+    let mut pwm = PCA9685::new(bus.clone(), ADDR.0).unwrap();
+    pwm.enable().unwrap();
+
+    assert_eq!(recoveries.load(Ordering::SeqCst), 1);
+    let stats = bus.lock().stats();
+    assert_eq!(stats.recoveries, 1);
+    assert_eq!(stats.retries, 4);
+}
+
+#[test]
+fn deadline_stops_retries() {
+    let bus = setup(RetryPolicy {
+        backoff: Duration::from_millis(10),
+        backoff_factor: 1.0,
+        deadline: Some(Duration::from_millis(25)),
+        ..policy(100)
+    });
+    bus.lock().inner().inject_failures(ADDR, 100);
+    let mut pwm = PCA9685::new(bus.clone(), ADDR.0).unwrap();
+
+    let start = Instant::now();
+    pwm.enable().expect_err("Must time out");
+    assert!(start.elapsed() < Duration::from_millis(100));
+    let stats = bus.lock().stats();
+    // 2 unless the sleeps overshoot.
+    assert!((1..=2).contains(&stats.retries));
+    assert_eq!(stats.failures, 1);
+}
+
+#[test]
+fn reads_with_side_effects_are_not_retried() {
+    let bus = setup(policy(3));
+    bus.lock().inner().attach(ADDRESS_LOW, SimMpu6050::still());
+    bus.lock().set_no_retry(ADDRESS_LOW, &NO_RETRY_REGISTERS);
+    let mut mpu = MPU6050::new(I2cWithAddr::new(bus.clone(), ADDRESS_LOW)).unwrap();
+
+    bus.lock().inner().inject_failures(ADDRESS_LOW, 1);
+    let err = mpu.get_interrupt_status().expect_err("Must fail");
+    assert_eq!(err.kind(), ErrorKind::WriteRead);
+    assert_eq!(bus.lock().stats().retries, 0);
+
+    bus.lock().inner().inject_failures(ADDRESS_LOW, 1);
+    mpu.get_infos().unwrap();
+    let stats = bus.lock().stats();
+    assert_eq!(stats.retries, 1);
+    assert_eq!(stats.failures, 1);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Counter(u8);
+
+impl From<u8> for Counter {
+    fn from(v: u8) -> Self {
+        Self(v)
+    }
+}
+
+impl From<Counter> for u8 {
+    fn from(v: Counter) -> Self {
+        v.0
+    }
+}
+
+impl Register for Counter {
+    // SMPLRT_DIV, which the simulator keeps as written.
+    const ADDR: RegAddr = RegAddr(0x19);
+}
+
+#[test]
+fn lock_bus_holds_shared_bus() {
+    let mut sim = SimBus::new();
+    sim.attach(ADDRESS_LOW, SimMpu6050::still());
+    let shared = ThreadSafeI2c::new(sim);
+    let mut dev = I2cWithAddr::new(RetryingI2c::new(shared.clone(), policy(3)), ADDRESS_LOW);
+
+    shared.lock().inject_failures(ADDRESS_LOW, 1);
+    let (before, other) = dev.i2c_device().lock_bus(|bus| {
+        let mut other = I2cWithAddr::new(shared.clone(), ADDRESS_LOW);
+        let handle = std::thread::spawn(move || other.write_register(Counter(0xff)).unwrap());
+        std::thread::sleep(Duration::from_millis(20));
+        let mut buf = [0];
+        bus.write_read(ADDRESS_LOW.0, &[Counter::ADDR.0], &mut buf)
+            .unwrap();
+        (buf[0], handle)
+    });
+    other.join().unwrap();
+
+    // The other thread waited for the lock.
+    assert_eq!(before, 0);
+    assert_eq!(dev.read_register::<Counter>().unwrap(), Counter(0xff));
+    // The failure inside the lock was retried.
+    assert_eq!(dev.i2c_device().stats().retries, 1);
+}
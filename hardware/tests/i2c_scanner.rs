@@ -0,0 +1,96 @@
+use hardware::i2c::pca9685::ALL_CALL_ADDRESS;
+use hardware::i2c::scanner::*;
+use hardware::i2c::sim::mpu6050::SimMpu6050;
+use hardware::i2c::sim::pca9685::SimPca9685;
+use hardware::i2c::sim::SimBus;
+use hardware::i2c::I2cAddr;
+use hardware::Device;
+
+mod i2c_mock;
+use i2c_mock::*;
+
+fn bus_with(present: &[u8]) -> MockI2c {
+    let mut mock = MockI2c::default();
+    mock.absent = (0..0x80).filter(|a| !present.contains(a)).collect();
+    mock
+}
+
+#[test]
+fn scan_identifies_devices() {
+    let mut mock = bus_with(&[0x1e, 0x40, 0x68, 0x71]);
+    mock.prepare_data(0x40, &[0x11, 0x04]);
+    mock.prepare_data(0x68, &[0x68]);
+    mock.prepare_data(0x71, &[0x00, 0xff]);
+
+    let found = scan(&mut mock);
+    let expected = vec![
+        Found {
+            address: I2cAddr(0x1e),
+            device: Device::Unknown,
+        },
+        Found {
+            address: I2cAddr(0x40),
+            device: Device::Pca9685,
+        },
+        Found {
+            address: I2cAddr(0x68),
+            device: Device::Mpu6050,
+        },
+        Found {
+            address: I2cAddr(0x71),
+            device: Device::Unknown,
+        },
+    ];
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn scan_reserved_addresses() {
+    let mut mock = bus_with(&[0x00, 0x02, 0x03, 0x77, 0x78]);
+    let found: Vec<_> = scan(&mut mock).iter().map(|f| f.address).collect();
+    assert_eq!(found, vec![FIRST_ADDRESS, LAST_ADDRESS]);
+}
+
+#[test]
+fn scan_mpu6050_with_wrong_identity() {
+    let mut mock = bus_with(&[0x69]);
+    mock.prepare_data(0x69, &[0x72, 0xff, 0xff]);
+    let found = scan(&mut mock);
+    assert_eq!(found[0].device, Device::Unknown);
+}
+
+#[test]
+fn scan_skips_all_call_of_pca9685() {
+    let mut bus = SimBus::new();
+    bus.attach(I2cAddr(0x41), SimPca9685::new());
+    // The board answers LED All Call as a PCA9685 too.
+    bus.attach(ALL_CALL_ADDRESS, SimPca9685::new());
+    bus.attach(I2cAddr(0x68), SimMpu6050::still());
+
+    let found = scan(&mut bus);
+    let expected = vec![
+        Found {
+            address: I2cAddr(0x41),
+            device: Device::Pca9685,
+        },
+        Found {
+            address: I2cAddr(0x68),
+            device: Device::Mpu6050,
+        },
+    ];
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn scan_keeps_0x70_without_pca9685() {
+    let mut mock = bus_with(&[0x70]);
+    mock.prepare_data(0x70, &[0x00, 0xff]);
+    let found = scan(&mut mock);
+    assert_eq!(
+        found,
+        vec![Found {
+            address: ALL_CALL_ADDRESS,
+            device: Device::Unknown,
+        }]
+    );
+}
@@ -0,0 +1,179 @@
+use hardware::i2c::pca9685::frame::*;
+use hardware::i2c::pca9685::PCA9685;
+use hardware::i2c::servo::calibration::*;
+use hardware::i2c::servo::{ServoMotor, SG90_180};
+use hardware::ErrorKind;
+use pwm_pca9685::Channel;
+
+mod i2c_mock;
+use i2c_mock::*;
+
+const ADDR: u8 = 0x40;
+
+fn written(i2c: &ClonableI2c<MockI2c>) -> Vec<u8> {
+    i2c.0.borrow().written[&ADDR].iter().copied().collect()
+}
+
+fn off_bytes(rate: f64) -> [u8; 2] {
+    let v = (4096.0 * rate) as u16;
+    [v as u8, (v >> 8) as u8]
+}
+
+#[test]
+fn servo_by_rate() {
+    let i2c = ClonableI2c::new(MockI2c::default());
+    let mut pwm = PCA9685::new(i2c.clone(), ADDR).unwrap();
+
+    let servo = ServoMotor::new(Channel::C0, 50.0, 0.5, 2.4);
+    servo.set_by_rate(&mut pwm, 1.0).unwrap();
+
+    let [off_l, off_h] = off_bytes(servo.calc_pulse(1.0));
+    let bytes = written(&i2c);
+    assert!(bytes.windows(2).any(|w| w == [0xfe, 121]));
+    assert!(bytes.ends_with(&[0x06, 0, 0, off_l, off_h]));
+}
+
+#[test]
+fn servo_angle() {
+    let i2c = ClonableI2c::new(MockI2c::default());
+    let mut pwm = PCA9685::new(i2c.clone(), ADDR).unwrap();
+
+    let servo = SG90_180::new(Channel::C3, 50.0, 0.5, 2.4);
+    servo.set_angle(&mut pwm, 90.0).unwrap();
+
+    let [off_l, off_h] = off_bytes(servo.calc_pulse_by_angle(90.0));
+    assert!(written(&i2c).ends_with(&[0x12, 0, 0, off_l, off_h]));
+}
+
+#[test]
+fn prescale_is_written_once() {
+    let i2c = ClonableI2c::new(MockI2c::default());
+    let mut pwm = PCA9685::new(i2c.clone(), ADDR).unwrap();
+
+    let servos = [
+        SG90_180::new(Channel::C1, 50.0, 0.5, 2.4),
+        SG90_180::new(Channel::C2, 50.0, 0.5, 2.4),
+    ];
+    for servo in &servos {
+        servo.set_angle(&mut pwm, 0.0).unwrap();
+    }
+
+    let bytes = written(&i2c);
+    let prescales = bytes.windows(2).filter(|w| w == &[0xfe, 121]).count();
+    assert_eq!(prescales, 1);
+}
+
+#[test]
+fn verified_new() {
+    let mut mock = MockI2c::default();
+    mock.prepare_data(ADDR, &[0x11, 0x04, 0x68, 0xe0]);
+    let i2c = ClonableI2c::new(mock);
+
+    PCA9685::new_verified(i2c.clone(), ADDR).unwrap();
+    let err = PCA9685::new_verified(i2c.clone(), ADDR)
+        .err()
+        .expect("MODE2 has reserved bits");
+    assert_eq!(err.kind(), ErrorKind::UnexpectedDevice);
+    assert_eq!(written(&i2c), vec![0x00, 0x01, 0x00, 0x01]);
+}
+
+#[test]
+fn calibrated_servo_angle() {
+    let i2c = ClonableI2c::new(MockI2c::default());
+    let mut pwm = PCA9685::new(i2c.clone(), ADDR).unwrap();
+
+    let calibration = ServoCalibration {
+        trim: 4.5,
+        inverted: true,
+        max_angle: Some(120.0),
+        ..ServoCalibration::default()
+    };
+    let servo = CalibratedServo::new(
+        ServoMotor::new(Channel::C1, 50.0, 0.5, 2.3),
+        180.0,
+        calibration,
+    );
+    servo.set_angle(&mut pwm, 150.0).unwrap();
+
+    // Limited to 120, mirrored to 60 and trimmed to 64.5 degrees.
+    let width = 0.5 + 1.8 * 64.5 / 180.0;
+    assert_eq!(servo.calc_width_by_angle(150.0), width);
+    let [off_l, off_h] = off_bytes(servo.servo().calc_pulse_by_width(width));
+    assert!(written(&i2c).ends_with(&[0x0a, 0, 0, off_l, off_h]));
+}
+
+#[test]
+fn frame_in_one_burst() {
+    let i2c = ClonableI2c::new(MockI2c::default());
+    let mut pwm = PCA9685::new(i2c.clone(), ADDR).unwrap();
+    pwm.set_one_duty_cycle(Channel::C0, 0.05).unwrap();
+
+    let mut frame = PwmFrame::new(Stagger::None);
+    frame.set(Channel::C1, 0.1).set(Channel::C15, 0.125);
+    pwm.write_frame(&frame).unwrap();
+
+    let mut expected = vec![0x06];
+    for (i, off) in [(0, 204_u16), (1, 409), (15, 512)] {
+        expected.resize(1 + 4 * (i + 1), 0);
+        expected[4 * i + 3] = off as u8;
+        expected[4 * i + 4] = (off >> 8) as u8;
+    }
+    // Channel 0 is kept, and the others are off.
+    assert!(written(&i2c).ends_with(&expected));
+    assert_eq!(expected.len(), 65);
+}
+
+#[test]
+fn frame_of_same_counts() {
+    let i2c = ClonableI2c::new(MockI2c::default());
+    let mut pwm = PCA9685::new(i2c.clone(), ADDR).unwrap();
+
+    let mut frame = PwmFrame::new(Stagger::None);
+    frame.set(Channel::All, 0.075);
+    pwm.write_frame(&frame).unwrap();
+    assert!(written(&i2c).ends_with(&[0xfa, 0, 0, 0x33, 0x01]));
+
+    // Only channel 3 changes.
+    let mut frame = PwmFrame::new(Stagger::None);
+    frame.set(Channel::C3, 0.1);
+    pwm.write_frame(&frame).unwrap();
+    let bytes = written(&i2c);
+    assert_eq!(bytes[bytes.len() - 65], 0x06);
+    assert!(bytes.ends_with(&[0, 0, 0x33, 0x01]));
+}
+
+#[test]
+fn together_needs_one_prescale() {
+    let i2c = ClonableI2c::new(MockI2c::default());
+    let mut pwm = PCA9685::new(i2c.clone(), ADDR).unwrap();
+
+    let servos = [
+        ServoMotor::new(Channel::C0, 50.0, 0.5, 2.4),
+        ServoMotor::new(Channel::C1, 60.0, 0.5, 2.4),
+    ];
+    let rates: Vec<_> = servos.iter().map(|s| (s, s.calc_pulse(0.5))).collect();
+    let err = pwm
+        .set_duty_cycle_together(&rates, Stagger::None)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(!i2c.0.borrow().written.contains_key(&ADDR));
+
+    pwm.set_duty_cycle_together(&rates[..1], Stagger::None)
+        .unwrap();
+    assert!(written(&i2c).windows(2).any(|w| w == [0xfe, 121]));
+}
+
+#[test]
+fn duty_cycle_needs_one_prescale() {
+    let i2c = ClonableI2c::new(MockI2c::default());
+    let mut pwm = PCA9685::new(i2c.clone(), ADDR).unwrap();
+
+    let slow = ServoMotor::new(Channel::C0, 50.0, 0.5, 2.4);
+    let fast = ServoMotor::new(Channel::C1, 100.0, 0.5, 2.4);
+    let err = pwm
+        .set_duty_cycle(&[(&slow, slow.calc_pulse(0.5)), (&fast, fast.calc_pulse(0.5))])
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(!i2c.0.borrow().written.contains_key(&ADDR));
+    assert_eq!(pwm.prescale(), None);
+}
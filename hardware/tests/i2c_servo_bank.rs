@@ -0,0 +1,192 @@
+use hardware::i2c::pca9685::frame::Stagger;
+use hardware::i2c::pca9685::PCA9685;
+use hardware::i2c::servo::bank::*;
+use hardware::i2c::servo::calibration::*;
+use hardware::i2c::servo::spec::*;
+use hardware::i2c::servo::ServoMotor;
+use hardware::i2c::sim::pca9685::*;
+use hardware::i2c::sim::SimBus;
+use hardware::i2c::*;
+use hardware::ErrorKind;
+use pwm_pca9685::Channel;
+use std::convert::TryFrom;
+
+const LEFT: I2cAddr = I2cAddr(0x40);
+const RIGHT: I2cAddr = I2cAddr(0x41);
+
+type Bus = ThreadSafeI2c<SimBus>;
+
+/// Two boards on the main bus and one on the second bus.
+fn setup() -> ([Bus; 2], ServoBank<Bus>) {
+    let mut main = SimBus::new();
+    main.attach(LEFT, SimPca9685::new());
+    main.attach(RIGHT, SimPca9685::new());
+    let mut second = SimBus::new();
+    second.attach(LEFT, SimPca9685::new());
+    let buses = [ThreadSafeI2c::new(main), ThreadSafeI2c::new(second)];
+
+    let mut bank = ServoBank::new(Stagger::Spread);
+    for (bus, address) in [(&buses[0], LEFT), (&buses[0], RIGHT), (&buses[1], LEFT)] {
+        bank.add_board(PCA9685::new_verified(bus.clone(), address.0).unwrap());
+    }
+    bank.enable().unwrap();
+    (buses, bank)
+}
+
+fn output(bus: &Bus, address: I2cAddr, channel: Channel) -> PwmOutput {
+    let bus = bus.lock();
+    let sim: &SimPca9685 = bus.device(address).unwrap();
+    sim.output(channel)
+}
+
+fn channel(i: usize) -> Channel {
+    Channel::try_from(i as u8).unwrap()
+}
+
+#[test]
+fn servos_over_boards() {
+    let (buses, mut bank) = setup();
+    // 18 servos: 16 on the first board and 2 on the second.
+    let servos: Vec<_> = (0..18)
+        .map(|i| ServoMotor::from_spec(channel(i % 16), MG996R))
+        .collect();
+    for (i, servo) in servos.iter().enumerate() {
+        bank.attach(ServoId(i as u16), i / 16, servo).unwrap();
+    }
+    assert_eq!(
+        bank.port(ServoId(17)),
+        Some(ServoPort {
+            board: 1,
+            channel: Channel::C1
+        })
+    );
+
+    let rates: Vec<_> = servos
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (ServoId(i as u16), s.calc_pulse(i as f64 / 17.0)))
+        .collect();
+    bank.set_duty_cycle(&rates).unwrap();
+
+    let first = output(&buses[0], LEFT, Channel::C15);
+    assert_eq!(first.on, 3840);
+    assert!((first.pulse_width_us - (500.0 + 2000.0 * 15.0 / 17.0)).abs() < 20.0);
+    let last = output(&buses[0], RIGHT, Channel::C1);
+    assert_eq!(last.on, 256);
+    assert!((last.pulse_width_us - 2500.0).abs() < 20.0);
+    assert_eq!(output(&buses[0], RIGHT, Channel::C2).pulse_width_us, 0.0);
+}
+
+#[test]
+fn prescale_of_each_board() {
+    let (buses, mut bank) = setup();
+    let slow = ServoMotor::new(Channel::C0, 50.0, 0.5, 2.4);
+    let fast = ServoMotor::new(Channel::C0, 100.0, 0.5, 2.4);
+    bank.attach(ServoId(0), 0, &slow).unwrap();
+    bank.attach(ServoId(1), 2, &fast).unwrap();
+
+    bank.set_duty_cycle(&[
+        (ServoId(0), slow.calc_pulse(0.0)),
+        (ServoId(1), fast.calc_pulse(0.0)),
+    ])
+    .unwrap();
+    assert!((output(&buses[0], LEFT, Channel::C0).frequency - 50.0).abs() < 1.0);
+    assert!((output(&buses[1], LEFT, Channel::C0).frequency - 100.0).abs() < 1.0);
+    assert!((output(&buses[1], LEFT, Channel::C0).pulse_width_us - 500.0).abs() < 10.0);
+}
+
+#[test]
+fn invalid_attach() {
+    let (_, mut bank) = setup();
+    let servo = ServoMotor::new(Channel::C3, 50.0, 0.5, 2.4);
+    bank.attach(ServoId(7), 1, &servo).unwrap();
+
+    let other = ServoMotor::new(Channel::C4, 50.0, 0.5, 2.4);
+    let fast = ServoMotor::new(Channel::C5, 100.0, 0.5, 2.4);
+    let all = ServoMotor::new(Channel::All, 50.0, 0.5, 2.4);
+    for (id, servo) in [(7, &other), (8, &servo), (8, &fast), (8, &all)] {
+        let err = bank.attach(ServoId(id), 1, servo).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert_eq!(err.address(), RIGHT);
+    }
+
+    // The channel and the prescale are free again.
+    assert_eq!(bank.detach(ServoId(7)).map(|p| p.board), Some(1));
+    bank.attach(ServoId(8), 1, &fast).unwrap();
+    let moved = ServoMotor::new(Channel::C3, 100.0, 0.5, 2.4);
+    bank.attach(ServoId(9), 1, &moved).unwrap();
+    assert_eq!(bank.ids().collect::<Vec<_>>(), vec![ServoId(8), ServoId(9)]);
+}
+
+fn profile(name: &str, channel: u8, board: usize) -> ServoProfile {
+    ServoProfile {
+        name: name.to_string(),
+        channel,
+        board,
+        frequency: 50.0,
+        min_width: 0.5,
+        max_width: 2.5,
+        travel: 180.0,
+        calibration: ServoCalibration::default(),
+    }
+}
+
+#[test]
+fn profiles_to_boards() {
+    let (buses, mut bank) = setup();
+    let profiles = ServoProfiles {
+        servos: vec![profile("coxa", 0, 0), profile("femur", 0, 2)],
+    };
+    profiles.validate().unwrap();
+    let servos = bank.attach_profiles(&profiles).unwrap();
+
+    let rates: Vec<_> = servos
+        .iter()
+        .map(|(id, s)| (*id, s.calc_pulse_by_angle(90.0)))
+        .collect();
+    bank.set_duty_cycle(&rates).unwrap();
+    assert!((output(&buses[1], LEFT, Channel::C0).pulse_width_us - 1500.0).abs() < 20.0);
+
+    let profiles = ServoProfiles {
+        servos: vec![profile("tibia", 0, 3)],
+    };
+    let err = bank.attach_profiles(&profiles).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn failed_profiles_are_not_attached() {
+    let (_, mut bank) = setup();
+    let fast = ServoProfile {
+        frequency: 100.0,
+        ..profile("tibia", 1, 0)
+    };
+    for failing in [profile("tibia", 0, 9), profile("tibia", 0, 0), fast.clone()] {
+        let profiles = ServoProfiles {
+            servos: vec![profile("coxa", 0, 0), profile("femur", 0, 2), failing],
+        };
+        let err = bank.attach_profiles(&profiles).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(bank.ids().count(), 0);
+    }
+
+    // The channels and the prescales are free again.
+    let profiles = ServoProfiles {
+        servos: vec![fast, profile("femur", 0, 2)],
+    };
+    assert_eq!(bank.attach_profiles(&profiles).unwrap().len(), 2);
+}
+
+#[test]
+fn unknown_id() {
+    let (buses, mut bank) = setup();
+    let servo = ServoMotor::new(Channel::C0, 50.0, 0.5, 2.4);
+    bank.attach(ServoId(0), 0, &servo).unwrap();
+
+    let err = bank
+        .set_duty_cycle(&[(ServoId(0), servo.calc_pulse(1.0)), (ServoId(1), 0.1)])
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    // Nothing is written for the known one either.
+    assert_eq!(output(&buses[0], LEFT, Channel::C0).pulse_width_us, 0.0);
+}
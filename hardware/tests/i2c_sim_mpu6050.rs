@@ -0,0 +1,138 @@
+use hardware::i2c::mpu6050::fifo::*;
+use hardware::i2c::mpu6050::interrupt::*;
+use hardware::i2c::mpu6050::raw_data::*;
+use hardware::i2c::mpu6050::{ADDRESS_LOW, MPU6050};
+use hardware::i2c::sim::mpu6050::*;
+use hardware::i2c::sim::SimBus;
+use hardware::i2c::*;
+use hardware::ErrorKind;
+use std::time::Duration;
+
+struct NoDelay;
+
+impl embedded_hal::blocking::delay::DelayMs<u8> for NoDelay {
+    fn delay_ms(&mut self, _: u8) {}
+}
+
+fn setup(sim: SimMpu6050) -> (ThreadSafeI2c<SimBus>, MPU6050<ThreadSafeI2c<SimBus>>) {
+    let mut bus = SimBus::new();
+    bus.attach(ADDRESS_LOW, sim);
+    let bus = ThreadSafeI2c::new(bus);
+    let mut mpu = MPU6050::new_verified(I2cWithAddr::new(bus.clone(), ADDRESS_LOW)).unwrap();
+    mpu.normal_setup(&mut NoDelay).unwrap();
+    (bus, mpu)
+}
+
+fn advance(bus: &ThreadSafeI2c<SimBus>, millis: u64) {
+    bus.lock().advance(Duration::from_millis(millis));
+}
+
+#[test]
+fn measures_the_profile() {
+    let (bus, mut mpu) = setup(SimMpu6050::new(|t: Duration| Motion {
+        accel: [GRAVITY, 0.0, 0.0],
+        gyro: [
+            0.0,
+            0.0,
+            if t < Duration::from_millis(100) {
+                0.0
+            } else {
+                50.0
+            },
+        ],
+        temp: 30.0,
+    }));
+    advance(&bus, 500);
+
+    let raw = mpu.get_infos().unwrap();
+    assert_eq!(
+        raw.accel,
+        AccelData {
+            x: 16375,
+            y: 0,
+            z: 0
+        }
+    );
+    // 50 deg/s at ±2000 deg/s.
+    assert_eq!(raw.gyro, GyroData { x: 0, y: 0, z: 819 });
+    let temp: f64 = raw.temp.celsius().into();
+    assert!((temp - 30.0).abs() < 0.01);
+}
+
+#[test]
+fn honors_full_scale() {
+    let (bus, mut mpu) = setup(SimMpu6050::still());
+    mpu.set_accel_full_scale(AccelFullScale::G8).unwrap();
+    advance(&bus, 1);
+    assert_eq!(mpu.get_infos().unwrap().accel.z, 4094);
+
+    let m = mpu.get_measurement::<f64>().unwrap();
+    let z: f64 = m.accel.z().into();
+    assert!((z - GRAVITY).abs() < 0.01);
+}
+
+#[test]
+fn asleep_until_woken() {
+    let mut bus = SimBus::new();
+    bus.attach(ADDRESS_LOW, SimMpu6050::still());
+    bus.advance(Duration::from_millis(10));
+
+    let mut mpu = MPU6050::new(I2cWithAddr::new(bus, ADDRESS_LOW)).unwrap();
+    assert_eq!(mpu.get_infos().unwrap().accel.z, 0);
+}
+
+#[test]
+fn fifo_frames_at_sample_rate() {
+    let (bus, mut mpu) = setup(SimMpu6050::still());
+    // 8kHz / (1 + 7)
+    mpu.set_sample_rate_divider(7.into()).unwrap();
+    mpu.enable_fifo(FifoSensors::all()).unwrap();
+    advance(&bus, 10);
+
+    let frames = mpu.read_fifo().unwrap();
+    assert_eq!(frames.len(), 10);
+    assert!(frames.iter().all(|f| f.accel.unwrap().z == 16375));
+    assert!(mpu.read_fifo().unwrap().is_empty());
+}
+
+#[test]
+fn fifo_overflow() {
+    let (bus, mut mpu) = setup(SimMpu6050::still());
+    mpu.enable_interrupts(Interrupts {
+        fifo_overflow: true,
+        ..Interrupts::default()
+    })
+    .unwrap();
+    mpu.enable_fifo(FifoSensors::all()).unwrap();
+    advance(&bus, 1000);
+
+    assert!(mpu.get_interrupt_status().unwrap().fifo_overflow);
+    let err = mpu.read_fifo().expect_err("Must overflow");
+    assert_eq!(err.kind(), ErrorKind::FifoOverflow);
+
+    advance(&bus, 1);
+    assert!(!mpu.read_fifo().unwrap().is_empty());
+}
+
+#[test]
+fn data_ready_status_is_cleared_by_reading() {
+    let (bus, mut mpu) = setup(SimMpu6050::still());
+    mpu.enable_interrupts(Interrupts::data_ready()).unwrap();
+    assert!(!mpu.get_interrupt_status().unwrap().data_ready);
+
+    advance(&bus, 1);
+    assert!(mpu.get_interrupt_status().unwrap().data_ready);
+    assert!(!mpu.get_interrupt_status().unwrap().data_ready);
+}
+
+#[test]
+fn reset_restores_power_on_values() {
+    let (bus, mut mpu) = setup(SimMpu6050::still());
+    mpu.set_accel_full_scale(AccelFullScale::G16).unwrap();
+    mpu.reset(&mut NoDelay).unwrap();
+
+    let bus = bus.lock();
+    let sim: &SimMpu6050 = bus.device(ADDRESS_LOW).unwrap();
+    assert_eq!(sim.register(RegAddr(0x1c)), 0);
+    assert_eq!(sim.register(RegAddr(0x6b)), 0x40);
+}
@@ -0,0 +1,167 @@
+use hardware::i2c::pca9685::frame::*;
+use hardware::i2c::pca9685::PCA9685;
+use hardware::i2c::servo::spec::*;
+use hardware::i2c::servo::{ServoMotor, SG90_180};
+use hardware::i2c::sim::pca9685::*;
+use hardware::i2c::sim::SimBus;
+use hardware::i2c::*;
+use hardware::ErrorKind;
+use pwm_pca9685::Channel;
+
+const ADDR: I2cAddr = I2cAddr(0x40);
+
+fn setup() -> (ThreadSafeI2c<SimBus>, PCA9685<ThreadSafeI2c<SimBus>>) {
+    let mut bus = SimBus::new();
+    bus.attach(ADDR, SimPca9685::new());
+    let bus = ThreadSafeI2c::new(bus);
+    let pwm = PCA9685::new_verified(bus.clone(), ADDR.0).unwrap();
+    (bus, pwm)
+}
+
+fn output(bus: &ThreadSafeI2c<SimBus>, channel: Channel) -> PwmOutput {
+    let bus = bus.lock();
+    let sim: &SimPca9685 = bus.device(ADDR).unwrap();
+    sim.output(channel)
+}
+
+/// Within 2 counts of the 12 bit counter:
+/// the counts are truncated, and so is PRE_SCALE, which makes the frequency a little higher.
+fn assert_pulse(out: PwmOutput, expected_us: f64) {
+    let count_us = 1_000_000.0 / out.frequency / 4096.0;
+    assert!(
+        (out.pulse_width_us - expected_us).abs() <= 2.0 * count_us,
+        "{:?} is not {}us",
+        out,
+        expected_us
+    );
+}
+
+#[test]
+fn servo_pulse() {
+    let (bus, mut pwm) = setup();
+    let servo = ServoMotor::new(Channel::C0, 50.0, 0.5, 2.4);
+    servo.set_by_rate(&mut pwm, 1.0).unwrap();
+
+    // The oscillator is stopped until enabled.
+    assert_eq!(output(&bus, Channel::C0).pulse_width_us, 0.0);
+    pwm.enable().unwrap();
+
+    let out = output(&bus, Channel::C0);
+    assert!((out.frequency - 50.0).abs() < 0.5);
+    assert_pulse(out, 2400.0);
+
+    servo.set_by_rate(&mut pwm, 0.0).unwrap();
+    assert_pulse(output(&bus, Channel::C0), 500.0);
+}
+
+#[test]
+fn sg90_angles() {
+    let (bus, mut pwm) = setup();
+    pwm.enable().unwrap();
+    let servos = [
+        SG90_180::new(Channel::C3, 50.0, 0.5, 2.4),
+        SG90_180::new(Channel::C15, 50.0, 0.5, 2.4),
+    ];
+    servos[0].set_angle(&mut pwm, 90.0).unwrap();
+    servos[1].set_angle(&mut pwm, 180.0).unwrap();
+
+    assert_pulse(output(&bus, Channel::C3), 1450.0);
+    assert_pulse(output(&bus, Channel::C15), 2400.0);
+    assert_eq!(output(&bus, Channel::C4).pulse_width_us, 0.0);
+}
+
+#[test]
+fn prescale_is_kept_while_running() {
+    let (bus, mut pwm) = setup();
+    pwm.enable().unwrap();
+    pwm.set_prescale(121).unwrap();
+    pwm.set_prescale(60).unwrap();
+
+    let bus = bus.lock();
+    let sim: &SimPca9685 = bus.device(ADDR).unwrap();
+    assert_eq!(sim.prescale(), 60);
+    assert!(!sim.is_sleeping());
+}
+
+#[test]
+fn servo_specs() {
+    let (bus, mut pwm) = setup();
+    pwm.enable().unwrap();
+    let leg = ServoMotor::from_spec(Channel::C0, DS3218_270);
+    let wheel = ServoMotor::from_spec(Channel::C1, FS90R);
+
+    leg.set_angle(&mut pwm, 270.0).unwrap();
+    assert_pulse(output(&bus, Channel::C0), 2500.0);
+    leg.set_angle(&mut pwm, 67.5).unwrap();
+    assert_pulse(output(&bus, Channel::C0), 1000.0);
+
+    wheel.set_speed(&mut pwm, 0.0).unwrap();
+    assert_pulse(output(&bus, Channel::C1), 1500.0);
+    wheel.set_speed(&mut pwm, -0.5).unwrap();
+    assert_pulse(output(&bus, Channel::C1), 1100.0);
+
+    let err = wheel.set_angle(&mut pwm, 90.0).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = leg.set_speed(&mut pwm, 1.0).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = ServoMotor::new(Channel::C2, 50.0, 0.5, 2.4)
+        .set_angle(&mut pwm, 90.0)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn leg_moves_together() {
+    let (bus, mut pwm) = setup();
+    pwm.enable().unwrap();
+    let leg = [
+        ServoMotor::from_spec(Channel::C0, MG996R),
+        ServoMotor::from_spec(Channel::C1, MG996R),
+        ServoMotor::from_spec(Channel::C2, MG996R),
+    ];
+    let rates: Vec<_> = leg
+        .iter()
+        .zip([0.0, 0.5, 1.0])
+        .map(|(s, v)| (s, s.calc_pulse(v)))
+        .collect();
+    pwm.set_duty_cycle_together(&rates, Stagger::Spread)
+        .unwrap();
+
+    for (channel, on, width) in [
+        (Channel::C0, 0, 500.0),
+        (Channel::C1, 256, 1500.0),
+        (Channel::C2, 512, 2500.0),
+    ] {
+        let out = output(&bus, channel);
+        assert_eq!(out.on, on);
+        assert_pulse(out, width);
+    }
+    assert_eq!(output(&bus, Channel::C3).pulse_width_us, 0.0);
+}
+
+#[test]
+fn servos_share_the_frequency() {
+    let (bus, mut pwm) = setup();
+    pwm.enable().unwrap();
+    let err = pwm.set_pulse_width(Channel::C0, 1500.0).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    let slow = ServoMotor::new(Channel::C0, 50.0, 0.5, 2.4);
+    let fast = ServoMotor::new(Channel::C1, 100.0, 0.5, 2.4);
+    slow.set_by_width(&mut pwm, 1.0).unwrap();
+    // The board keeps the frequency of the first one, and the width is kept in microseconds.
+    fast.set_by_rate(&mut pwm, 1.0).unwrap();
+    assert!((pwm.frequency().unwrap() - 50.0).abs() < 1.0);
+    assert_pulse(output(&bus, Channel::C0), 1000.0);
+    assert_pulse(output(&bus, Channel::C1), 2400.0);
+    assert!((output(&bus, Channel::C1).frequency - 50.0).abs() < 1.0);
+
+    // The running channels keep their widths at the new frequency.
+    let frequency = pwm.set_frequency(100.0).unwrap();
+    assert!((frequency - 100.0).abs() < 1.0);
+    assert!((output(&bus, Channel::C0).frequency - 100.0).abs() < 1.0);
+    assert_pulse(output(&bus, Channel::C0), 1000.0);
+    assert_pulse(output(&bus, Channel::C1), 2400.0);
+    fast.set_by_width(&mut pwm, 2.0).unwrap();
+    assert_pulse(output(&bus, Channel::C1), 2000.0);
+}
@@ -67,3 +67,31 @@ fn write_registers_addresses() {
         assert_eq!(chunks, expected);
     }
 }
+
+#[test]
+fn modify_register_holds_lock() {
+    let mut mock = MockI2c::default();
+    let initial: Vec<u8> = (0..50).collect();
+    mock.prepare_data(3, &initial);
+    let safe_i2c = ThreadSafeI2c::new(mock);
+
+    let mut handles = vec![];
+    for _ in 0..50 {
+        let mut i2c = I2cWithAddr::new(safe_i2c.clone(), 3.into());
+        handles.push(std::thread::spawn(move || {
+            sleep_rand();
+            i2c.modify_register(|r: &mut MockRegisterA| r.0 |= 0x80)
+                .unwrap();
+        }));
+    }
+    for h in handles {
+        h.join().unwrap();
+    }
+
+    // Every write follows its own read without another access in between.
+    let written = safe_i2c.lock().written.clone();
+    let vec: Vec<_> = written[&3].iter().copied().collect();
+    let chunks: Vec<_> = vec.chunks(3).map(|c| [c[0], c[1], c[2]]).collect();
+    let expected: Vec<_> = initial.iter().map(|v| [0x01, 0x01, v | 0x80]).collect();
+    assert_eq!(chunks, expected);
+}
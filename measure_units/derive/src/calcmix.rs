@@ -91,8 +91,8 @@ fn impl_calcmix(
         where
             #gs
         {
-            fn unit_name() -> std::lazy::Lazy<String> {
-                std::lazy::Lazy::new(|| #unit_name)
+            fn unit_name() -> std::cell::LazyCell<String> {
+                std::cell::LazyCell::new(|| #unit_name)
             }
         }
 
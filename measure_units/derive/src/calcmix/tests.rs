@@ -24,8 +24,8 @@ fn with_single() {
         impl CalcMix<f64> for Km
         where
         {
-            fn unit_name() -> std::lazy::Lazy<String> {
-                std::lazy::Lazy::new(|| "km".to_string())
+            fn unit_name() -> std::cell::LazyCell<String> {
+                std::cell::LazyCell::new(|| "km".to_string())
             }
         }
 
@@ -137,8 +137,8 @@ fn with_generics() {
         impl<V> CalcMix<V> for Meter<V>
         where
         {
-            fn unit_name() -> std::lazy::Lazy<String> {
-                std::lazy::Lazy::new(|| "m".to_string())
+            fn unit_name() -> std::cell::LazyCell<String> {
+                std::cell::LazyCell::new(|| "m".to_string())
             }
         }
 
@@ -252,8 +252,8 @@ fn with_generics_bound() {
         impl<V: FloatConst> CalcMix<V> for Meter<V>
         where
         {
-            fn unit_name() -> std::lazy::Lazy<String> {
-                std::lazy::Lazy::new(|| "m".to_string())
+            fn unit_name() -> std::cell::LazyCell<String> {
+                std::cell::LazyCell::new(|| "m".to_string())
             }
         }
 
@@ -356,8 +356,8 @@ fn with_generics_into() {
         impl<V> CalcMix<V> for Meter<V>
         where
         {
-            fn unit_name() -> std::lazy::Lazy<String> {
-                std::lazy::Lazy::new(|| "m".to_string())
+            fn unit_name() -> std::cell::LazyCell<String> {
+                std::cell::LazyCell::new(|| "m".to_string())
             }
         }
 
@@ -462,8 +462,8 @@ fn with_mix() {
             A: CalcMix<V>,
             B: CalcMix<V>,
         {
-            fn unit_name() -> std::lazy::Lazy<String> {
-                std::lazy::Lazy::new(|| format!("{}/{}", *A::unit_name(), *B::unit_name()))
+            fn unit_name() -> std::cell::LazyCell<String> {
+                std::cell::LazyCell::new(|| format!("{}/{}", *A::unit_name(), *B::unit_name()))
             }
         }
 
@@ -567,8 +567,8 @@ fn with_mix_conc1() {
         where
             A: CalcMix<V>,
         {
-            fn unit_name() -> std::lazy::Lazy<String> {
-                std::lazy::Lazy::new(|| format!("{}!", *A::unit_name()))
+            fn unit_name() -> std::cell::LazyCell<String> {
+                std::cell::LazyCell::new(|| format!("{}!", *A::unit_name()))
             }
         }
 
@@ -673,8 +673,8 @@ fn with_mix_conc2() {
             A: CalcMix<V>,
             B: CalcMix<V>,
         {
-            fn unit_name() -> std::lazy::Lazy<String> {
-                std::lazy::Lazy::new(|| format!("{}${}", *A::unit_name(), *B::unit_name()))
+            fn unit_name() -> std::cell::LazyCell<String> {
+                std::cell::LazyCell::new(|| format!("{}${}", *A::unit_name(), *B::unit_name()))
             }
         }
 
@@ -779,8 +779,8 @@ fn with_mix_conc2_bound() {
             A: CalcMix<V>,
             B: CalcMix<V>,
         {
-            fn unit_name() -> std::lazy::Lazy<String> {
-                std::lazy::Lazy::new(|| format!("{}${}", *A::unit_name(), *B::unit_name()))
+            fn unit_name() -> std::cell::LazyCell<String> {
+                std::cell::LazyCell::new(|| format!("{}${}", *A::unit_name(), *B::unit_name()))
             }
         }
 
@@ -1,6 +1,6 @@
 use measure_units_derive::*;
 
-use std::lazy::Lazy;
+use std::cell::LazyCell as Lazy;
 use std::marker::PhantomData;
 use std::ops::{Add, Div, Mul, Sub};
 
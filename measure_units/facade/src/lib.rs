@@ -1,4 +1,4 @@
-#![feature(once_cell)]
+#![allow(stable_features)]
 
 mod calc_mix;
 mod traits;
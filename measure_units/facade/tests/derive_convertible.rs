@@ -1,4 +1,4 @@
-#![feature(once_cell)]
+#![allow(stable_features)]
 
 use measure_units::*;
 use num_traits::*;